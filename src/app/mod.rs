@@ -0,0 +1,507 @@
+use crossterm::event::KeyCode;
+use ratatui::Frame;
+
+use crate::audio_test;
+use crate::benchmark;
+use crate::capacity_test;
+use crate::gamepad_test;
+use crate::gpu_test::{self, TestMode};
+use crate::inspect;
+use crate::jobs;
+use crate::keyboard_test;
+use crate::logging;
+use crate::menu;
+use crate::nvidia_drivers;
+use crate::photo_exporter;
+use crate::preflight;
+use crate::report;
+use crate::session;
+use crate::smart::{self, self_test::SelfTestKind};
+use crate::stability_test;
+use crate::stress_test;
+use crate::surface_scan;
+use crate::wipe;
+use crate::workflow;
+
+/// Every screen the TUI can show. Screens are kept on a stack so that
+/// Esc/Backspace always returns to whatever was shown before.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Screen {
+    MainMenu,
+    DiskSelect,
+    SmartOutput,
+    SmartBatch,
+    SurfaceScan,
+    Benchmark,
+    Inspection,
+    CapacityConfirm,
+    CapacityTest,
+    WipeConfirm,
+    Wipe,
+    GpuTest,
+    StressTest,
+    DriverSelect,
+    DriverInstall,
+    PhotoExport,
+    KeyboardTest,
+    GamepadTest,
+    AudioTest,
+    SessionSummary,
+    Preflight,
+    Jobs,
+    LogViewer,
+    WorkflowSelect,
+    Workflow,
+}
+
+/// What a screen wants the app to do after handling a key.
+pub enum Transition {
+    Stay,
+    Push(Screen),
+    Pop,
+    /// Pops the current screen and pushes another in its place.
+    Replace(Screen),
+    Quit,
+}
+
+impl Screen {
+    pub fn draw(&self, f: &mut Frame) {
+        match self {
+            Screen::MainMenu => menu::draw_main_menu(f),
+            Screen::DiskSelect => menu::disk::draw_disk_selection(f),
+            Screen::SmartOutput => menu::disk::draw_smart_output(f),
+            Screen::SmartBatch => smart::batch::draw_batch(f),
+            Screen::SurfaceScan => surface_scan::draw_surface_scan(f),
+            Screen::Benchmark => benchmark::draw_benchmark(f),
+            Screen::Inspection => inspect::draw_inspection(f),
+            Screen::CapacityConfirm => capacity_test::draw_capacity_confirm(f),
+            Screen::CapacityTest => capacity_test::draw_capacity_test(f),
+            Screen::WipeConfirm => wipe::draw_wipe_confirm(f),
+            Screen::Wipe => wipe::draw_wipe(f),
+            Screen::GpuTest => gpu_test::draw_gpu_testing(f),
+            Screen::StressTest => stress_test::draw_stress_test_popup(f),
+            Screen::DriverSelect => menu::gpu::draw_driver_menu(f),
+            Screen::DriverInstall => nvidia_drivers::draw_driver_install_output(f),
+            Screen::PhotoExport => photo_exporter::draw_photo_export_progress(f),
+            Screen::KeyboardTest => keyboard_test::draw_keyboard_test(f),
+            Screen::GamepadTest => gamepad_test::draw_gamepad_test(f),
+            Screen::AudioTest => audio_test::draw_audio_test(f),
+            Screen::SessionSummary => session::draw_session_summary(f),
+            Screen::Preflight => preflight::draw_preflight(f),
+            Screen::Jobs => jobs::draw_jobs_panel(f),
+            Screen::LogViewer => logging::draw_log_viewer(f),
+            Screen::WorkflowSelect => workflow::draw_workflow_select(f),
+            Screen::Workflow => workflow::draw_workflow(f),
+        }
+    }
+
+    /// Screens that need every key, including Esc and Backspace, while a test runs.
+    pub fn captures_input(&self) -> bool {
+        match self {
+            Screen::KeyboardTest => keyboard_test::is_capturing(),
+            Screen::WipeConfirm | Screen::CapacityConfirm => true,
+            _ => false,
+        }
+    }
+
+    pub fn handle_key(&self, key: KeyCode) -> Transition {
+        match self {
+            Screen::WipeConfirm => return wipe::confirm_key(key),
+            Screen::CapacityConfirm => return capacity_test::confirm_key(key),
+            _ => {}
+        }
+        if self.captures_input() {
+            keyboard_test::record_key(key);
+            return Transition::Stay;
+        }
+
+        match (self, key) {
+            (Screen::MainMenu, KeyCode::Char('q')) => menu::quit(),
+            (_, KeyCode::Char('q')) => Transition::Pop,
+            (Screen::Jobs, KeyCode::Char('j')) => Transition::Stay,
+            (_, KeyCode::Char('j')) => {
+                jobs::enter_jobs_panel();
+                Transition::Push(Screen::Jobs)
+            }
+
+            (Screen::MainMenu, KeyCode::Up) => {
+                menu::decrement_menu();
+                Transition::Stay
+            }
+            (Screen::MainMenu, KeyCode::Down) => {
+                menu::increment_menu();
+                Transition::Stay
+            }
+            (Screen::MainMenu, KeyCode::Enter) => menu::handle_main_menu_enter(),
+
+            (Screen::DiskSelect, KeyCode::Up) => {
+                menu::disk::decrement_disk_selection();
+                Transition::Stay
+            }
+            (Screen::DiskSelect, KeyCode::Down) => {
+                menu::disk::increment_disk_selection();
+                Transition::Stay
+            }
+            (Screen::DiskSelect, KeyCode::Enter) => {
+                menu::disk::run_selected_disk_smart();
+                Transition::Push(Screen::SmartOutput)
+            }
+            (Screen::DiskSelect, KeyCode::Char('w')) if menu::disk::wipe_selected_disk() => {
+                Transition::Push(Screen::WipeConfirm)
+            }
+            (Screen::DiskSelect, KeyCode::Char('s')) if menu::disk::scan_selected_disk() => {
+                Transition::Push(Screen::SurfaceScan)
+            }
+            (Screen::DiskSelect, KeyCode::Char('b')) if menu::disk::benchmark_selected_disk() => {
+                Transition::Push(Screen::Benchmark)
+            }
+            (Screen::DiskSelect, KeyCode::Char('i')) if menu::disk::inspect_selected_disk() => {
+                Transition::Push(Screen::Inspection)
+            }
+            (Screen::DiskSelect, KeyCode::Char('f')) if menu::disk::capacity_test_selected_disk() => {
+                Transition::Push(Screen::CapacityConfirm)
+            }
+            (Screen::DiskSelect, KeyCode::Char('v')) => {
+                menu::disk::toggle_virtual_drives();
+                Transition::Stay
+            }
+            (Screen::DiskSelect, KeyCode::Char('a')) => {
+                smart::batch::start_batch_from_list();
+                Transition::Push(Screen::SmartBatch)
+            }
+
+            (Screen::SmartBatch, KeyCode::Up) => {
+                smart::batch::previous_row();
+                Transition::Stay
+            }
+            (Screen::SmartBatch, KeyCode::Down) => {
+                smart::batch::next_row();
+                Transition::Stay
+            }
+            (Screen::SmartBatch, KeyCode::Enter) if smart::batch::open_selected_row() => {
+                Transition::Push(Screen::SmartOutput)
+            }
+            (Screen::SmartBatch, KeyCode::Char('e')) => {
+                smart::batch::export_batch();
+                Transition::Stay
+            }
+            (Screen::SmartBatch, KeyCode::Char('c')) => {
+                smart::batch::cancel_batch();
+                Transition::Stay
+            }
+
+            (Screen::SurfaceScan, KeyCode::Char('c')) => {
+                surface_scan::cancel_scan();
+                Transition::Stay
+            }
+
+            (Screen::Benchmark, KeyCode::Char('c')) => {
+                benchmark::cancel_benchmark();
+                Transition::Stay
+            }
+
+            (Screen::Inspection, KeyCode::Char('c')) => {
+                inspect::cancel_inspection();
+                Transition::Stay
+            }
+
+            (Screen::CapacityTest, KeyCode::Char('c')) => {
+                capacity_test::cancel_capacity_test();
+                Transition::Stay
+            }
+
+            (Screen::Wipe, KeyCode::Char('c')) => {
+                wipe::cancel_wipe();
+                Transition::Stay
+            }
+
+            (Screen::SmartOutput, KeyCode::Up) => {
+                smart::scroll_up();
+                Transition::Stay
+            }
+            (Screen::SmartOutput, KeyCode::Down) => {
+                smart::scroll_down();
+                Transition::Stay
+            }
+            (Screen::SmartOutput, KeyCode::Char('s')) => {
+                smart::self_test::run_self_test(SelfTestKind::Short);
+                Transition::Stay
+            }
+            (Screen::SmartOutput, KeyCode::Char('e')) => {
+                smart::self_test::run_self_test(SelfTestKind::Extended);
+                Transition::Stay
+            }
+            (Screen::SmartOutput, KeyCode::Char('v')) => {
+                smart::self_test::run_self_test(SelfTestKind::Conveyance);
+                Transition::Stay
+            }
+            (Screen::SmartOutput, KeyCode::Char('c')) => {
+                smart::self_test::cancel_self_test();
+                Transition::Stay
+            }
+
+            (Screen::GpuTest, KeyCode::Char('s')) => {
+                gpu_test::run_test(TestMode::Stress);
+                Transition::Push(Screen::StressTest)
+            }
+            (Screen::GpuTest, KeyCode::Char('t')) => {
+                gpu_test::run_test(TestMode::Stability);
+                Transition::Stay
+            }
+            (Screen::GpuTest, KeyCode::Char('c')) => {
+                stability_test::cancel_stability_test();
+                Transition::Stay
+            }
+
+            (Screen::StressTest, KeyCode::Char('c')) => {
+                stress_test::cancel_stress_test();
+                Transition::Stay
+            }
+
+            (Screen::PhotoExport, KeyCode::Char('c')) => {
+                photo_exporter::cancel_export();
+                Transition::Stay
+            }
+
+            (Screen::DriverInstall, KeyCode::Char('c')) => {
+                nvidia_drivers::cancel_install();
+                Transition::Stay
+            }
+
+            (Screen::DriverSelect, KeyCode::Up) => {
+                menu::gpu::decrement_driver_selection_menu();
+                Transition::Stay
+            }
+            (Screen::DriverSelect, KeyCode::Down) => {
+                menu::gpu::increment_driver_selection_menu();
+                Transition::Stay
+            }
+            (Screen::DriverSelect, KeyCode::Enter) => {
+                menu::gpu::install_selected_driver_menu();
+                Transition::Push(Screen::DriverInstall)
+            }
+
+            (Screen::KeyboardTest, KeyCode::Up) => {
+                keyboard_test::decrement_device_selection();
+                Transition::Stay
+            }
+            (Screen::KeyboardTest, KeyCode::Down) => {
+                keyboard_test::increment_device_selection();
+                Transition::Stay
+            }
+            (Screen::KeyboardTest, KeyCode::Enter) => {
+                keyboard_test::run_keyboard_test();
+                Transition::Stay
+            }
+
+            (Screen::GamepadTest, KeyCode::Up) => {
+                gamepad_test::decrement_device_selection();
+                Transition::Stay
+            }
+            (Screen::GamepadTest, KeyCode::Down) => {
+                gamepad_test::increment_device_selection();
+                Transition::Stay
+            }
+            (Screen::GamepadTest, KeyCode::Enter) => {
+                gamepad_test::run_gamepad_test();
+                Transition::Stay
+            }
+
+            (Screen::AudioTest, KeyCode::Up) => {
+                audio_test::decrement_device_selection();
+                Transition::Stay
+            }
+            (Screen::AudioTest, KeyCode::Down) => {
+                audio_test::increment_device_selection();
+                Transition::Stay
+            }
+            (Screen::AudioTest, KeyCode::Enter) => {
+                audio_test::run_audio_test();
+                Transition::Stay
+            }
+            (Screen::AudioTest, KeyCode::Char('c')) => {
+                audio_test::cancel_audio_test();
+                Transition::Stay
+            }
+            (Screen::AudioTest, KeyCode::Char('y')) => {
+                audio_test::confirm_audio(true);
+                Transition::Stay
+            }
+            (Screen::AudioTest, KeyCode::Char('n')) => {
+                audio_test::confirm_audio(false);
+                Transition::Stay
+            }
+
+            (Screen::SessionSummary, KeyCode::Char('r')) => {
+                report::generate_current_report();
+                Transition::Stay
+            }
+            (Screen::SessionSummary, KeyCode::Char('n')) => {
+                session::reset();
+                Transition::Stay
+            }
+
+            (Screen::Preflight, KeyCode::Char('r')) => {
+                preflight::run_preflight();
+                Transition::Stay
+            }
+
+            (Screen::Jobs, KeyCode::Up) => {
+                jobs::previous_job();
+                Transition::Stay
+            }
+            (Screen::Jobs, KeyCode::Down) => {
+                jobs::next_job();
+                Transition::Stay
+            }
+            (Screen::Jobs, KeyCode::Char('c')) => {
+                jobs::cancel_selected_job();
+                Transition::Stay
+            }
+
+            (Screen::LogViewer, KeyCode::Up) => {
+                logging::scroll_up();
+                Transition::Stay
+            }
+            (Screen::LogViewer, KeyCode::Down) => {
+                logging::scroll_down();
+                Transition::Stay
+            }
+            (Screen::LogViewer, KeyCode::End) => {
+                logging::follow();
+                Transition::Stay
+            }
+            (Screen::LogViewer, KeyCode::Char('m')) => {
+                logging::cycle_module_filter();
+                Transition::Stay
+            }
+            (Screen::LogViewer, KeyCode::Char('l')) => {
+                logging::cycle_level_filter();
+                Transition::Stay
+            }
+
+            (Screen::WorkflowSelect, KeyCode::Up) => {
+                workflow::previous_profile();
+                Transition::Stay
+            }
+            (Screen::WorkflowSelect, KeyCode::Down) => {
+                workflow::next_profile();
+                Transition::Stay
+            }
+            (Screen::WorkflowSelect, KeyCode::Enter) => workflow::start_selected_profile(),
+
+            (Screen::Workflow, KeyCode::Enter) => workflow::confirm(),
+            (Screen::Workflow, KeyCode::Char('r')) => workflow::retry(),
+            (Screen::Workflow, KeyCode::Char('s')) => {
+                workflow::skip();
+                Transition::Stay
+            }
+            (Screen::Workflow, KeyCode::Char('a')) => {
+                workflow::abort();
+                Transition::Stay
+            }
+
+            _ => Transition::Stay,
+        }
+    }
+
+    /// Resets the module state behind a screen when it is popped.
+    fn on_exit(&self) {
+        match self {
+            Screen::SmartOutput => smart::exit_smart_output(),
+            Screen::WipeConfirm => wipe::exit_wipe_confirm(),
+            Screen::CapacityConfirm => capacity_test::exit_capacity_confirm(),
+            Screen::GpuTest => gpu_test::clear_test_mode(),
+            Screen::DriverSelect => menu::gpu::exit_driver_selection_menu(),
+            Screen::PhotoExport => photo_exporter::exit_export(),
+            Screen::KeyboardTest => keyboard_test::exit_keyboard_test(),
+            Screen::GamepadTest => gamepad_test::exit_gamepad_test(),
+            Screen::AudioTest => audio_test::exit_audio_test(),
+            Screen::MainMenu
+            | Screen::DiskSelect
+            | Screen::SmartBatch
+            | Screen::SurfaceScan
+            | Screen::Benchmark
+            | Screen::Inspection
+            | Screen::CapacityTest
+            | Screen::Wipe
+            | Screen::StressTest
+            | Screen::DriverInstall
+            | Screen::SessionSummary
+            | Screen::Preflight
+            | Screen::Jobs
+            | Screen::LogViewer
+            | Screen::WorkflowSelect
+            | Screen::Workflow => {}
+        }
+    }
+}
+
+pub struct App {
+    stack: Vec<Screen>,
+    running: bool,
+}
+
+impl App {
+    pub fn new() -> Self {
+        Self {
+            stack: vec![Screen::MainMenu],
+            running: true,
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running
+    }
+
+    pub fn current(&self) -> Screen {
+        *self.stack.last().unwrap_or(&Screen::MainMenu)
+    }
+
+    pub fn draw(&self, f: &mut Frame) {
+        self.current().draw(f);
+    }
+
+    /// Runs once per UI loop iteration, between key events.
+    pub fn tick(&mut self) {
+        workflow::tick();
+        if self.current() == Screen::DiskSelect {
+            smart::watch_drives();
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyCode) {
+        let transition = match key {
+            _ if self.current().captures_input() => self.current().handle_key(key),
+            KeyCode::Esc | KeyCode::Backspace => Transition::Pop,
+            _ => self.current().handle_key(key),
+        };
+        self.apply(transition);
+    }
+
+    pub fn push(&mut self, screen: Screen) {
+        self.stack.push(screen);
+    }
+
+    /// Goes back one level. The main menu is never popped.
+    pub fn pop(&mut self) {
+        if self.stack.len() > 1 {
+            if let Some(screen) = self.stack.pop() {
+                screen.on_exit();
+            }
+        }
+    }
+
+    fn apply(&mut self, transition: Transition) {
+        match transition {
+            Transition::Stay => {}
+            Transition::Push(screen) => self.push(screen),
+            Transition::Pop => self.pop(),
+            Transition::Replace(screen) => {
+                self.pop();
+                self.push(screen);
+            }
+            Transition::Quit => self.running = false,
+        }
+    }
+}
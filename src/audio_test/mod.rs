@@ -5,26 +5,62 @@ use ratatui::{
     widgets::{Block, Borders, Gauge, List, ListItem, ListState, Paragraph},
     Frame,
 };
-use rodio::{Decoder, OutputStream, Sink};
+use rodio::{Decoder, OutputStream, Sink, Source};
 use std::fs::File;
 use std::io::BufReader;
+use std::path::Path;
 use std::sync::Mutex;
 use std::thread;
 use std::time::{Duration, Instant};
 
+use chrono::{DateTime, Local};
 use once_cell::sync::Lazy;
 
-static DEVICES: Lazy<Mutex<Vec<String>>> = Lazy::new(|| Mutex::new(vec![]));
-static DEVICE_INDEX: Lazy<Mutex<usize>> = Lazy::new(|| Mutex::new(0));
-static MESSAGE: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new(String::new()));
-pub static AUDIO_TEST_ACTIVE: Lazy<Mutex<bool>> = Lazy::new(|| Mutex::new(false));
-pub static AUDIO_TEST_PROGRESS: Lazy<Mutex<u16>> = Lazy::new(|| Mutex::new(0));
+use crate::config;
+use crate::jobs::{self, JobId};
+use crate::session::{self, TestResult, TestStatus};
+
+static AUDIO_JOB: Lazy<Mutex<Option<JobId>>> = Lazy::new(|| Mutex::new(None));
 pub static AUDIO_TEST_MESSAGE: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new(String::new()));
 pub static AUDIO_DEVICES: Lazy<Mutex<Vec<String>>> = Lazy::new(|| Mutex::new(vec![]));
 pub static AUDIO_DEVICE_INDEX: Lazy<Mutex<usize>> = Lazy::new(|| Mutex::new(0));
+static PLAYBACK_STARTED: Lazy<Mutex<Option<DateTime<Local>>>> = Lazy::new(|| Mutex::new(None));
+static AWAITING_CONFIRMATION: Lazy<Mutex<bool>> = Lazy::new(|| Mutex::new(false));
+
+/// Plays a sound file on the default output device and blocks until it ends.
+pub fn play_file(path: &Path) -> Result<(), String> {
+    play_file_until(path, |_| {}, || false)
+}
 
-pub fn check_audio_test_active() -> bool {
-    *AUDIO_TEST_ACTIVE.lock().unwrap()
+/// Like `play_file`, but reports percent played (when the length is known)
+/// and stops early once `should_stop` returns true.
+pub fn play_file_until(
+    path: &Path,
+    mut on_progress: impl FnMut(u16),
+    should_stop: impl Fn() -> bool,
+) -> Result<(), String> {
+    let (_stream, stream_handle) =
+        OutputStream::try_default().map_err(|e| format!("No audio output device: {}", e))?;
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let source = Decoder::new(BufReader::new(file))
+        .map_err(|e| format!("Failed to decode {}: {}", path.display(), e))?;
+    let total = source.total_duration();
+    let sink = Sink::try_new(&stream_handle).map_err(|e| format!("Failed to open sink: {}", e))?;
+    sink.append(source);
+
+    let start = Instant::now();
+    while !sink.empty() {
+        if should_stop() {
+            sink.stop();
+            return Err("cancelled by operator".to_string());
+        }
+        if let Some(total) = total.filter(|t| !t.is_zero()) {
+            on_progress((start.elapsed().as_secs_f64() / total.as_secs_f64() * 100.0).min(100.0) as u16);
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+    on_progress(100);
+    Ok(())
 }
 
 pub fn enter_audio_test() {
@@ -33,33 +69,97 @@ pub fn enter_audio_test() {
         "Headphones".to_string(),
     ];
     *AUDIO_DEVICE_INDEX.lock().unwrap() = 0;
-    *AUDIO_TEST_ACTIVE.lock().unwrap() = true;
-    *AUDIO_TEST_PROGRESS.lock().unwrap() = 0;
-    *AUDIO_TEST_MESSAGE.lock().unwrap() = "Playing audio test...".to_string();
+    play_test_sound();
+}
+
+fn play_test_sound() {
+    let mut job = AUDIO_JOB.lock().unwrap();
+    if job.is_some_and(jobs::is_running) {
+        return;
+    }
 
-    thread::spawn(|| {
-        if let Ok((_stream, stream_handle)) = OutputStream::try_default() {
-            if let Ok(file) = File::open("assets/audio/test.wav") {
-                if let Ok(source) = Decoder::new(BufReader::new(file)) {
-                    let sink = Sink::try_new(&stream_handle).unwrap();
-                    sink.append(source);
-                    sink.sleep_until_end();
-                }
+    *AUDIO_TEST_MESSAGE.lock().unwrap() = "Playing audio test...".to_string();
+    *AWAITING_CONFIRMATION.lock().unwrap() = false;
+    *PLAYBACK_STARTED.lock().unwrap() = Some(Local::now());
+
+    *job = Some(jobs::spawn("Audio playback", |ctx| async move {
+        let path = config::get().audio.test_file.clone();
+        log::info!("Playing {}", path.display());
+
+        let progress = ctx.clone();
+        let token = ctx.token();
+        let played = tokio::task::spawn_blocking(move || {
+            play_file_until(&path, |p| progress.progress(p), || token.is_cancelled())
+        })
+        .await
+        .unwrap_or_else(|e| Err(e.to_string()));
+
+        match played {
+            Ok(()) => {
+                *AWAITING_CONFIRMATION.lock().unwrap() = true;
+                *AUDIO_TEST_MESSAGE.lock().unwrap() =
+                    "Audio test completed. Did you hear the test sound? (y/n)".to_string();
+                Ok("Waiting for the operator to confirm".to_string())
+            }
+            Err(e) => {
+                log::error!("Audio test could not play: {}", e);
+                session::record(
+                    TestResult::new("Audio", &selected_device())
+                        .started(PLAYBACK_STARTED.lock().unwrap().unwrap_or_else(Local::now))
+                        .raw_output(e.clone())
+                        .finish(),
+                );
+                *AUDIO_TEST_MESSAGE.lock().unwrap() = format!("Audio test failed: {}", e);
+                Err(e)
             }
         }
+    }));
+}
 
-        for i in 0..=100 {
-            *AUDIO_TEST_PROGRESS.lock().unwrap() = i;
-            thread::sleep(Duration::from_millis(20));
-        }
+pub fn cancel_audio_test() {
+    if let Some(id) = *AUDIO_JOB.lock().unwrap() {
+        jobs::cancel(id);
+    }
+}
+
+fn selected_device() -> String {
+    let devices = AUDIO_DEVICES.lock().unwrap();
+    devices
+        .get(*AUDIO_DEVICE_INDEX.lock().unwrap())
+        .cloned()
+        .unwrap_or_else(|| "default".to_string())
+}
+
+pub fn is_awaiting_confirmation() -> bool {
+    *AWAITING_CONFIRMATION.lock().unwrap()
+}
 
-        *AUDIO_TEST_MESSAGE.lock().unwrap() = "Audio test completed.".to_string();
-    });
+/// Records the operator's answer to "did you hear it?".
+pub fn confirm_audio(heard: bool) {
+    if !is_awaiting_confirmation() {
+        return;
+    }
+    *AWAITING_CONFIRMATION.lock().unwrap() = false;
+
+    let started = PLAYBACK_STARTED.lock().unwrap().take().unwrap_or_else(Local::now);
+    log::info!("Operator {} the test sound", if heard { "heard" } else { "did not hear" });
+    session::record(
+        TestResult::new("Audio", &selected_device())
+            .started(started)
+            .status(if heard { TestStatus::Pass } else { TestStatus::Fail })
+            .metric("heard", if heard { "yes" } else { "no" })
+            .finish(),
+    );
+
+    *AUDIO_TEST_MESSAGE.lock().unwrap() = if heard {
+        "Audio confirmed by operator. Test passed.".to_string()
+    } else {
+        "Operator did not hear the test sound. Test failed.".to_string()
+    };
 }
 
 pub fn exit_audio_test() {
-    *AUDIO_TEST_ACTIVE.lock().unwrap() = false;
-    *AUDIO_TEST_PROGRESS.lock().unwrap() = 0;
+    *AWAITING_CONFIRMATION.lock().unwrap() = false;
     *AUDIO_TEST_MESSAGE.lock().unwrap() = String::new();
 }
 
@@ -78,15 +178,9 @@ pub fn decrement_device_selection() {
     }
 }
 
+/// Replays the test sound; device routing is still left to the system default.
 pub fn run_audio_test() {
-    let devices = DEVICES.lock().unwrap();
-    let index = *DEVICE_INDEX.lock().unwrap();
-
-    let fallback = "No device selected.".to_string();
-    let selected = devices.get(index).unwrap_or(&fallback);
-
-    let mut msg = MESSAGE.lock().unwrap();
-    *msg = format!("Starting test on: {}\n(Audio response test in development)", selected);
+    play_test_sound();
 }
 
 pub fn draw_audio_test(f: &mut Frame) {
@@ -99,7 +193,12 @@ pub fn draw_audio_test(f: &mut Frame) {
 
     let devices = AUDIO_DEVICES.lock().unwrap();
     let selected = *AUDIO_DEVICE_INDEX.lock().unwrap();
-    let progress = *AUDIO_TEST_PROGRESS.lock().unwrap();
+    let progress = AUDIO_JOB
+        .lock()
+        .unwrap()
+        .and_then(jobs::get)
+        .and_then(|job| job.progress)
+        .unwrap_or(0);
     let message = AUDIO_TEST_MESSAGE.lock().unwrap();
 
     let mut state = ListState::default();
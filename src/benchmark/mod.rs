@@ -0,0 +1,594 @@
+use chrono::{DateTime, Local};
+use once_cell::sync::Lazy;
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Gauge, Paragraph, Row, Table, Wrap},
+    Frame,
+};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Seek, SeekFrom};
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::{FileExt, OpenOptionsExt};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::command::SystemRunner;
+use crate::config::{self, BenchmarkConfig, ExpectedRange};
+use crate::jobs::{self, cancelled_error, JobId};
+use crate::session::{self, TestResult, TestStatus};
+use crate::smart::{self, SmartData};
+use crate::theme::bordered_block;
+use crate::wipe::overwrite::splitmix64;
+
+/// Direct I/O needs buffers, offsets and lengths aligned to the logical
+/// block size; 4 KiB covers every drive we see.
+const ALIGN: u64 = 4096;
+const SEQUENTIAL_CHUNK: u64 = 1 << 20;
+const RANDOM_SIZE: u64 = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Interface {
+    SataHdd,
+    SataSsd,
+    Nvme,
+}
+
+impl Interface {
+    /// Spinning drives report a rotation rate; SSDs report 0 or nothing.
+    pub fn from_smart(data: &SmartData) -> Self {
+        if data.is_nvme() {
+            Interface::Nvme
+        } else if data.rotation_rate.is_some_and(|rpm| rpm > 0) {
+            Interface::SataHdd
+        } else {
+            Interface::SataSsd
+        }
+    }
+
+    /// The class to grade against, if any. Drives behind a USB bridge or an
+    /// SD card reader run at whatever the adapter allows, so they get none.
+    pub fn detect(data: Option<&SmartData>, transport: Option<&str>) -> Option<Self> {
+        match transport {
+            Some("usb" | "mmc") => None,
+            _ => data.map(Self::from_smart),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Interface::SataHdd => "SATA HDD",
+            Interface::SataSsd => "SATA SSD",
+            Interface::Nvme => "NVMe",
+        }
+    }
+
+    pub fn expected<'a>(&self, config: &'a BenchmarkConfig) -> &'a ExpectedRange {
+        match self {
+            Interface::SataHdd => &config.sata_hdd,
+            Interface::SataSsd => &config.sata_ssd,
+            Interface::Nvme => &config.nvme,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Sequential {
+    /// "start", "middle" or "end" of the disk.
+    pub zone: String,
+    pub offset: u64,
+    pub bytes: u64,
+    pub mb_per_sec: f64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Random {
+    pub reads: usize,
+    pub iops: f64,
+    pub p50_us: u64,
+    pub p90_us: u64,
+    pub p99_us: u64,
+    pub max_us: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BenchmarkReport {
+    pub device: String,
+    pub size_bytes: u64,
+    /// False when the device refused O_DIRECT and the page cache was dropped instead.
+    pub direct_io: bool,
+    pub started_at: DateTime<Local>,
+    pub finished_at: DateTime<Local>,
+    pub sequential: Vec<Sequential>,
+    pub random: Random,
+}
+
+impl BenchmarkReport {
+    pub fn slowest_sequential(&self) -> f64 {
+        self.sequential
+            .iter()
+            .map(|s| s.mb_per_sec)
+            .fold(f64::INFINITY, f64::min)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Below,
+    Within,
+    Above,
+}
+
+/// One measurement against the range expected for the interface.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Check {
+    pub name: String,
+    pub value: f64,
+    pub expected: String,
+    pub verdict: Verdict,
+}
+
+impl Check {
+    /// Latency above its limit counts as below the range: slower than expected.
+    pub fn is_slow(&self) -> bool {
+        self.verdict == Verdict::Below
+    }
+}
+
+fn within(value: f64, (min, max): (f64, f64)) -> Verdict {
+    if value < min {
+        Verdict::Below
+    } else if value > max {
+        Verdict::Above
+    } else {
+        Verdict::Within
+    }
+}
+
+pub fn compare(report: &BenchmarkReport, expected: &ExpectedRange) -> Vec<Check> {
+    let mut checks: Vec<Check> = report
+        .sequential
+        .iter()
+        .map(|s| Check {
+            name: format!("Sequential read ({})", s.zone),
+            value: s.mb_per_sec,
+            expected: format!("{:.0}-{:.0} MB/s", expected.seq_mb_s.0, expected.seq_mb_s.1),
+            verdict: within(s.mb_per_sec, expected.seq_mb_s),
+        })
+        .collect();
+    checks.push(Check {
+        name: "Random 4K read".to_string(),
+        value: report.random.iops,
+        expected: format!("{:.0}-{:.0} IOPS", expected.iops.0, expected.iops.1),
+        verdict: within(report.random.iops, expected.iops),
+    });
+    let p99_ms = report.random.p99_us as f64 / 1000.0;
+    checks.push(Check {
+        name: "99th percentile latency".to_string(),
+        value: p99_ms,
+        expected: format!("under {:.1} ms", expected.p99_ms),
+        verdict: if p99_ms > expected.p99_ms {
+            Verdict::Below
+        } else {
+            Verdict::Within
+        },
+    });
+    checks
+}
+
+/// The `p`th percentile of sorted samples, nearest-rank.
+pub fn percentile(sorted: &[u64], p: f64) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// A buffer whose start is `ALIGN`-aligned, as O_DIRECT requires.
+struct AlignedBuf {
+    data: Vec<u8>,
+    start: usize,
+    len: usize,
+}
+
+impl AlignedBuf {
+    fn new(len: usize) -> Self {
+        let data = vec![0u8; len + ALIGN as usize];
+        let start = data.as_ptr().align_offset(ALIGN as usize);
+        Self { data, start, len }
+    }
+
+    fn as_mut(&mut self) -> &mut [u8] {
+        &mut self.data[self.start..self.start + self.len]
+    }
+}
+
+/// Opens `path` read-only with O_DIRECT, falling back to buffered reads on
+/// filesystems that refuse it.
+pub fn open_direct(path: &str) -> io::Result<(File, bool)> {
+    match OpenOptions::new().read(true).custom_flags(libc::O_DIRECT).open(path) {
+        Ok(file) => Ok((file, true)),
+        Err(e) if e.raw_os_error() == Some(libc::EINVAL) => {
+            log::warn!("{} does not support direct I/O; dropping the page cache instead", path);
+            Ok((File::open(path)?, false))
+        }
+        Err(e) => Err(e),
+    }
+}
+
+fn read_timed(file: &File, buf: &mut [u8], offset: u64, direct: bool) -> io::Result<Duration> {
+    let started = Instant::now();
+    file.read_exact_at(buf, offset)?;
+    let elapsed = started.elapsed();
+    if !direct {
+        unsafe {
+            libc::posix_fadvise(
+                file.as_raw_fd(),
+                offset as libc::off_t,
+                buf.len() as libc::off_t,
+                libc::POSIX_FADV_DONTNEED,
+            );
+        }
+    }
+    Ok(elapsed)
+}
+
+fn align_down(value: u64, to: u64) -> u64 {
+    value / to * to
+}
+
+/// Where each sequential zone starts and how much of it is read.
+pub fn zones(size: u64, sequential_bytes: u64) -> Vec<(&'static str, u64, u64)> {
+    let len = align_down(sequential_bytes.min(size / 3), ALIGN);
+    if len == 0 {
+        return Vec::new();
+    }
+    vec![
+        ("start", 0, len),
+        ("middle", align_down(size / 2 - len / 2, ALIGN), len),
+        ("end", align_down(size - len, ALIGN), len),
+    ]
+}
+
+/// Measures sequential reads at the start, middle and end of the device and
+/// random 4K reads over all of it. `progress` gets a phase and a percentage
+/// and returns false to stop.
+pub fn run(
+    file: &File,
+    device: &str,
+    direct: bool,
+    settings: &BenchmarkConfig,
+    progress: &mut dyn FnMut(&str, u16) -> bool,
+) -> io::Result<BenchmarkReport> {
+    let started_at = Local::now();
+    let size = {
+        let mut handle = file;
+        handle.seek(SeekFrom::End(0))?
+    };
+    let zones = zones(size, settings.sequential_mib << 20);
+    if zones.is_empty() || size < RANDOM_SIZE {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is too small to benchmark", device),
+        ));
+    }
+
+    let mut buf = AlignedBuf::new(SEQUENTIAL_CHUNK as usize);
+    let mut sequential = Vec::new();
+    for (i, (zone, offset, len)) in zones.iter().enumerate() {
+        let phase = format!("Sequential read ({})", zone);
+        let mut elapsed = Duration::ZERO;
+        let mut done = 0;
+        while done < *len {
+            let n = (len - done).min(SEQUENTIAL_CHUNK) as usize;
+            elapsed += read_timed(file, &mut buf.as_mut()[..n], offset + done, direct)?;
+            done += n as u64;
+            let percent = (i as u64 * 100 + done * 100 / len) * 75 / 300;
+            if !progress(&phase, percent as u16) {
+                return Err(cancelled_error());
+            }
+        }
+        sequential.push(Sequential {
+            zone: zone.to_string(),
+            offset: *offset,
+            bytes: *len,
+            mb_per_sec: *len as f64 / 1e6 / elapsed.as_secs_f64().max(1e-9),
+        });
+    }
+
+    let mut buf = AlignedBuf::new(RANDOM_SIZE as usize);
+    let sectors = size / RANDOM_SIZE;
+    let limit = Duration::from_secs(settings.random_secs);
+    let seed = started_at.timestamp_nanos_opt().unwrap_or_default() as u64;
+    let mut latencies = Vec::with_capacity(settings.random_reads);
+    let random_started = Instant::now();
+    for i in 0..settings.random_reads {
+        if random_started.elapsed() >= limit {
+            break;
+        }
+        let offset = splitmix64(seed ^ i as u64) % sectors * RANDOM_SIZE;
+        let latency = read_timed(file, buf.as_mut(), offset, direct)?;
+        latencies.push(latency.as_micros() as u64);
+        let percent = 75 + (i + 1) * 25 / settings.random_reads.max(1);
+        if !progress("Random 4K read", percent as u16) {
+            return Err(cancelled_error());
+        }
+    }
+    latencies.sort_unstable();
+    let random = Random {
+        reads: latencies.len(),
+        iops: latencies.len() as f64 / random_started.elapsed().as_secs_f64().max(1e-9),
+        p50_us: percentile(&latencies, 50.0),
+        p90_us: percentile(&latencies, 90.0),
+        p99_us: percentile(&latencies, 99.0),
+        max_us: latencies.last().copied().unwrap_or_default(),
+    };
+
+    Ok(BenchmarkReport {
+        device: device.to_string(),
+        size_bytes: size,
+        direct_io: direct,
+        started_at,
+        finished_at: Local::now(),
+        sequential,
+        random,
+    })
+}
+
+/// The benchmark as metrics on the drive's SMART result.
+pub fn metrics(report: &BenchmarkReport, interface: Option<Interface>, checks: &[Check]) -> Vec<(String, String)> {
+    let mut metrics: Vec<(String, String)> = report
+        .sequential
+        .iter()
+        .map(|s| (format!("bench_seq_{}_mb_s", s.zone), format!("{:.1}", s.mb_per_sec)))
+        .collect();
+    metrics.extend([
+        ("bench_random_iops".to_string(), format!("{:.0}", report.random.iops)),
+        (
+            "bench_latency_p50_ms".to_string(),
+            format!("{:.2}", report.random.p50_us as f64 / 1000.0),
+        ),
+        (
+            "bench_latency_p90_ms".to_string(),
+            format!("{:.2}", report.random.p90_us as f64 / 1000.0),
+        ),
+        (
+            "bench_latency_p99_ms".to_string(),
+            format!("{:.2}", report.random.p99_us as f64 / 1000.0),
+        ),
+        ("bench_direct_io".to_string(), report.direct_io.to_string()),
+    ]);
+    let slow: Vec<&str> = checks.iter().filter(|c| c.is_slow()).map(|c| c.name.as_str()).collect();
+    let verdict = match interface {
+        None => "no expected range for this device".to_string(),
+        Some(interface) if slow.is_empty() => format!("within {} range", interface.label()),
+        Some(interface) => format!("below {} range: {}", interface.label(), slow.join(", ")),
+    };
+    metrics.push(("bench_verdict".to_string(), verdict));
+    metrics
+}
+
+/// Adds the benchmark to the drive's SMART result, which drops to a warning
+/// when the drive is slower than its interface should be. Devices without
+/// SMART get a result of their own.
+pub fn attach_to_smart(
+    report: &BenchmarkReport,
+    smart: Option<&SmartData>,
+    interface: Option<Interface>,
+    checks: &[Check],
+) {
+    let metrics = metrics(report, interface, checks);
+    let slow = checks.iter().any(Check::is_slow);
+    let attach = |result: &mut TestResult| {
+        result.metrics.extend(metrics.iter().cloned());
+        if slow && result.status == TestStatus::Pass {
+            result.status = TestStatus::Warn;
+        }
+    };
+
+    if session::update("SMART", &report.device, attach) {
+        return;
+    }
+    let mut result = match smart {
+        Some(data) => smart::smart_result(&report.device, data),
+        None => TestResult::new("Benchmark", &report.device)
+            .started(report.started_at)
+            .status(TestStatus::Pass)
+            .finish(),
+    };
+    attach(&mut result);
+    session::record(result);
+}
+
+#[derive(Debug, Clone, Default)]
+struct BenchmarkState {
+    device: String,
+    interface: Option<Interface>,
+    phase: String,
+    percent: u16,
+    report: Option<BenchmarkReport>,
+    checks: Vec<Check>,
+    error: Option<String>,
+}
+
+static BENCHMARK_JOB: Lazy<Mutex<Option<JobId>>> = Lazy::new(|| Mutex::new(None));
+static BENCHMARK_STATE: Lazy<Mutex<BenchmarkState>> = Lazy::new(|| Mutex::new(BenchmarkState::default()));
+
+pub fn is_running() -> bool {
+    BENCHMARK_JOB.lock().unwrap().is_some_and(jobs::is_running)
+}
+
+pub fn start_benchmark(device: &str) {
+    if is_running() {
+        log::warn!("A benchmark is already running");
+        return;
+    }
+    *BENCHMARK_STATE.lock().unwrap() = BenchmarkState {
+        device: device.to_string(),
+        phase: "Reading SMART".to_string(),
+        ..BenchmarkState::default()
+    };
+    let settings = config::get().benchmark.clone();
+    let transport = smart::DISK_LIST
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|d| d.path == device)
+        .and_then(|d| d.transport.clone());
+    let device = device.to_string();
+
+    let id = jobs::spawn(&format!("Benchmark {}", device), move |ctx| async move {
+        let token = ctx.token();
+        let job = ctx.clone();
+        let name = device.clone();
+        let outcome = tokio::task::spawn_blocking(move || {
+            let smart = smart::read_smart(&SystemRunner, &name)
+                .map_err(|e| log::warn!("No SMART data for {}: {}", name, e))
+                .ok();
+            let interface = Interface::detect(smart.as_ref(), transport.as_deref());
+            BENCHMARK_STATE.lock().unwrap().interface = interface;
+
+            let (file, direct) = open_direct(&name).map_err(|e| format!("Cannot open {}: {}", name, e))?;
+            let report = run(&file, &name, direct, &settings, &mut |phase, percent| {
+                job.progress(percent);
+                let mut state = BENCHMARK_STATE.lock().unwrap();
+                state.phase = phase.to_string();
+                state.percent = percent;
+                !token.is_cancelled()
+            })
+            .map_err(|e| e.to_string())?;
+            let checks = interface
+                .map(|i| compare(&report, i.expected(&settings)))
+                .unwrap_or_default();
+            attach_to_smart(&report, smart.as_ref(), interface, &checks);
+            Ok::<_, String>((report, checks))
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+
+        let mut state = BENCHMARK_STATE.lock().unwrap();
+        match outcome {
+            Ok((report, checks)) => {
+                let summary = format!(
+                    "{:.0} MB/s slowest sequential, {:.0} IOPS random 4K",
+                    report.slowest_sequential(),
+                    report.random.iops
+                );
+                log::info!("Benchmark of {}: {}", device, summary);
+                state.report = Some(report);
+                state.checks = checks;
+                Ok(summary)
+            }
+            Err(e) => {
+                log::warn!("Benchmark of {} failed: {}", device, e);
+                state.error = Some(e.clone());
+                Err(e)
+            }
+        }
+    });
+    *BENCHMARK_JOB.lock().unwrap() = Some(id);
+}
+
+pub fn cancel_benchmark() {
+    if let Some(id) = *BENCHMARK_JOB.lock().unwrap() {
+        jobs::cancel(id);
+    }
+}
+
+fn ms(us: u64) -> String {
+    format!("{:.2} ms", us as f64 / 1000.0)
+}
+
+pub fn draw_benchmark(f: &mut Frame) {
+    let state = BENCHMARK_STATE.lock().unwrap().clone();
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Min(8),
+            Constraint::Length(4),
+        ])
+        .split(f.area());
+
+    let interface = state.interface.map(|i| i.label()).unwrap_or("unknown interface");
+    let header =
+        Paragraph::new(format!("{} ({}), read only", state.device, interface)).block(bordered_block("Drive Benchmark"));
+    f.render_widget(header, chunks[0]);
+
+    let gauge = Gauge::default()
+        .block(bordered_block("Progress"))
+        .gauge_style(Style::default().fg(Color::Green).bg(Color::Black))
+        .percent(state.percent.min(100))
+        .label(format!("{} ({}%)", state.phase, state.percent));
+    f.render_widget(gauge, chunks[1]);
+
+    let mut rows: Vec<Row> = Vec::new();
+    if let Some(report) = &state.report {
+        for s in &report.sequential {
+            rows.push(Row::new(vec![
+                format!("Sequential read ({})", s.zone),
+                format!("{:.1} MB/s", s.mb_per_sec),
+            ]));
+        }
+        let random = &report.random;
+        rows.push(Row::new(vec![
+            "Random 4K read".to_string(),
+            format!("{:.0} IOPS ({} reads)", random.iops, random.reads),
+        ]));
+        rows.push(Row::new(vec![
+            "Latency p50 / p90 / p99 / max".to_string(),
+            format!(
+                "{} / {} / {} / {}",
+                ms(random.p50_us),
+                ms(random.p90_us),
+                ms(random.p99_us),
+                ms(random.max_us)
+            ),
+        ]));
+        for check in &state.checks {
+            let (text, color) = match check.verdict {
+                Verdict::Below => ("SLOW", Color::Red),
+                Verdict::Within => ("ok", Color::Green),
+                Verdict::Above => ("fast", Color::Cyan),
+            };
+            rows.push(
+                Row::new(vec![
+                    format!("{} expected {}", check.name, check.expected),
+                    text.to_string(),
+                ])
+                .style(Style::default().fg(color)),
+            );
+        }
+    }
+    let table = Table::new(rows, [Constraint::Percentage(60), Constraint::Percentage(40)])
+        .header(Row::new(vec!["Measurement", "Result"]).style(Style::default().add_modifier(Modifier::BOLD)))
+        .block(bordered_block("Results"));
+    f.render_widget(table, chunks[2]);
+
+    let status = match (&state.error, &state.report) {
+        (Some(e), _) => Span::styled(e.clone(), Style::default().fg(Color::Red)),
+        (None, Some(report)) if !report.direct_io => Span::styled(
+            "Direct I/O unavailable; results may include cache effects",
+            Style::default().fg(Color::Yellow),
+        ),
+        (None, Some(_)) => Span::styled(
+            "Done; results added to the drive's SMART result",
+            Style::default().fg(Color::Green),
+        ),
+        (None, None) => Span::raw("Running..."),
+    };
+    let footer = Paragraph::new(vec![Line::from(status), Line::from("c: cancel, Esc: back, j: jobs")])
+        .block(bordered_block("Status"))
+        .wrap(Wrap { trim: true });
+    f.render_widget(footer, chunks[3]);
+}
+
+#[cfg(test)]
+mod tests;
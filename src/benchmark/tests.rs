@@ -0,0 +1,181 @@
+use super::*;
+use crate::command::FixtureRunner;
+use std::fs;
+
+fn data(name: &str) -> SmartData {
+    SmartData::parse(&FixtureRunner::fixture(name)).unwrap()
+}
+
+fn small_settings() -> BenchmarkConfig {
+    BenchmarkConfig {
+        sequential_mib: 1,
+        random_reads: 64,
+        random_secs: 10,
+        ..BenchmarkConfig::default()
+    }
+}
+
+fn report(seq: [f64; 3], iops: f64, p99_us: u64) -> BenchmarkReport {
+    BenchmarkReport {
+        device: "/dev/sdz".to_string(),
+        size_bytes: 1 << 40,
+        direct_io: true,
+        started_at: Local::now(),
+        finished_at: Local::now(),
+        sequential: ["start", "middle", "end"]
+            .iter()
+            .zip(seq)
+            .map(|(zone, mb_per_sec)| Sequential {
+                zone: zone.to_string(),
+                offset: 0,
+                bytes: 1 << 28,
+                mb_per_sec,
+            })
+            .collect(),
+        random: Random {
+            reads: 2000,
+            iops,
+            p50_us: p99_us / 4,
+            p90_us: p99_us / 2,
+            p99_us,
+            max_us: p99_us * 2,
+        },
+    }
+}
+
+#[test]
+fn interface_comes_from_smart() {
+    let mut hdd = data("smartctl_sata_hdd.stdout");
+    assert_eq!(Interface::from_smart(&hdd), Interface::SataHdd);
+    hdd.rotation_rate = Some(0);
+    assert_eq!(Interface::from_smart(&hdd), Interface::SataSsd);
+    assert_eq!(Interface::from_smart(&data("smartctl_nvme.stdout")), Interface::Nvme);
+}
+
+#[test]
+fn usb_and_sd_cards_are_not_graded() {
+    let mut ssd = data("smartctl_sata_hdd.stdout");
+    ssd.rotation_rate = Some(0);
+    assert_eq!(Interface::detect(Some(&ssd), Some("sata")), Some(Interface::SataSsd));
+    assert_eq!(Interface::detect(Some(&ssd), Some("usb")), None);
+    assert_eq!(Interface::detect(None, Some("mmc")), None);
+    assert_eq!(Interface::detect(None, Some("sata")), None);
+}
+
+#[test]
+fn percentiles_use_nearest_rank() {
+    let samples: Vec<u64> = (1..=100).collect();
+    assert_eq!(percentile(&samples, 50.0), 50);
+    assert_eq!(percentile(&samples, 99.0), 99);
+    assert_eq!(percentile(&samples, 100.0), 100);
+    assert_eq!(percentile(&[7], 99.0), 7);
+    assert_eq!(percentile(&[], 50.0), 0);
+}
+
+#[test]
+fn zones_cover_start_middle_and_end() {
+    let size = 100 << 20;
+    let zones = zones(size, 8 << 20);
+    assert_eq!(zones[0], ("start", 0, 8 << 20));
+    assert_eq!(zones[1], ("middle", 46 << 20, 8 << 20));
+    assert_eq!(zones[2], ("end", 92 << 20, 8 << 20));
+    // Small devices read a third each, aligned for direct I/O.
+    let small = super::zones(30_000, 1 << 20);
+    assert!(small.iter().all(|(_, offset, len)| offset % ALIGN == 0 && *len == 8192));
+    assert!(super::zones(4096, 1 << 20).is_empty());
+}
+
+#[test]
+fn benchmark_reads_an_image() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("disk.img");
+    fs::write(&path, vec![0x5a; 6 << 20]).unwrap();
+    let path = path.to_string_lossy().into_owned();
+
+    let (file, direct) = open_direct(&path).unwrap();
+    let mut phases = Vec::new();
+    let report = run(&file, &path, direct, &small_settings(), &mut |phase, percent| {
+        if phases.last().map(|(p, _)| p) != Some(&phase.to_string()) {
+            phases.push((phase.to_string(), percent));
+        }
+        true
+    })
+    .unwrap();
+
+    let zones: Vec<(&str, u64)> = report.sequential.iter().map(|s| (s.zone.as_str(), s.offset)).collect();
+    assert_eq!(zones, vec![("start", 0), ("middle", 5 << 19), ("end", 5 << 20)]);
+    assert!(report
+        .sequential
+        .iter()
+        .all(|s| s.mb_per_sec > 0.0 && s.bytes == 1 << 20));
+    assert_eq!(report.random.reads, 64);
+    assert!(report.random.iops > 0.0);
+    assert!(report.random.p50_us <= report.random.p99_us && report.random.p99_us <= report.random.max_us);
+    let names: Vec<&str> = phases.iter().map(|(p, _)| p.as_str()).collect();
+    assert_eq!(
+        names,
+        vec![
+            "Sequential read (start)",
+            "Sequential read (middle)",
+            "Sequential read (end)",
+            "Random 4K read"
+        ]
+    );
+
+    let stopped = run(&file, &path, direct, &small_settings(), &mut |_, _| false);
+    assert_eq!(stopped.unwrap_err().kind(), io::ErrorKind::Interrupted);
+}
+
+#[test]
+fn results_are_compared_with_the_interface_range() {
+    let config = BenchmarkConfig::default();
+    let healthy = compare(
+        &report([180.0, 150.0, 95.0], 110.0, 30_000),
+        Interface::SataHdd.expected(&config),
+    );
+    assert!(healthy.iter().all(|c| c.verdict == Verdict::Within));
+
+    // An SSD-fast start on a drive that crawls at the end, with long stalls.
+    let checks = compare(
+        &report([700.0, 150.0, 20.0], 80.0, 250_000),
+        Interface::SataHdd.expected(&config),
+    );
+    let verdicts: Vec<Verdict> = checks.iter().map(|c| c.verdict).collect();
+    assert_eq!(
+        verdicts,
+        vec![
+            Verdict::Above,
+            Verdict::Within,
+            Verdict::Below,
+            Verdict::Within,
+            Verdict::Below
+        ]
+    );
+    assert_eq!(checks[2].name, "Sequential read (end)");
+}
+
+#[test]
+fn benchmark_is_attached_to_the_smart_result() {
+    let device = "/dev/bench-test";
+    let hdd = data("smartctl_sata_hdd.stdout");
+    session::record(smart::smart_result(device, &hdd));
+
+    let mut slow = report([150.0, 120.0, 20.0], 100.0, 10_000);
+    slow.device = device.to_string();
+    let checks = compare(&slow, Interface::SataHdd.expected(&BenchmarkConfig::default()));
+    attach_to_smart(&slow, Some(&hdd), Some(Interface::SataHdd), &checks);
+
+    let session = session::current();
+    let results: Vec<&TestResult> = session.results.iter().filter(|r| r.device == device).collect();
+    assert_eq!(results.len(), 1);
+    let smart = results[0];
+    assert_eq!(smart.test, "SMART");
+    assert_eq!(smart.status, TestStatus::Warn);
+    assert_eq!(smart.metrics["bench_seq_end_mb_s"], "20.0");
+    assert_eq!(smart.metrics["bench_latency_p99_ms"], "10.00");
+    assert_eq!(
+        smart.metrics["bench_verdict"],
+        "below SATA HDD range: Sequential read (end)"
+    );
+    assert_eq!(smart.metrics["serial"], "ZFL1ABCD");
+}
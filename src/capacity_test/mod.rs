@@ -0,0 +1,485 @@
+use chrono::{DateTime, Local};
+use crossterm::event::KeyCode;
+use once_cell::sync::Lazy;
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Gauge, Paragraph, Wrap},
+    Frame,
+};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Seek, SeekFrom};
+use std::os::unix::fs::{FileExt, OpenOptionsExt};
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::app::{Screen, Transition};
+use crate::confirm::{Confirmation, Outcome};
+use crate::jobs::{self, cancelled_error, JobId};
+use crate::session::{self, TestResult, TestStatus};
+use crate::smart::{self, format_capacity};
+use crate::theme::{bordered_block, info_box};
+use crate::wipe::overwrite::{self, Pattern};
+
+/// Every sector carries its own offset, so a read shows where the data
+/// really came from.
+pub const SECTOR: u64 = 4096;
+/// Sectors written or read per request.
+const CHUNK_SECTORS: u64 = 256;
+const MAGIC: &[u8; 8] = b"ETTCAP01";
+
+/// Block storage addressed by byte offset. Files and block devices
+/// implement it; tests use fakes that wrap around like counterfeit media.
+pub trait Media {
+    fn size(&mut self) -> io::Result<u64>;
+    fn write_block(&mut self, buf: &[u8], offset: u64) -> io::Result<()>;
+    fn read_block(&mut self, buf: &mut [u8], offset: u64) -> io::Result<()>;
+    /// Pushes writes out to the media and drops them from the page cache.
+    fn sync(&mut self) -> io::Result<()>;
+}
+
+impl Media for File {
+    fn size(&mut self) -> io::Result<u64> {
+        self.seek(SeekFrom::End(0))
+    }
+
+    fn write_block(&mut self, buf: &[u8], offset: u64) -> io::Result<()> {
+        self.write_all_at(buf, offset)
+    }
+
+    fn read_block(&mut self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        self.read_exact_at(buf, offset)
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        overwrite::sync_and_drop_cache(self)
+    }
+}
+
+/// What one sector held when it was read back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SectorState {
+    Good,
+    /// Holds the block written for another offset: the media wrapped around.
+    Overwritten {
+        from: u64,
+    },
+    /// Zeros, garbage or a damaged tag.
+    Corrupted,
+    Unreadable,
+}
+
+/// Fills `buf` with the tagged sector for `offset`.
+pub fn tagged_sector(buf: &mut [u8], offset: u64, seed: u64) {
+    overwrite::fill(buf, offset, Pattern::Random, seed);
+    buf[..8].copy_from_slice(MAGIC);
+    buf[8..16].copy_from_slice(&offset.to_le_bytes());
+    buf[16..24].copy_from_slice(&seed.to_le_bytes());
+}
+
+/// What the sector read back as `got` holds. `want` is scratch space of the
+/// same length, passed in so the read pass allocates it once.
+pub fn classify(got: &[u8], offset: u64, seed: u64, want: &mut [u8]) -> SectorState {
+    tagged_sector(want, offset, seed);
+    if got == want {
+        return SectorState::Good;
+    }
+    let field = |range: std::ops::Range<usize>| u64::from_le_bytes(got[range].try_into().unwrap_or_default());
+    if &got[..8] != MAGIC || field(16..24) != seed {
+        return SectorState::Corrupted;
+    }
+    let from = field(8..16);
+    tagged_sector(want, from, seed);
+    if from != offset && got == want {
+        SectorState::Overwritten { from }
+    } else {
+        SectorState::Corrupted
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CapacityReport {
+    pub device: String,
+    /// What the device says it holds.
+    pub claimed_bytes: u64,
+    /// Bytes from the start that can be trusted: up to the first sector that
+    /// lost its data, and no further than where the media wraps around.
+    pub usable_bytes: u64,
+    /// The shortest distance between a sector and the block it really holds,
+    /// i.e. the offset at which writes start landing on earlier sectors.
+    pub alias_offset: Option<u64>,
+    pub good: u64,
+    pub overwritten: u64,
+    pub corrupted: u64,
+    pub unreadable: u64,
+    pub write_errors: u64,
+    pub started_at: DateTime<Local>,
+    pub finished_at: DateTime<Local>,
+}
+
+impl CapacityReport {
+    /// Every tested sector read back what was written to it.
+    pub fn genuine(&self) -> bool {
+        self.overwritten == 0 && self.corrupted == 0 && self.unreadable == 0 && self.write_errors == 0
+    }
+
+    pub fn summary(&self) -> String {
+        if self.genuine() {
+            return format!("Genuine: all {} verified", format_capacity(self.claimed_bytes));
+        }
+        let mut text = format!(
+            "COUNTERFEIT: claims {}, only {} usable",
+            format_capacity(self.claimed_bytes),
+            format_capacity(self.usable_bytes)
+        );
+        if let Some(offset) = self.alias_offset {
+            text.push_str(&format!(
+                ", wraps around at {} (byte {})",
+                format_capacity(offset),
+                offset
+            ));
+        }
+        if self.write_errors > 0 {
+            text.push_str(&format!(", {} sectors could not be written", self.write_errors));
+        }
+        text
+    }
+}
+
+/// Share of the gauge for writing; reading back gets the rest.
+const WRITE_SHARE: u64 = 50;
+
+/// Fills the whole device with tagged sectors, reads them back and works out
+/// how much of it is real. Destroys all data on the device. `progress` gets
+/// a phase and a percentage and returns false to stop.
+pub fn run(
+    media: &mut dyn Media,
+    device: &str,
+    seed: u64,
+    progress: &mut dyn FnMut(&str, u16) -> bool,
+) -> io::Result<CapacityReport> {
+    let started_at = Local::now();
+    let claimed_bytes = media.size()?;
+    let sectors = claimed_bytes / SECTOR;
+    if sectors == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{} is empty", device),
+        ));
+    }
+    let mut buf = vec![0u8; (CHUNK_SECTORS * SECTOR) as usize];
+    let percent = |done: u64, base: u64| (base + done * WRITE_SHARE / sectors) as u16;
+
+    let mut write_errors = 0;
+    let mut sector = 0;
+    while sector < sectors {
+        let count = (sectors - sector).min(CHUNK_SECTORS);
+        let chunk = &mut buf[..(count * SECTOR) as usize];
+        for (i, block) in chunk.chunks_mut(SECTOR as usize).enumerate() {
+            tagged_sector(block, (sector + i as u64) * SECTOR, seed);
+        }
+        if let Err(e) = media.write_block(chunk, sector * SECTOR) {
+            log::warn!("{}: write failed at byte {}: {}", device, sector * SECTOR, e);
+            write_errors += count;
+        }
+        sector += count;
+        if !progress("Writing tagged blocks", percent(sector, 0)) {
+            return Err(cancelled_error());
+        }
+    }
+    if let Err(e) = media.sync() {
+        // The device would not confirm its writes, so none of them count as done.
+        log::warn!("{}: flushing writes failed: {}", device, e);
+        write_errors = sectors;
+    }
+
+    let mut report = CapacityReport {
+        device: device.to_string(),
+        claimed_bytes,
+        usable_bytes: sectors * SECTOR,
+        alias_offset: None,
+        good: 0,
+        overwritten: 0,
+        corrupted: 0,
+        unreadable: 0,
+        write_errors,
+        started_at,
+        finished_at: started_at,
+    };
+    let mut first_bad = None;
+    let mut want = vec![0u8; SECTOR as usize];
+    let mut sector = 0;
+    while sector < sectors {
+        let count = (sectors - sector).min(CHUNK_SECTORS);
+        let chunk = &mut buf[..(count * SECTOR) as usize];
+        let chunk_read = media.read_block(chunk, sector * SECTOR).is_ok();
+        for i in 0..count {
+            let offset = (sector + i) * SECTOR;
+            let block = &mut chunk[(i * SECTOR) as usize..((i + 1) * SECTOR) as usize];
+            // Retry one sector at a time to find which ones are unreadable.
+            let state = if chunk_read || media.read_block(block, offset).is_ok() {
+                classify(block, offset, seed, &mut want)
+            } else {
+                SectorState::Unreadable
+            };
+            match state {
+                SectorState::Good => report.good += 1,
+                SectorState::Overwritten { from } => {
+                    report.overwritten += 1;
+                    let distance = from.abs_diff(offset);
+                    report.alias_offset = Some(report.alias_offset.map_or(distance, |a| a.min(distance)));
+                }
+                SectorState::Corrupted => report.corrupted += 1,
+                SectorState::Unreadable => report.unreadable += 1,
+            }
+            let lost = matches!(state, SectorState::Corrupted | SectorState::Unreadable);
+            if lost && first_bad.is_none() {
+                first_bad = Some(offset);
+            }
+        }
+        sector += count;
+        if !progress("Reading back", percent(sector, WRITE_SHARE)) {
+            return Err(cancelled_error());
+        }
+    }
+
+    report.usable_bytes = [first_bad, report.alias_offset]
+        .into_iter()
+        .flatten()
+        .fold(report.usable_bytes, u64::min);
+    report.finished_at = Local::now();
+    Ok(report)
+}
+
+pub fn capacity_result(report: &CapacityReport) -> TestResult {
+    let result = TestResult::new("Capacity", &report.device)
+        .started(report.started_at)
+        .status(if report.genuine() {
+            TestStatus::Pass
+        } else {
+            TestStatus::Fail
+        })
+        .metric("claimed_bytes", report.claimed_bytes)
+        .metric("usable_bytes", report.usable_bytes)
+        .metric("good_sectors", report.good)
+        .metric("overwritten_sectors", report.overwritten)
+        .metric("corrupted_sectors", report.corrupted)
+        .metric("unreadable_sectors", report.unreadable)
+        .metric("write_errors", report.write_errors)
+        .raw_output(report.summary());
+    match report.alias_offset {
+        Some(offset) => result.metric("alias_offset", offset).finish(),
+        None => result.finish(),
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct CapacityState {
+    device: String,
+    phase: String,
+    percent: u16,
+    report: Option<CapacityReport>,
+    error: Option<String>,
+}
+
+/// The device waiting for typed confirmation, or why it was refused.
+static CONFIRM: Confirmation<String> = Confirmation::new();
+static CAPACITY_JOB: Lazy<Mutex<Option<JobId>>> = Lazy::new(|| Mutex::new(None));
+static CAPACITY_STATE: Lazy<Mutex<CapacityState>> = Lazy::new(|| Mutex::new(CapacityState::default()));
+
+pub fn is_running() -> bool {
+    CAPACITY_JOB.lock().unwrap().is_some_and(jobs::is_running)
+}
+
+/// The device name the operator must type, e.g. `sdb`.
+fn confirmation(device: &str) -> String {
+    Path::new(device)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| device.to_string())
+}
+
+pub fn enter_capacity_confirm(device: &str) {
+    CONFIRM.open(smart::usage::guard(device).map(|_| device.to_string()));
+}
+
+pub fn exit_capacity_confirm() {
+    CONFIRM.close();
+}
+
+pub fn confirm_key(key: KeyCode) -> Transition {
+    let mismatch = "That does not match. Nothing was written.";
+    let device = match CONFIRM.handle_key(key, |device: &String| confirmation(device), mismatch) {
+        Outcome::Confirmed(device) => device,
+        Outcome::Done(transition) => return transition,
+    };
+    if is_running() {
+        CONFIRM.set_message("Another capacity test is still running.");
+        return Transition::Stay;
+    }
+    if let Err(e) = smart::usage::guard(&device) {
+        CONFIRM.set_message(&e);
+        return Transition::Stay;
+    }
+    start_capacity_test(&device);
+    Transition::Replace(Screen::CapacityTest)
+}
+
+fn start_capacity_test(device: &str) {
+    log::warn!("Capacity test on {}: overwriting the whole device", device);
+    *CAPACITY_STATE.lock().unwrap() = CapacityState {
+        device: device.to_string(),
+        phase: "Starting".to_string(),
+        ..CapacityState::default()
+    };
+    let device = device.to_string();
+
+    let id = jobs::spawn(&format!("Capacity test {}", device), move |ctx| async move {
+        let token = ctx.token();
+        let job = ctx.clone();
+        let name = device.clone();
+        let outcome = tokio::task::spawn_blocking(move || {
+            // O_DSYNC makes each write report its own error; fake media
+            // often reject writes past their real size.
+            let mut file = OpenOptions::new()
+                .read(true)
+                .write(true)
+                .custom_flags(libc::O_DSYNC)
+                .open(&name)
+                .map_err(|e| format!("Cannot open {} for writing: {}", name, e))?;
+            let seed = Local::now().timestamp_nanos_opt().unwrap_or_default() as u64;
+            run(&mut file, &name, seed, &mut |phase, percent| {
+                job.progress(percent);
+                let mut state = CAPACITY_STATE.lock().unwrap();
+                state.phase = phase.to_string();
+                state.percent = percent;
+                !token.is_cancelled()
+            })
+            .map_err(|e| format!("{}: {}", name, e))
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+
+        let mut state = CAPACITY_STATE.lock().unwrap();
+        match outcome {
+            Ok(report) => {
+                let summary = report.summary();
+                log::info!("Capacity test of {}: {}", device, summary);
+                session::record(capacity_result(&report));
+                let genuine = report.genuine();
+                state.report = Some(report);
+                if genuine {
+                    Ok(summary)
+                } else {
+                    Err(summary)
+                }
+            }
+            Err(e) => {
+                log::error!("Capacity test of {} failed: {}", device, e);
+                session::record(
+                    TestResult::new("Capacity", &device)
+                        .status(TestStatus::Fail)
+                        .raw_output(e.clone())
+                        .finish(),
+                );
+                state.error = Some(e.clone());
+                Err(e)
+            }
+        }
+    });
+    *CAPACITY_JOB.lock().unwrap() = Some(id);
+}
+
+pub fn cancel_capacity_test() {
+    if let Some(id) = *CAPACITY_JOB.lock().unwrap() {
+        jobs::cancel(id);
+    }
+}
+
+pub fn draw_capacity_confirm(f: &mut Frame) {
+    let Some((device, area)) = CONFIRM.draw(f, "Capacity Test", 6, "start", |device: &String| confirmation(device))
+    else {
+        return;
+    };
+    let details = Paragraph::new(vec![
+        info_box("Device", &device),
+        Line::from("Writes a tagged block to every sector, then reads them all back to find"),
+        Line::from("how much of the claimed capacity is real."),
+        Line::from(""),
+        Line::from(Span::styled(
+            "ALL DATA ON THIS DEVICE WILL BE DESTROYED",
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        )),
+    ])
+    .block(bordered_block("Capacity Test"))
+    .wrap(Wrap { trim: true });
+    f.render_widget(details, area);
+}
+
+pub fn draw_capacity_test(f: &mut Frame) {
+    let state = CAPACITY_STATE.lock().unwrap().clone();
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([Constraint::Min(6), Constraint::Length(3), Constraint::Length(4)])
+        .split(f.area());
+
+    let mut lines = vec![info_box("Device", &state.device)];
+    if let Some(report) = &state.report {
+        lines.push(Line::from(format!(
+            "Claimed: {} ({} bytes)",
+            format_capacity(report.claimed_bytes),
+            report.claimed_bytes
+        )));
+        lines.push(Line::from(format!(
+            "Usable: {} ({} bytes)",
+            format_capacity(report.usable_bytes),
+            report.usable_bytes
+        )));
+        if let Some(offset) = report.alias_offset {
+            lines.push(Line::from(format!(
+                "Aliasing starts at byte {} ({})",
+                offset,
+                format_capacity(offset)
+            )));
+        }
+        lines.push(Line::from(format!(
+            "Sectors: {} good, {} overwritten, {} corrupted, {} unreadable, {} not written",
+            report.good, report.overwritten, report.corrupted, report.unreadable, report.write_errors
+        )));
+    }
+    let details = Paragraph::new(lines)
+        .block(bordered_block("Capacity Test"))
+        .wrap(Wrap { trim: true });
+    f.render_widget(details, chunks[0]);
+
+    let gauge = Gauge::default()
+        .block(bordered_block("Progress"))
+        .gauge_style(Style::default().fg(Color::Green).bg(Color::Black))
+        .percent(state.percent.min(100))
+        .label(format!("{} ({}%)", state.phase, state.percent));
+    f.render_widget(gauge, chunks[1]);
+
+    let status = match (&state.error, &state.report) {
+        (Some(e), _) => Span::styled(e.clone(), Style::default().fg(Color::Red)),
+        (None, Some(report)) => Span::styled(
+            report.summary(),
+            Style::default()
+                .fg(if report.genuine() { Color::Green } else { Color::Red })
+                .add_modifier(Modifier::BOLD),
+        ),
+        (None, None) => Span::raw("Running..."),
+    };
+    let footer = Paragraph::new(vec![
+        Line::from(status),
+        Line::from("c: cancel, Esc: back (the test keeps running), j: jobs"),
+    ])
+    .block(bordered_block("Result"))
+    .wrap(Wrap { trim: true });
+    f.render_widget(footer, chunks[2]);
+}
+
+#[cfg(test)]
+mod tests;
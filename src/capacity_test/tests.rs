@@ -0,0 +1,221 @@
+use super::*;
+
+const MIB: u64 = 1 << 20;
+
+/// A counterfeit: claims `claimed` bytes but wraps every offset onto the
+/// `real` bytes it has, the way most fake flash controllers do.
+struct Wrapping {
+    data: Vec<u8>,
+    claimed: u64,
+}
+
+impl Media for Wrapping {
+    fn size(&mut self) -> io::Result<u64> {
+        Ok(self.claimed)
+    }
+
+    fn write_block(&mut self, buf: &[u8], offset: u64) -> io::Result<()> {
+        for (i, byte) in buf.iter().enumerate() {
+            let at = (offset as usize + i) % self.data.len();
+            self.data[at] = *byte;
+        }
+        Ok(())
+    }
+
+    fn read_block(&mut self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        for (i, byte) in buf.iter_mut().enumerate() {
+            *byte = self.data[(offset as usize + i) % self.data.len()];
+        }
+        Ok(())
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Drops writes past `real` and reads zeros there; fails reads in `bad`.
+struct Dropping {
+    inner: Wrapping,
+    real: u64,
+    bad: Option<u64>,
+}
+
+impl Media for Dropping {
+    fn size(&mut self) -> io::Result<u64> {
+        self.inner.size()
+    }
+
+    fn write_block(&mut self, buf: &[u8], offset: u64) -> io::Result<()> {
+        let keep = self.real.saturating_sub(offset).min(buf.len() as u64) as usize;
+        self.inner.write_block(&buf[..keep], offset)
+    }
+
+    fn read_block(&mut self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        if self
+            .bad
+            .is_some_and(|bad| (offset..offset + buf.len() as u64).contains(&bad))
+        {
+            return Err(io::Error::from_raw_os_error(libc::EIO));
+        }
+        self.inner.read_block(buf, offset)?;
+        for (i, byte) in buf.iter_mut().enumerate() {
+            if offset + i as u64 >= self.real {
+                *byte = 0;
+            }
+        }
+        Ok(())
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Accepts every write, then fails to flush them.
+struct Unflushable(Wrapping);
+
+impl Media for Unflushable {
+    fn size(&mut self) -> io::Result<u64> {
+        self.0.size()
+    }
+
+    fn write_block(&mut self, buf: &[u8], offset: u64) -> io::Result<()> {
+        self.0.write_block(buf, offset)
+    }
+
+    fn read_block(&mut self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        self.0.read_block(buf, offset)
+    }
+
+    fn sync(&mut self) -> io::Result<()> {
+        Err(io::Error::from_raw_os_error(libc::EIO))
+    }
+}
+
+fn run_quiet(media: &mut dyn Media) -> CapacityReport {
+    run(media, "/dev/sdz", 42, &mut |_, _| true).unwrap()
+}
+
+#[test]
+fn sectors_are_tagged_with_their_offset() {
+    let mut sector = vec![0u8; SECTOR as usize];
+    let mut want = vec![0u8; SECTOR as usize];
+    tagged_sector(&mut sector, 8 * SECTOR, 42);
+    assert_eq!(classify(&sector, 8 * SECTOR, 42, &mut want), SectorState::Good);
+    assert_eq!(
+        classify(&sector, 2 * SECTOR, 42, &mut want),
+        SectorState::Overwritten { from: 8 * SECTOR }
+    );
+    // A leftover from an earlier run is not mistaken for aliasing.
+    assert_eq!(classify(&sector, 2 * SECTOR, 7, &mut want), SectorState::Corrupted);
+    assert_eq!(
+        classify(&vec![0u8; SECTOR as usize], 0, 42, &mut want),
+        SectorState::Corrupted
+    );
+    sector[100] ^= 1;
+    assert_eq!(classify(&sector, 8 * SECTOR, 42, &mut want), SectorState::Corrupted);
+}
+
+#[test]
+fn genuine_image_passes() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("flash.img");
+    // Not a whole number of chunks.
+    File::create(&path).unwrap().set_len(3 * MIB + 5 * SECTOR).unwrap();
+    let mut file = OpenOptions::new().read(true).write(true).open(&path).unwrap();
+
+    let mut last = 0;
+    let report = run(&mut file, "flash.img", 42, &mut |_, percent| {
+        assert!(percent >= last);
+        last = percent;
+        true
+    })
+    .unwrap();
+    assert_eq!(last, 100);
+    assert!(report.genuine());
+    assert_eq!(report.good, 3 * 256 + 5);
+    assert_eq!(report.usable_bytes, report.claimed_bytes);
+    assert_eq!(report.alias_offset, None);
+    assert_eq!(capacity_result(&report).status, TestStatus::Pass);
+}
+
+#[test]
+fn wrapping_media_reports_real_capacity_and_alias_offset() {
+    let mut fake = Wrapping {
+        data: vec![0; 2 * MIB as usize],
+        claimed: 8 * MIB,
+    };
+    let report = run_quiet(&mut fake);
+
+    assert!(!report.genuine());
+    assert_eq!(report.claimed_bytes, 8 * MIB);
+    // Only the last 2 MiB written survive, in the 2 MiB the device really has.
+    assert_eq!(report.usable_bytes, 2 * MIB);
+    assert_eq!(report.alias_offset, Some(2 * MIB));
+    assert_eq!(report.good, 512);
+    assert_eq!(report.overwritten, 1536);
+
+    let result = capacity_result(&report);
+    assert_eq!(result.status, TestStatus::Fail);
+    assert_eq!(result.metrics["alias_offset"], (2 * MIB).to_string());
+    assert!(result.raw_output.starts_with("COUNTERFEIT"));
+}
+
+#[test]
+fn dropped_writes_and_read_errors_are_counted() {
+    let mut fake = Dropping {
+        inner: Wrapping {
+            data: vec![0; 4 * MIB as usize],
+            claimed: 4 * MIB,
+        },
+        real: 3 * MIB,
+        bad: Some(MIB + 10),
+    };
+    let report = run_quiet(&mut fake);
+
+    assert_eq!(report.usable_bytes, MIB);
+    assert_eq!(report.unreadable, 1);
+    assert_eq!(report.corrupted, 256);
+    assert_eq!(report.good, 3 * 256 - 1);
+    assert_eq!(report.alias_offset, None);
+    assert_eq!(
+        report.summary(),
+        format!(
+            "COUNTERFEIT: claims {}, only {} usable",
+            format_capacity(4 * MIB),
+            format_capacity(MIB)
+        )
+    );
+}
+
+#[test]
+fn failed_flush_counts_as_write_errors() {
+    let mut fake = Unflushable(Wrapping {
+        data: vec![0; 2 * MIB as usize],
+        claimed: 2 * MIB,
+    });
+    let report = run_quiet(&mut fake);
+
+    assert_eq!(report.write_errors, 512);
+    assert_eq!(report.good, 512);
+    assert!(!report.genuine());
+    assert!(report.summary().ends_with("512 sectors could not be written"));
+    assert_eq!(capacity_result(&report).status, TestStatus::Fail);
+}
+
+#[test]
+fn cancelling_stops_while_writing() {
+    let mut fake = Wrapping {
+        data: vec![0; MIB as usize],
+        claimed: 4 * MIB,
+    };
+    let mut calls = 0;
+    let outcome = run(&mut fake, "/dev/sdz", 42, &mut |phase, _| {
+        calls += 1;
+        assert_eq!(phase, "Writing tagged blocks");
+        calls < 2
+    });
+    assert_eq!(outcome.unwrap_err().kind(), io::ErrorKind::Interrupted);
+    assert_eq!(calls, 2);
+}
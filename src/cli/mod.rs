@@ -0,0 +1,377 @@
+use clap::{Parser, Subcommand};
+use serde::Serialize;
+use serde_json::json;
+use chrono::Local;
+use std::path::PathBuf;
+
+use crate::audio_test;
+use crate::command::SystemRunner;
+use crate::config;
+use crate::gpu_detect::{self, GpuType};
+use crate::history;
+use crate::nvidia_drivers;
+use crate::photo_exporter;
+use crate::preflight::{self, Feature};
+use crate::session::{self, TestResult, TestStatus};
+use crate::smart;
+use crate::stress_test;
+use crate::wipe;
+
+/// Every check passed.
+pub const EXIT_PASS: i32 = 0;
+/// The check ran but the unit failed it.
+pub const EXIT_FAIL: i32 = 1;
+/// The check could not be run (missing tool, device or permissions).
+pub const EXIT_ERROR: i32 = 2;
+
+#[derive(Parser)]
+#[command(name = "electronics_tui_tester", version, about)]
+pub struct Cli {
+    /// Print machine-readable JSON instead of text
+    #[arg(long, global = true)]
+    pub json: bool,
+
+    /// Station config file (defaults to $XDG_CONFIG_HOME/electronics_tui_tester/config.toml)
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+}
+
+#[derive(Subcommand)]
+pub enum Commands {
+    /// Read SMART data from a drive
+    Smart {
+        /// Device path, e.g. /dev/sda
+        device: String,
+    },
+    /// GPU checks
+    Gpu {
+        #[command(subcommand)]
+        action: GpuAction,
+    },
+    /// Audio checks
+    Audio {
+        #[command(subcommand)]
+        action: AudioAction,
+    },
+    /// Pull all photos off the camera into the next SWxxx folder
+    ExportPhotos,
+    /// NVIDIA driver management
+    Drivers {
+        #[command(subcommand)]
+        action: DriverAction,
+    },
+    /// Run one stress test cycle
+    Stress,
+    /// Check that every external tool is installed
+    Preflight,
+    /// Erase a drive, loop device or image file and verify it
+    Wipe {
+        /// Device or image path, e.g. /dev/sdb or a `losetup --show` loop device
+        device: String,
+        /// The drive's serial number (or the device name when it has none), as a safety check
+        #[arg(long, value_name = "SERIAL")]
+        confirm: String,
+    },
+    /// Check a wipe certificate against the drive history; its content hash only detects corruption
+    VerifyCertificate {
+        /// The certificate's JSON file
+        path: PathBuf,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum GpuAction {
+    /// Detect the installed GPU vendor
+    Detect,
+}
+
+#[derive(Subcommand)]
+pub enum AudioAction {
+    /// Play the test sound on the default output
+    Play {
+        /// Sound file to play instead of the bundled test sound
+        #[arg(long)]
+        file: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum DriverAction {
+    /// List the installable driver packages
+    List,
+    /// Install a driver package and rebuild the initramfs
+    Install {
+        /// Package name as listed by `drivers list`, e.g. nvidia-open
+        name: String,
+    },
+}
+
+/// Runs a headless command and returns the process exit code.
+pub fn run(command: Commands, json: bool) -> i32 {
+    match command {
+        Commands::Smart { device } => run_smart(&device, json),
+        Commands::Gpu { action: GpuAction::Detect } => run_gpu_detect(json),
+        Commands::Audio { action: AudioAction::Play { file } } => run_audio_play(file, json),
+        Commands::ExportPhotos => run_export_photos(json),
+        Commands::Drivers { action: DriverAction::List } => run_driver_list(json),
+        Commands::Drivers { action: DriverAction::Install { name } } => run_driver_install(&name, json),
+        Commands::Stress => run_stress(json),
+        Commands::Preflight => run_preflight(json),
+        Commands::Wipe { device, confirm } => run_wipe(&device, &confirm, json),
+        Commands::VerifyCertificate { path } => run_verify_certificate(&path, json),
+    }
+}
+
+fn emit<T: Serialize>(json: bool, value: &T, text: &str) {
+    if json {
+        println!("{}", serde_json::to_string_pretty(value).unwrap_or_default());
+    } else {
+        println!("{}", text);
+    }
+}
+
+fn emit_error(json: bool, message: &str) -> i32 {
+    log::error!("{}", message);
+    if json {
+        println!("{}", json!({ "error": message }));
+    } else {
+        eprintln!("{}", message);
+    }
+    EXIT_ERROR
+}
+
+/// Prints a session result and maps its status to an exit code.
+fn emit_result(json: bool, result: &TestResult, text: &str) -> i32 {
+    emit(json, result, text);
+    exit_code(result.status)
+}
+
+fn exit_code(status: TestStatus) -> i32 {
+    match status {
+        TestStatus::Pass | TestStatus::Warn => EXIT_PASS,
+        TestStatus::Fail => EXIT_FAIL,
+        TestStatus::Skipped => EXIT_ERROR,
+    }
+}
+
+fn run_smart(device: &str, json: bool) -> i32 {
+    let data = match smart::read_smart(&SystemRunner, device) {
+        Ok(data) => data,
+        Err(e) => return emit_error(json, &format!("Failed to run smartctl: {}", e)),
+    };
+
+    let result = smart::smart_result(device, &data);
+    let text = format!(
+        "Device: {}\nStatus: {}\nHealth: {}\nModel Family: {}\nDevice Model: {}\nSerial Number: {}\nCapacity: {}\nTemperature (°C): {}\nRuntime Hours: {}",
+        device,
+        result.status.label(),
+        result.metrics["health"],
+        result.metrics["family"],
+        result.metrics["model"],
+        result.metrics["serial"],
+        result.metrics["capacity"],
+        result.metrics["temperature_c"],
+        result.metrics["power_on_hours"]
+    );
+    emit_result(json, &result, &text)
+}
+
+fn run_gpu_detect(json: bool) -> i32 {
+    let gpu_type = gpu_detect::detect_gpu_type(&SystemRunner);
+    let devices = gpu_detect::list_gpus(&SystemRunner);
+
+    let mut text = format!("GPU type: {:?}", gpu_type);
+    for device in &devices {
+        text.push_str(&format!("\n  {}", device));
+    }
+
+    let status = match gpu_type {
+        GpuType::Unknown => TestStatus::Fail,
+        _ => TestStatus::Pass,
+    };
+    let result = TestResult::new("GPU detect", &format!("{:?}", gpu_type))
+        .status(status)
+        .metric("devices", devices.len())
+        .raw_output(devices.join("\n"))
+        .finish();
+    emit_result(json, &result, &text)
+}
+
+fn run_audio_play(file: Option<String>, json: bool) -> i32 {
+    let path = file
+        .map(PathBuf::from)
+        .unwrap_or_else(|| config::get().audio.test_file.clone());
+    let started = Local::now();
+    let result = TestResult::new("Audio playback", "default")
+        .started(started)
+        .metric("file", path.display());
+    let (result, text) = match audio_test::play_file(&path) {
+        Ok(()) => (result.status(TestStatus::Pass).finish(), format!("Played {}", path.display())),
+        Err(e) => (result.raw_output(e.clone()).finish(), e),
+    };
+    emit_result(json, &result, &text)
+}
+
+fn run_export_photos(json: bool) -> i32 {
+    let started = Local::now();
+    let output = photo_exporter::export_photos(&SystemRunner);
+    let result = photo_exporter::export_result(&output, started);
+    let text = match result.status {
+        TestStatus::Pass => format!("Photo export complete:\n{}", result.raw_output),
+        _ => format!("Photo export failed:\n{}", result.raw_output),
+    };
+    emit_result(json, &result, &text)
+}
+
+fn run_driver_list(json: bool) -> i32 {
+    let drivers = &config::get().drivers.packages;
+    let text = drivers
+        .iter()
+        .map(|d| format!("{} ({})", d.label, d.package))
+        .collect::<Vec<_>>()
+        .join("\n");
+    emit(json, &json!({ "drivers": drivers }), &text);
+    EXIT_PASS
+}
+
+fn run_driver_install(name: &str, json: bool) -> i32 {
+    let started = Local::now();
+    let output = nvidia_drivers::install_driver(&SystemRunner, name);
+    let result = nvidia_drivers::install_result(name, &output, started);
+    let text = match result.status {
+        TestStatus::Pass => format!("Driver installed successfully.\n\n{}\n\nReboot required.", result.raw_output),
+        _ => format!("Driver install failed:\n{}", result.raw_output),
+    };
+    emit_result(json, &result, &text)
+}
+
+fn run_stress(json: bool) -> i32 {
+    let started = Local::now();
+    stress_test::run_stress_cycle(|_| {});
+    let result = stress_test::stress_result(&format!("{:?}", gpu_detect::detect_gpu_type(&SystemRunner)), started);
+    let text = format!("Stress test completed in {}s.", result.metrics["duration_secs"]);
+    emit_result(json, &result, &text)
+}
+
+fn run_wipe(device: &str, confirm: &str, json: bool) -> i32 {
+    let plan = match wipe::WipePlan::new(&SystemRunner, device, &config::get().wipe) {
+        Ok(plan) => plan,
+        Err(e) => return emit_error(json, &e),
+    };
+    if confirm != plan.confirmation() {
+        return emit_error(
+            json,
+            &format!("--confirm must be {} to wipe {}; nothing was erased", plan.confirmation(), device),
+        );
+    }
+    if let Some(note) = &plan.note {
+        eprintln!("{}", note);
+    }
+    eprintln!("Wiping {} with {}", device, plan.method_text());
+
+    let mut last = None;
+    let report = wipe::execute(&SystemRunner, &plan, config::get().wipe.verify_samples, &mut |progress| {
+        if !json && last.as_ref() != Some(progress) {
+            eprintln!("{} {}%", progress.phase, progress.percent);
+            last = Some(progress.clone());
+        }
+        true
+    });
+    match report {
+        Ok(report) => {
+            let (result, certificate) = match wipe::certify(&report, &session::current().operator) {
+                Ok((certificate, html)) => (
+                    wipe::wipe_result(&report).metric("certificate", &certificate.id),
+                    format!("Certificate {} saved to {}", certificate.id, html.display()),
+                ),
+                Err(e) => (wipe::wipe_result(&report), e),
+            };
+            let text = format!("{}: {}\n{}", result.status.label(), report.verification_text(), certificate);
+            emit_result(json, &result, &text)
+        }
+        Err(e) => emit_error(json, &format!("Wipe failed: {}", e)),
+    }
+}
+
+fn run_verify_certificate(path: &std::path::Path, json: bool) -> i32 {
+    let certificate = match wipe::certificate::load(path) {
+        Ok(certificate) => certificate,
+        Err(e) => return emit_error(json, &e),
+    };
+    let hash_ok = certificate.verify();
+    // The copy kept in the drive history must match the file byte for byte.
+    let recorded = history::get()
+        .and_then(|h| h.certificates(&certificate.serial).ok())
+        .and_then(|certificates| certificates.into_iter().find(|c| c.id == certificate.id));
+    let history_ok = recorded.as_ref().map(|c| *c == certificate);
+
+    let mut problems = Vec::new();
+    if !hash_ok {
+        problems.push("content hash does not match; the certificate was altered".to_string());
+    }
+    if history_ok == Some(false) {
+        problems.push("certificate differs from the copy in the drive history".to_string());
+    }
+    // The hash is unkeyed, so only the history copy shows the certificate is genuine.
+    let history_text = match history_ok {
+        Some(true) => "matches the drive history, so it is authentic",
+        Some(false) => "differs from the drive history",
+        None => {
+            "not found in this station's drive history; the content hash only rules out corruption, \
+             so authenticity is unconfirmed"
+        }
+    };
+    let text = if problems.is_empty() {
+        format!(
+            "Certificate {} is valid ({} {}, {}); {}",
+            certificate.id, certificate.model, certificate.serial, certificate.method, history_text
+        )
+    } else {
+        format!("Certificate {} is INVALID: {}", certificate.id, problems.join("; "))
+    };
+    emit(
+        json,
+        &json!({
+            "id": certificate.id,
+            "serial": certificate.serial,
+            "hash_valid": hash_ok,
+            "in_history": history_ok,
+            "authentic": history_ok == Some(true),
+            "valid": problems.is_empty(),
+        }),
+        &text,
+    );
+    if problems.is_empty() {
+        EXIT_PASS
+    } else {
+        EXIT_FAIL
+    }
+}
+
+fn run_preflight(json: bool) -> i32 {
+    let tools = preflight::run_preflight();
+    let text = tools
+        .iter()
+        .map(|tool| {
+            let features: Vec<&str> = tool.features.iter().map(Feature::label).collect();
+            format!(
+                "{:<12} {:<8} {}  [{}]",
+                tool.name,
+                if tool.found() { "found" } else { "MISSING" },
+                tool.version.as_deref().unwrap_or("-"),
+                features.join(", ")
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+    emit(json, &json!({ "tools": tools }), &text);
+
+    if tools.iter().all(|t| t.found()) {
+        EXIT_PASS
+    } else {
+        EXIT_FAIL
+    }
+}
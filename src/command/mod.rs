@@ -0,0 +1,118 @@
+use std::io;
+use std::process::Command;
+
+/// Captured result of one external command.
+#[derive(Debug, Clone, Default)]
+pub struct CommandOutput {
+    /// Exit code, or `None` when the process was killed by a signal.
+    pub status: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl CommandOutput {
+    pub fn success(&self) -> bool {
+        self.status == Some(0)
+    }
+}
+
+/// Everything that shells out goes through this trait, so tests can replay
+/// recorded output instead of touching real hardware.
+pub trait CommandRunner: Send + Sync {
+    fn run(&self, program: &str, args: &[&str]) -> io::Result<CommandOutput>;
+}
+
+/// Runs the real commands on this machine.
+pub struct SystemRunner;
+
+impl CommandRunner for SystemRunner {
+    fn run(&self, program: &str, args: &[&str]) -> io::Result<CommandOutput> {
+        let output = Command::new(program).args(args).output()?;
+        Ok(CommandOutput {
+            status: output.status.code(),
+            stdout: String::from_utf8_lossy(&output.stdout).to_string(),
+            stderr: String::from_utf8_lossy(&output.stderr).to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+pub use fixture::FixtureRunner;
+
+#[cfg(test)]
+mod fixture {
+    use super::{CommandOutput, CommandRunner};
+    use std::collections::HashMap;
+    use std::fs;
+    use std::io;
+    use std::path::PathBuf;
+    use std::sync::Mutex;
+
+    /// Replays recorded output from `tests/fixtures/<name>.stdout`, plus the
+    /// optional `<name>.stderr` and `<name>.status` (exit code, default 0).
+    /// Commands that were not registered fail as if the tool were missing.
+    #[derive(Default)]
+    pub struct FixtureRunner {
+        fixtures: HashMap<String, String>,
+        calls: Mutex<Vec<String>>,
+    }
+
+    impl FixtureRunner {
+        pub fn new() -> Self {
+            Self::default()
+        }
+
+        /// Maps a full command line such as `"lsblk -d -o NAME"` to a fixture name.
+        pub fn with(mut self, command_line: &str, fixture: &str) -> Self {
+            self.fixtures.insert(command_line.to_string(), fixture.to_string());
+            self
+        }
+
+        pub fn calls(&self) -> Vec<String> {
+            self.calls.lock().unwrap().clone()
+        }
+
+        pub fn fixture_path(name: &str) -> PathBuf {
+            PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("tests")
+                .join("fixtures")
+                .join(name)
+        }
+
+        /// Contents of a fixture file. Panics when it is missing.
+        pub fn fixture(name: &str) -> String {
+            fs::read_to_string(Self::fixture_path(name)).unwrap()
+        }
+
+        fn read(name: &str, extension: &str) -> Option<String> {
+            fs::read_to_string(Self::fixture_path(&format!("{}.{}", name, extension))).ok()
+        }
+    }
+
+    impl CommandRunner for FixtureRunner {
+        fn run(&self, program: &str, args: &[&str]) -> io::Result<CommandOutput> {
+            let command_line = std::iter::once(program)
+                .chain(args.iter().copied())
+                .collect::<Vec<_>>()
+                .join(" ");
+            self.calls.lock().unwrap().push(command_line.clone());
+
+            let name = self.fixtures.get(&command_line).ok_or_else(|| {
+                io::Error::new(io::ErrorKind::NotFound, format!("no fixture for `{}`", command_line))
+            })?;
+
+            let stdout = Self::read(name, "stdout").ok_or_else(|| {
+                io::Error::new(io::ErrorKind::NotFound, format!("missing fixture {}.stdout", name))
+            })?;
+            let status = Self::read(name, "status")
+                .and_then(|s| s.trim().parse().ok())
+                .unwrap_or(0);
+
+            Ok(CommandOutput {
+                status: Some(status),
+                stdout,
+                stderr: Self::read(name, "stderr").unwrap_or_default(),
+            })
+        }
+    }
+}
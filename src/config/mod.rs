@@ -0,0 +1,309 @@
+use once_cell::sync::OnceCell;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::wipe::Pattern;
+
+/// Per-station settings loaded from `config.toml`. Every field has a default,
+/// so a config file only needs to list what differs on that station.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub session: SessionConfig,
+    pub photos: PhotoConfig,
+    pub audio: AudioConfig,
+    pub drivers: DriverConfig,
+    pub logging: LoggingConfig,
+    pub workflows: WorkflowConfig,
+    pub grading: GradingConfig,
+    pub smart: SmartConfig,
+    pub history: HistoryConfig,
+    pub wipe: WipeConfig,
+    pub surface: SurfaceConfig,
+    pub benchmark: BenchmarkConfig,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SessionConfig {
+    /// Operator name printed on reports; falls back to `$USER` when empty.
+    pub operator: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PhotoConfig {
+    pub base_path: PathBuf,
+    pub folder_prefix: String,
+    pub start_number: u32,
+}
+
+impl Default for PhotoConfig {
+    fn default() -> Self {
+        Self {
+            base_path: PathBuf::from("/home/ecom/Pictures/ebay"),
+            folder_prefix: "SW".to_string(),
+            start_number: 84,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioConfig {
+    pub test_file: PathBuf,
+}
+
+impl Default for AudioConfig {
+    fn default() -> Self {
+        Self {
+            test_file: PathBuf::from("assets/audio/test.wav"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DriverConfig {
+    /// Shell command that installs a package; `{package}` is substituted.
+    pub install_command: String,
+    /// Shell command run after a successful install, e.g. to rebuild the initramfs.
+    pub post_install_command: String,
+    pub packages: Vec<DriverPackage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DriverPackage {
+    pub label: String,
+    pub package: String,
+}
+
+impl DriverPackage {
+    fn new(label: &str, package: &str) -> Self {
+        Self {
+            label: label.to_string(),
+            package: package.to_string(),
+        }
+    }
+}
+
+impl Default for DriverConfig {
+    fn default() -> Self {
+        Self {
+            install_command: "sudo aura -A --noconfirm {package}".to_string(),
+            post_install_command: "sudo mkinitcpio -P".to_string(),
+            packages: vec![
+                DriverPackage::new("nvidia (stable)", "nvidia"),
+                DriverPackage::new("nvidia-beta", "nvidia-beta"),
+                DriverPackage::new("nvidia-open", "nvidia-open"),
+                DriverPackage::new("nvidia-390xx", "nvidia-390xx"),
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// Where session logs are written; defaults to the user state directory.
+    pub dir: Option<PathBuf>,
+    /// Lowest severity written to the log: error, warn, info, debug or trace.
+    pub level: String,
+    /// How many session log files to keep before the oldest are deleted.
+    pub keep_sessions: usize,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            dir: None,
+            level: "info".to_string(),
+            keep_sessions: 20,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WorkflowConfig {
+    /// Extra workflow profiles (`*.toml`); defaults to `workflows/` next to the config file.
+    pub dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SmartConfig {
+    /// Where batch exports are written; defaults to the user data directory.
+    pub export_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HistoryConfig {
+    /// The drive history database; defaults to the user data directory.
+    pub path: Option<PathBuf>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WipeConfig {
+    /// Patterns written, in order, when a drive has to be overwritten.
+    pub overwrite_passes: Vec<Pattern>,
+    /// Sectors read back after the wipe to verify it.
+    pub verify_samples: usize,
+    /// Where wipe certificates are written; defaults to the user data directory.
+    pub certificate_dir: Option<PathBuf>,
+}
+
+impl Default for WipeConfig {
+    fn default() -> Self {
+        Self {
+            overwrite_passes: vec![Pattern::Zeros],
+            verify_samples: 1000,
+            certificate_dir: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SurfaceConfig {
+    /// Bytes read per request during a surface scan.
+    pub block_size: u64,
+    /// Blocks taking at least this long to read are marked slow.
+    pub slow_ms: u64,
+}
+
+impl Default for SurfaceConfig {
+    fn default() -> Self {
+        Self {
+            block_size: 1 << 20,
+            slow_ms: 150,
+        }
+    }
+}
+
+/// What a healthy drive of one interface should manage: sequential MB/s and
+/// random 4K IOPS as (min, max), and the worst acceptable p99 latency.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExpectedRange {
+    pub seq_mb_s: (f64, f64),
+    pub iops: (f64, f64),
+    pub p99_ms: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BenchmarkConfig {
+    /// Read at each of the start, middle and end of the disk.
+    pub sequential_mib: u64,
+    /// Random 4K reads, stopping early after `random_secs`.
+    pub random_reads: usize,
+    pub random_secs: u64,
+    pub sata_hdd: ExpectedRange,
+    pub sata_ssd: ExpectedRange,
+    pub nvme: ExpectedRange,
+}
+
+impl Default for BenchmarkConfig {
+    fn default() -> Self {
+        Self {
+            sequential_mib: 256,
+            random_reads: 2000,
+            random_secs: 15,
+            sata_hdd: ExpectedRange {
+                seq_mb_s: (60.0, 280.0),
+                iops: (40.0, 250.0),
+                p99_ms: 60.0,
+            },
+            sata_ssd: ExpectedRange {
+                seq_mb_s: (300.0, 600.0),
+                iops: (3000.0, 20000.0),
+                p99_ms: 5.0,
+            },
+            nvme: ExpectedRange {
+                seq_mb_s: (800.0, 8000.0),
+                iops: (7000.0, 100000.0),
+                p99_ms: 2.0,
+            },
+        }
+    }
+}
+
+/// Values at or above which a drive drops to each grade; unset levels never apply.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Thresholds {
+    pub good: Option<u64>,
+    pub fair: Option<u64>,
+    pub bad: Option<u64>,
+}
+
+impl Thresholds {
+    pub const fn new(good: Option<u64>, fair: Option<u64>, bad: Option<u64>) -> Self {
+        Self { good, fair, bad }
+    }
+}
+
+/// Drive grading rules. Each rule caps the grade once its value reaches a threshold.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GradingConfig {
+    pub reallocated_sectors: Thresholds,
+    pub pending_sectors: Thresholds,
+    pub offline_uncorrectable: Thresholds,
+    /// Usually a cable or backplane fault rather than the drive itself.
+    pub crc_errors: Thresholds,
+    pub power_on_hours: Thresholds,
+    pub nvme_percentage_used: Thresholds,
+    pub nvme_media_errors: Thresholds,
+    /// Failed entries in the drive's self-test log.
+    pub self_test_failures: Thresholds,
+}
+
+impl Default for GradingConfig {
+    fn default() -> Self {
+        Self {
+            reallocated_sectors: Thresholds::new(Some(1), Some(10), Some(100)),
+            pending_sectors: Thresholds::new(None, Some(1), Some(10)),
+            offline_uncorrectable: Thresholds::new(None, Some(1), Some(10)),
+            crc_errors: Thresholds::new(Some(1), Some(100), None),
+            power_on_hours: Thresholds::new(Some(20_000), Some(50_000), None),
+            nvme_percentage_used: Thresholds::new(Some(30), Some(80), Some(100)),
+            nvme_media_errors: Thresholds::new(None, None, Some(1)),
+            self_test_failures: Thresholds::new(None, None, Some(1)),
+        }
+    }
+}
+
+static CONFIG: OnceCell<Config> = OnceCell::new();
+
+/// `$XDG_CONFIG_HOME/electronics_tui_tester/config.toml`, or the platform equivalent.
+pub fn default_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("electronics_tui_tester").join("config.toml"))
+}
+
+pub fn load_from(path: &Path) -> Result<Config, String> {
+    let text = fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read config {}: {}", path.display(), e))?;
+    toml::from_str(&text).map_err(|e| format!("Invalid config {}: {}", path.display(), e))
+}
+
+/// Loads the station config once at startup. An explicit `--config` path must
+/// exist; the default location is optional and falls back to built-in values.
+pub fn init(path: Option<&Path>) -> Result<(), String> {
+    let config = match path {
+        Some(path) => load_from(path)?,
+        None => match default_path() {
+            Some(path) if path.exists() => load_from(&path)?,
+            _ => Config::default(),
+        },
+    };
+    let _ = CONFIG.set(config);
+    Ok(())
+}
+
+pub fn get() -> &'static Config {
+    CONFIG.get_or_init(Config::default)
+}
@@ -0,0 +1,142 @@
+use crossterm::event::KeyCode;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Paragraph, Wrap},
+    Frame,
+};
+use std::sync::Mutex;
+
+use crate::app::Transition;
+use crate::theme::bordered_block;
+
+struct State<T> {
+    /// What waits for confirmation, or why it was refused.
+    pending: Option<Result<T, String>>,
+    input: String,
+    message: String,
+}
+
+/// A typed confirmation in front of a destructive operation: the operator
+/// types a word, such as the serial or device name, and presses Enter.
+pub struct Confirmation<T> {
+    state: Mutex<State<T>>,
+}
+
+/// What a key press on the confirmation screen came to.
+pub enum Outcome<T> {
+    Done(Transition),
+    /// The operator typed the word and pressed Enter.
+    Confirmed(T),
+}
+
+impl<T: Clone> Confirmation<T> {
+    pub const fn new() -> Self {
+        Self {
+            state: Mutex::new(State {
+                pending: None,
+                input: String::new(),
+                message: String::new(),
+            }),
+        }
+    }
+
+    pub fn open(&self, pending: Result<T, String>) {
+        *self.state.lock().unwrap() = State {
+            pending: Some(pending),
+            input: String::new(),
+            message: String::new(),
+        };
+    }
+
+    pub fn close(&self) {
+        let mut state = self.state.lock().unwrap();
+        state.pending = None;
+        state.input.clear();
+        state.message.clear();
+    }
+
+    /// Shown under the prompt, e.g. why a confirmed operation did not start.
+    pub fn set_message(&self, message: &str) {
+        self.state.lock().unwrap().message = message.to_string();
+    }
+
+    /// Takes every key as text until Enter or Esc. `mismatch` is shown when
+    /// the typed text is not `word` of the pending item.
+    pub fn handle_key(&self, key: KeyCode, word: impl Fn(&T) -> String, mismatch: &str) -> Outcome<T> {
+        let mut state = self.state.lock().unwrap();
+        match key {
+            KeyCode::Esc => return Outcome::Done(Transition::Pop),
+            KeyCode::Backspace => {
+                state.input.pop();
+            }
+            KeyCode::Char(c) => state.input.push(c),
+            KeyCode::Enter => {
+                let Some(Ok(item)) = state.pending.clone() else {
+                    return Outcome::Done(Transition::Stay);
+                };
+                if state.input.trim() == word(&item) {
+                    return Outcome::Confirmed(item);
+                }
+                state.message = mismatch.to_string();
+            }
+            _ => {}
+        }
+        Outcome::Done(Transition::Stay)
+    }
+
+    /// Draws the prompt to type `word`, or the refusal when there is nothing
+    /// to confirm. Returns the pending item and the area above the prompt,
+    /// at least `details_height` rows, for the caller to describe it in.
+    pub fn draw(
+        &self,
+        f: &mut Frame,
+        title: &str,
+        details_height: u16,
+        action: &str,
+        word: impl Fn(&T) -> String,
+    ) -> Option<(T, Rect)> {
+        let (pending, input, message) = {
+            let state = self.state.lock().unwrap();
+            (state.pending.clone()?, state.input.clone(), state.message.clone())
+        };
+        let item = match pending {
+            Ok(item) => item,
+            Err(e) => {
+                let error = Paragraph::new(vec![
+                    Line::from(Span::styled(e, Style::default().fg(Color::Red))),
+                    Line::from("Esc to go back"),
+                ])
+                .block(bordered_block(title))
+                .wrap(Wrap { trim: true });
+                f.render_widget(error, f.area());
+                return None;
+            }
+        };
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .margin(1)
+            .constraints([Constraint::Min(details_height), Constraint::Length(6)])
+            .split(f.area());
+        let prompt = Paragraph::new(vec![
+            Line::from(format!(
+                "Type {} and press Enter to {}, Esc to cancel",
+                word(&item),
+                action
+            )),
+            Line::from(Span::styled(
+                format!("> {}_", input),
+                Style::default().fg(Color::White).add_modifier(Modifier::BOLD),
+            )),
+            Line::from(Span::styled(message, Style::default().fg(Color::Yellow))),
+        ])
+        .block(bordered_block("Confirm"));
+        f.render_widget(prompt, chunks[1]);
+        Some((item, chunks[0]))
+    }
+}
+
+#[cfg(test)]
+mod tests;
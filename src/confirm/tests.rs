@@ -0,0 +1,43 @@
+use super::*;
+
+fn type_text(confirm: &Confirmation<String>, text: &str) {
+    for c in text.chars() {
+        confirm.handle_key(KeyCode::Char(c), String::clone, "no");
+    }
+}
+
+#[test]
+fn confirms_only_the_typed_word() {
+    let word = |item: &String| item.to_uppercase();
+    let confirm = Confirmation::new();
+    confirm.open(Ok("sdb".to_string()));
+    type_text(&confirm, "SDX");
+    confirm.handle_key(KeyCode::Backspace, word, "no");
+    assert!(matches!(
+        confirm.handle_key(KeyCode::Enter, word, "That does not match."),
+        Outcome::Done(Transition::Stay)
+    ));
+    assert_eq!(confirm.state.lock().unwrap().message, "That does not match.");
+
+    type_text(&confirm, "B");
+    match confirm.handle_key(KeyCode::Enter, word, "no") {
+        Outcome::Confirmed(item) => assert_eq!(item, "sdb"),
+        Outcome::Done(_) => panic!("not confirmed"),
+    }
+    assert!(matches!(
+        confirm.handle_key(KeyCode::Esc, word, "no"),
+        Outcome::Done(Transition::Pop)
+    ));
+}
+
+#[test]
+fn refused_operations_cannot_be_confirmed() {
+    let confirm: Confirmation<String> = Confirmation::new();
+    confirm.open(Err("sdb is mounted".to_string()));
+    assert!(matches!(
+        confirm.handle_key(KeyCode::Enter, String::clone, "no"),
+        Outcome::Done(Transition::Stay)
+    ));
+    confirm.close();
+    assert!(confirm.state.lock().unwrap().pending.is_none());
+}
@@ -7,26 +7,20 @@ use ratatui::{
 };
 use once_cell::sync::Lazy;
 use std::sync::Mutex;
-use std::process::Command;
+
+use crate::command::{CommandRunner, SystemRunner};
 
 static DEVICES: Lazy<Mutex<Vec<String>>> = Lazy::new(|| Mutex::new(vec![]));
 static DEVICE_INDEX: Lazy<Mutex<usize>> = Lazy::new(|| Mutex::new(0));
-static ACTIVE: Lazy<Mutex<bool>> = Lazy::new(|| Mutex::new(false));
 static MESSAGE: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new(String::new()));
 
-pub fn check_gamepad_test_active() -> bool {
-    *ACTIVE.lock().unwrap()
-}
-
 pub fn exit_gamepad_test() {
-    *ACTIVE.lock().unwrap() = false;
     *MESSAGE.lock().unwrap() = String::new();
 }
 
 pub fn enter_gamepad_test() {
-    *DEVICES.lock().unwrap() = fetch_gamepad_devices();
+    *DEVICES.lock().unwrap() = fetch_gamepad_devices(&SystemRunner);
     *DEVICE_INDEX.lock().unwrap() = 0;
-    *ACTIVE.lock().unwrap() = true;
     *MESSAGE.lock().unwrap() = "Select your controller device. Full testing coming soon.".to_string();
 }
 
@@ -87,13 +81,9 @@ pub fn draw_gamepad_test(f: &mut Frame) {
     f.render_widget(info, chunks[1]);
 }
 
-fn fetch_gamepad_devices() -> Vec<String> {
-    let output = Command::new("lsusb")
-        .output()
-        .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
-        .unwrap_or_else(|_| "Failed to run lsusb".to_string());
-
-    output
+/// Keeps the `lsusb` lines that describe a controller, gamepad or joystick.
+pub fn filter_gamepad_devices(lsusb: &str) -> Vec<String> {
+    lsusb
         .lines()
         .filter(|line| {
             let lower = line.to_lowercase();
@@ -101,4 +91,14 @@ fn fetch_gamepad_devices() -> Vec<String> {
         })
         .map(String::from)
         .collect()
-}
\ No newline at end of file
+}
+
+fn fetch_gamepad_devices(runner: &dyn CommandRunner) -> Vec<String> {
+    runner
+        .run("lsusb", &[])
+        .map(|o| filter_gamepad_devices(&o.stdout))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,20 @@
+use super::*;
+use crate::command::FixtureRunner;
+
+#[test]
+fn filters_gamepads_from_lsusb() {
+    let runner = FixtureRunner::new().with("lsusb", "lsusb");
+    let devices = fetch_gamepad_devices(&runner);
+    assert_eq!(devices.len(), 2);
+    assert!(devices[0].contains("Xbox360 Controller"));
+    assert!(devices[1].contains("PC TWIN SHOCK Gamepad"));
+}
+
+#[test]
+fn ignores_keyboards_and_hubs() {
+    let devices = filter_gamepad_devices(
+        "Bus 001 Device 004: ID 046d:c31c Logitech, Inc. Keyboard K120\n\
+         Bus 001 Device 001: ID 1d6b:0002 Linux Foundation 2.0 root hub",
+    );
+    assert!(devices.is_empty());
+}
@@ -1,10 +1,12 @@
 use once_cell::sync::Lazy;
-use std::process::Command;
+use serde::Serialize;
 use std::sync::Mutex;
 
-#[derive(Debug, Clone)]
+use crate::command::CommandRunner;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 pub enum GpuType {
-    AMD,
+    Amd,
     Nvidia,
     Unknown,
 }
@@ -12,29 +14,69 @@ pub enum GpuType {
 static SELECTED_GPU: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new(String::new()));
 static GPU_TYPE: Lazy<Mutex<GpuType>> = Lazy::new(|| Mutex::new(GpuType::Unknown));
 
-pub fn detect_gpu_type() -> GpuType {
-    let output = Command::new("lspci")
-        .arg("-nn")
-        .output()
-        .map(|o| String::from_utf8_lossy(&o.stdout).to_lowercase())
-        .unwrap_or_else(|_| "unknown".to_string());
+/// Keeps only the display controller lines (VGA, 3D and display class devices) of `lspci` output.
+pub fn filter_gpu_lines(lspci: &str) -> Vec<String> {
+    lspci
+        .lines()
+        .filter(|line| {
+            line.contains("VGA compatible controller")
+                || line.contains("3D controller")
+                || line.contains("Display controller")
+        })
+        .map(String::from)
+        .collect()
+}
+
+/// Picks the GPU vendor from display controller lines only, so AMD chipsets or
+/// unrelated devices elsewhere in `lspci` do not count as a GPU.
+pub fn parse_gpu_type(lspci: &str) -> GpuType {
+    let gpus = filter_gpu_lines(lspci).join("\n").to_lowercase();
 
-    let gpu_type = if output.contains("amd") || output.contains("ati") {
-        GpuType::AMD
-    } else if output.contains("nvidia") {
+    if gpus.contains("nvidia") {
         GpuType::Nvidia
+    } else if gpus.contains("advanced micro devices")
+        || gpus.contains("[amd")
+        || gpus.contains("amd/ati")
+        || gpus.contains("ati technologies")
+        || gpus.contains("radeon")
+    {
+        GpuType::Amd
     } else {
         GpuType::Unknown
-    };
+    }
+}
+
+pub fn detect_gpu_type(runner: &dyn CommandRunner) -> GpuType {
+    let output = runner
+        .run("lspci", &["-nn"])
+        .map(|o| o.stdout)
+        .unwrap_or_default();
+
+    let gpu_type = parse_gpu_type(&output);
 
     *GPU_TYPE.lock().unwrap() = gpu_type.clone();
     gpu_type
 }
 
+/// Returns the `lspci` lines for display controllers.
+pub fn list_gpus(runner: &dyn CommandRunner) -> Vec<String> {
+    runner
+        .run("lspci", &[])
+        .map(|o| filter_gpu_lines(&o.stdout))
+        .unwrap_or_default()
+}
+
 pub fn set_selected_gpu(name: &str) {
     *SELECTED_GPU.lock().unwrap() = name.to_string();
 }
 
 pub fn get_selected_gpu() -> String {
     SELECTED_GPU.lock().unwrap().clone()
-}
\ No newline at end of file
+}
+
+pub fn get_gpu_type() -> GpuType {
+    GPU_TYPE.lock().unwrap().clone()
+}
+
+#[cfg(test)]
+mod tests;
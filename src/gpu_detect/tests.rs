@@ -0,0 +1,54 @@
+use super::*;
+use crate::command::FixtureRunner;
+
+#[test]
+fn detects_nvidia() {
+    assert_eq!(parse_gpu_type(&FixtureRunner::fixture("lspci_nvidia.stdout")), GpuType::Nvidia);
+}
+
+#[test]
+fn detects_amd() {
+    assert_eq!(parse_gpu_type(&FixtureRunner::fixture("lspci_amd.stdout")), GpuType::Amd);
+}
+
+#[test]
+fn amd_chipset_does_not_hide_nvidia_card() {
+    assert_eq!(
+        parse_gpu_type(&FixtureRunner::fixture("lspci_amd_chipset_nvidia_gpu.stdout")),
+        GpuType::Nvidia
+    );
+}
+
+#[test]
+fn intel_only_is_unknown() {
+    // "Communication controller" contains "ati" and used to be reported as AMD.
+    assert_eq!(parse_gpu_type(&FixtureRunner::fixture("lspci_intel.stdout")), GpuType::Unknown);
+}
+
+#[test]
+fn filters_display_controllers() {
+    let gpus = filter_gpu_lines(&FixtureRunner::fixture("lspci_amd.stdout"));
+    assert_eq!(gpus.len(), 1);
+    assert!(gpus[0].contains("Navi 23"));
+}
+
+#[test]
+fn detects_through_runner() {
+    let runner = FixtureRunner::new().with("lspci -nn", "lspci_nvidia");
+    assert_eq!(detect_gpu_type(&runner), GpuType::Nvidia);
+}
+
+#[test]
+fn missing_lspci_is_unknown() {
+    let runner = FixtureRunner::new();
+    assert_eq!(detect_gpu_type(&runner), GpuType::Unknown);
+    assert!(list_gpus(&runner).is_empty());
+}
+
+#[test]
+fn lists_gpus_through_runner() {
+    let runner = FixtureRunner::new().with("lspci", "lspci_nvidia");
+    let gpus = list_gpus(&runner);
+    assert_eq!(gpus.len(), 1);
+    assert!(gpus[0].contains("GeForce RTX 2060"));
+}
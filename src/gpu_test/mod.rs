@@ -1,11 +1,13 @@
-use crate::gpu_detect::{get_selected_gpu, detect_gpu_type, GpuType};
+use crate::command::SystemRunner;
+use crate::gpu_detect::{detect_gpu_type, get_gpu_type, get_selected_gpu, set_selected_gpu};
 use crate::stress_test::start_stress_test;
 use crate::stability_test::start_stability_test;
+use crate::theme::{bordered_block, info_box};
 
 use ratatui::{
     Frame,
-    widgets::{Block, Borders, Paragraph},
-    text::Span,
+    widgets::Paragraph,
+    text::{Line, Text},
 };
 
 use once_cell::sync::Lazy;
@@ -17,51 +19,48 @@ pub enum TestMode {
     Stability,
 }
 
-pub static GPU_TEST_ACTIVE: Lazy<Mutex<bool>> = Lazy::new(|| Mutex::new(false));
 pub static CURRENT_TEST_MODE: Lazy<Mutex<Option<TestMode>>> = Lazy::new(|| Mutex::new(None));
-pub static GPU_TEST_STARTED: Lazy<Mutex<bool>> = Lazy::new(|| Mutex::new(false));
 
-pub fn set_test_mode(mode: TestMode) {
-    *GPU_TEST_ACTIVE.lock().unwrap() = true;
+pub fn enter_gpu_test(gpu: &str) {
+    set_selected_gpu(gpu);
+    detect_gpu_type(&SystemRunner);
+    clear_test_mode();
+}
+
+pub fn run_test(mode: TestMode) {
     *CURRENT_TEST_MODE.lock().unwrap() = Some(mode);
-    *GPU_TEST_STARTED.lock().unwrap() = false; // reset run flag
+    match mode {
+        TestMode::Stress => start_stress_test(),
+        TestMode::Stability => start_stability_test(),
+    }
 }
 
 pub fn clear_test_mode() {
-    *GPU_TEST_ACTIVE.lock().unwrap() = false;
     *CURRENT_TEST_MODE.lock().unwrap() = None;
-    *GPU_TEST_STARTED.lock().unwrap() = false;
-}
-
-pub fn check_test_active() -> bool {
-    *GPU_TEST_ACTIVE.lock().unwrap()
 }
 
 pub fn draw_gpu_testing(f: &mut Frame) {
     let area = f.area();
 
     let selected_gpu = get_selected_gpu();
-    let gpu_type = detect_gpu_type();
+    let gpu_type = get_gpu_type();
     let mode = *CURRENT_TEST_MODE.lock().unwrap();
-    let mut started = GPU_TEST_STARTED.lock().unwrap();
 
-    if let Some(test_mode) = mode {
-        // Start the test only once
-        if !*started {
-            match test_mode {
-                TestMode::Stress => start_stress_test(),
-                TestMode::Stability => start_stability_test(),
-            }
-            *started = true;
-        }
+    let detected = format!("{:?}", gpu_type);
+    let status = match mode {
+        Some(test_mode) => format!("Running {:?} test", test_mode),
+        None => "Idle".to_string(),
+    };
 
-        let status = format!(
-            "Running {:?} test on {:?}: {}\n\nPress 'q' to exit...",
-            test_mode, gpu_type, selected_gpu
-        );
+    let lines = vec![
+        info_box("Selected GPU", &selected_gpu),
+        info_box("Detected", &detected),
+        info_box("Status", &status),
+        Line::raw(""),
+        Line::raw("Press 's' for a stress test or 't' for a stability test, 'c' to cancel the stability test."),
+        Line::raw("Tests keep running in the background after Esc; press 'j' to see all jobs."),
+    ];
 
-        let block = Block::default().title("GPU Test").borders(Borders::ALL);
-        let paragraph = Paragraph::new(Span::raw(status)).block(block);
-        f.render_widget(paragraph, area);
-    }
-}
\ No newline at end of file
+    let paragraph = Paragraph::new(Text::from(lines)).block(bordered_block("GPU Test"));
+    f.render_widget(paragraph, area);
+}
@@ -0,0 +1,295 @@
+use chrono::{DateTime, Duration, Local};
+use once_cell::sync::Lazy;
+use redb::{Database, ReadableDatabase, TableDefinition, TableError};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::config::{self, GradingConfig};
+use crate::inspect::Inspection;
+use crate::smart::grade::{self, Grade, OFFLINE_UNCORRECTABLE, PENDING_SECTORS, REALLOCATED_SECTORS};
+use crate::smart::SmartData;
+use crate::wipe::certificate::Certificate;
+
+/// SMART snapshots keyed by (serial number, milliseconds since the epoch).
+const SMART: TableDefinition<(&str, i64), &str> = TableDefinition::new("smart");
+
+/// Wipe certificates keyed by (serial number, certificate id).
+const CERTIFICATES: TableDefinition<(&str, &str), &str> = TableDefinition::new("certificates");
+
+/// Intake inspections keyed by (serial number, milliseconds since the epoch).
+const INSPECTIONS: TableDefinition<(&str, i64), &str> = TableDefinition::new("inspections");
+
+/// Reads closer together than this are one visit; deltas compare against the visit before.
+const SAME_VISIT: Duration = Duration::hours(1);
+
+/// One SMART reading of one drive.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub taken: DateTime<Local>,
+    pub device: String,
+    pub data: SmartData,
+}
+
+/// A wear counter that moved between two snapshots.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CounterChange {
+    pub name: &'static str,
+    pub before: u64,
+    pub after: u64,
+}
+
+/// What changed on a drive since it was last tested.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SmartDelta {
+    pub since: DateTime<Local>,
+    pub hours_added: Option<u64>,
+    pub grade_before: Grade,
+    pub grade_after: Grade,
+    pub changes: Vec<CounterChange>,
+}
+
+impl SmartDelta {
+    /// The grade dropped or a wear counter grew.
+    pub fn degrading(&self) -> bool {
+        self.grade_after > self.grade_before || self.changes.iter().any(|c| c.after > c.before)
+    }
+
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        if let Some(hours) = self.hours_added {
+            lines.push(format!("+{} power-on hours", hours));
+        }
+        if self.grade_after != self.grade_before {
+            lines.push(format!("Grade {} → {}", self.grade_before.label(), self.grade_after.label()));
+        }
+        for c in &self.changes {
+            let diff = c.after as i128 - c.before as i128;
+            lines.push(format!("{} {} → {} ({:+})", c.name, c.before, c.after, diff));
+        }
+        if lines.is_empty() {
+            lines.push("No change".to_string());
+        }
+        lines
+    }
+}
+
+/// Counters that only grow as a drive wears out. CRC errors are left out;
+/// they point at the cable, not the drive.
+fn wear_counters(data: &SmartData) -> Vec<(&'static str, u64)> {
+    let mut counters = Vec::new();
+    let raw = |id: u8| data.attributes().iter().find(|a| a.id == id).map(|a| a.raw.value);
+    for (name, id) in [
+        ("Reallocated sectors", REALLOCATED_SECTORS),
+        ("Pending sectors", PENDING_SECTORS),
+        ("Offline uncorrectable", OFFLINE_UNCORRECTABLE),
+    ] {
+        if let Some(value) = raw(id) {
+            counters.push((name, value));
+        }
+    }
+    if let Some(log) = &data.nvme_smart_health_information_log {
+        counters.push(("Media errors", log.media_errors));
+        counters.push(("Endurance used %", log.percentage_used as u64));
+    }
+    let failed = data.self_tests().iter().filter(|t| t.passed == Some(false)).count();
+    counters.push(("Failed self-tests", failed as u64));
+    counters
+}
+
+pub fn smart_delta(before: &Snapshot, after: &SmartData, rules: &GradingConfig) -> SmartDelta {
+    let old = wear_counters(&before.data);
+    let changes = wear_counters(after)
+        .into_iter()
+        .filter_map(|(name, value)| {
+            let (_, previous) = old.iter().find(|(n, _)| *n == name)?;
+            (*previous != value).then_some(CounterChange {
+                name,
+                before: *previous,
+                after: value,
+            })
+        })
+        .collect();
+
+    SmartDelta {
+        since: before.taken,
+        hours_added: after
+            .power_on_hours()
+            .zip(before.data.power_on_hours())
+            .map(|(now, then)| now.saturating_sub(then)),
+        grade_before: grade::grade_drive(&before.data, rules).grade,
+        grade_after: grade::grade_drive(after, rules).grade,
+        changes,
+    }
+}
+
+/// The newest snapshot from an earlier visit. `snapshots` are oldest first.
+pub fn baseline(snapshots: &[Snapshot], now: DateTime<Local>) -> Option<&Snapshot> {
+    snapshots.iter().rev().find(|s| now - s.taken >= SAME_VISIT)
+}
+
+fn db_error(e: impl Into<redb::Error>) -> String {
+    format!("History database error: {}", e.into())
+}
+
+/// The on-disk store of past readings, one database file per station.
+pub struct History {
+    db: Database,
+}
+
+impl History {
+    pub fn open(path: &Path) -> Result<Self, String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        let db = Database::create(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        Ok(Self { db })
+    }
+
+    pub fn add_smart(&self, serial: &str, snapshot: &Snapshot) -> Result<(), String> {
+        let json = serde_json::to_string(snapshot).map_err(|e| e.to_string())?;
+        let txn = self.db.begin_write().map_err(db_error)?;
+        {
+            let mut table = txn.open_table(SMART).map_err(db_error)?;
+            table
+                .insert((serial, snapshot.taken.timestamp_millis()), json.as_str())
+                .map_err(db_error)?;
+        }
+        txn.commit().map_err(db_error)
+    }
+
+    /// Every snapshot of the drive with this serial, oldest first.
+    pub fn smart_snapshots(&self, serial: &str) -> Result<Vec<Snapshot>, String> {
+        let txn = self.db.begin_read().map_err(db_error)?;
+        let table = match txn.open_table(SMART) {
+            Ok(table) => table,
+            Err(TableError::TableDoesNotExist(_)) => return Ok(Vec::new()),
+            Err(e) => return Err(db_error(e)),
+        };
+        let mut snapshots = Vec::new();
+        for entry in table.range((serial, i64::MIN)..=(serial, i64::MAX)).map_err(db_error)? {
+            let (_, json) = entry.map_err(db_error)?;
+            match serde_json::from_str(json.value()) {
+                Ok(snapshot) => snapshots.push(snapshot),
+                Err(e) => log::warn!("Skipping unreadable SMART snapshot for {}: {}", serial, e),
+            }
+        }
+        Ok(snapshots)
+    }
+
+    pub fn add_certificate(&self, certificate: &Certificate) -> Result<(), String> {
+        let json = serde_json::to_string(certificate).map_err(|e| e.to_string())?;
+        let txn = self.db.begin_write().map_err(db_error)?;
+        {
+            let mut table = txn.open_table(CERTIFICATES).map_err(db_error)?;
+            table
+                .insert((certificate.serial.as_str(), certificate.id.as_str()), json.as_str())
+                .map_err(db_error)?;
+        }
+        txn.commit().map_err(db_error)
+    }
+
+    /// Every wipe certificate issued for the drive with this serial.
+    pub fn certificates(&self, serial: &str) -> Result<Vec<Certificate>, String> {
+        let txn = self.db.begin_read().map_err(db_error)?;
+        let table = match txn.open_table(CERTIFICATES) {
+            Ok(table) => table,
+            Err(TableError::TableDoesNotExist(_)) => return Ok(Vec::new()),
+            Err(e) => return Err(db_error(e)),
+        };
+        let mut certificates = Vec::new();
+        for entry in table.range((serial, "")..).map_err(db_error)? {
+            let (key, json) = entry.map_err(db_error)?;
+            if key.value().0 != serial {
+                break;
+            }
+            match serde_json::from_str(json.value()) {
+                Ok(certificate) => certificates.push(certificate),
+                Err(e) => log::warn!("Skipping unreadable certificate for {}: {}", serial, e),
+            }
+        }
+        Ok(certificates)
+    }
+
+    pub fn add_inspection(&self, serial: &str, inspection: &Inspection) -> Result<(), String> {
+        let json = serde_json::to_string(inspection).map_err(|e| e.to_string())?;
+        let txn = self.db.begin_write().map_err(db_error)?;
+        {
+            let mut table = txn.open_table(INSPECTIONS).map_err(db_error)?;
+            table
+                .insert((serial, inspection.taken.timestamp_millis()), json.as_str())
+                .map_err(db_error)?;
+        }
+        txn.commit().map_err(db_error)
+    }
+
+    /// Every intake inspection of the drive with this serial, oldest first.
+    pub fn inspections(&self, serial: &str) -> Result<Vec<Inspection>, String> {
+        let txn = self.db.begin_read().map_err(db_error)?;
+        let table = match txn.open_table(INSPECTIONS) {
+            Ok(table) => table,
+            Err(TableError::TableDoesNotExist(_)) => return Ok(Vec::new()),
+            Err(e) => return Err(db_error(e)),
+        };
+        let mut inspections = Vec::new();
+        for entry in table.range((serial, i64::MIN)..=(serial, i64::MAX)).map_err(db_error)? {
+            let (_, json) = entry.map_err(db_error)?;
+            match serde_json::from_str(json.value()) {
+                Ok(inspection) => inspections.push(inspection),
+                Err(e) => log::warn!("Skipping unreadable inspection for {}: {}", serial, e),
+            }
+        }
+        Ok(inspections)
+    }
+
+    /// Stores a reading and returns the change since the previous visit.
+    pub fn record_smart(&self, device: &str, data: &SmartData) -> Result<Option<SmartDelta>, String> {
+        let Some(serial) = data.serial_number.as_deref().filter(|s| !s.is_empty()) else {
+            return Ok(None);
+        };
+        let snapshot = Snapshot {
+            taken: Local::now(),
+            device: device.to_string(),
+            data: data.clone(),
+        };
+        let previous = self.smart_snapshots(serial)?;
+        self.add_smart(serial, &snapshot)?;
+        Ok(baseline(&previous, snapshot.taken).map(|before| smart_delta(before, data, &config::get().grading)))
+    }
+}
+
+/// `$XDG_DATA_HOME/electronics_tui_tester/history.redb`, unless the config says otherwise.
+pub fn db_path() -> PathBuf {
+    if let Some(path) = &config::get().history.path {
+        return path.clone();
+    }
+    dirs::data_local_dir()
+        .map(|dir| dir.join("electronics_tui_tester").join("history.redb"))
+        .unwrap_or_else(|| PathBuf::from("history.redb"))
+}
+
+/// Opened on first use. Another running instance holds the file lock, so
+/// history is then unavailable rather than fatal.
+static HISTORY: Lazy<Option<History>> = Lazy::new(|| match History::open(&db_path()) {
+    Ok(history) => Some(history),
+    Err(e) => {
+        log::error!("{}", e);
+        None
+    }
+});
+
+pub fn get() -> Option<&'static History> {
+    HISTORY.as_ref()
+}
+
+/// Records a SMART reading in the station history; `None` for a drive seen
+/// for the first time or when history is unavailable.
+pub fn record_smart(device: &str, data: &SmartData) -> Option<SmartDelta> {
+    get()?.record_smart(device, data).unwrap_or_else(|e| {
+        log::error!("{}", e);
+        None
+    })
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,165 @@
+use super::*;
+use crate::command::FixtureRunner;
+use crate::config::GradingConfig;
+use crate::inspect::{Filesystem, Partition, TableKind};
+use crate::wipe::certificate::Certificate;
+
+fn data(name: &str) -> SmartData {
+    SmartData::parse(&FixtureRunner::fixture(name)).unwrap()
+}
+
+fn snapshot(days_ago: i64, data: SmartData) -> Snapshot {
+    Snapshot {
+        taken: Local::now() - Duration::days(days_ago),
+        device: "/dev/sda".to_string(),
+        data,
+    }
+}
+
+/// The healthy SATA fixture after some more use and a few new bad sectors.
+fn worn(mut data: SmartData) -> SmartData {
+    data.power_on_time.as_mut().unwrap().hours += 312;
+    let table = &mut data.ata_smart_attributes.as_mut().unwrap().table;
+    table.iter_mut().find(|a| a.id == 5).unwrap().raw.value = 8;
+    table.iter_mut().find(|a| a.id == 197).unwrap().raw.value = 2;
+    data
+}
+
+#[test]
+fn snapshots_are_stored_per_serial_oldest_first() {
+    let dir = tempfile::tempdir().unwrap();
+    let history = History::open(&dir.path().join("history.redb")).unwrap();
+    assert!(history.smart_snapshots("ZFL1ABCD").unwrap().is_empty());
+
+    let hdd = data("smartctl_sata_hdd.stdout");
+    history.add_smart("ZFL1ABCD", &snapshot(1, worn(hdd.clone()))).unwrap();
+    history.add_smart("ZFL1ABCD", &snapshot(30, hdd)).unwrap();
+    history.add_smart("OTHER", &snapshot(2, data("smartctl_nvme.stdout"))).unwrap();
+
+    let snapshots = history.smart_snapshots("ZFL1ABCD").unwrap();
+    assert_eq!(snapshots.len(), 2);
+    assert!(snapshots[0].taken < snapshots[1].taken);
+    assert_eq!(snapshots[0].data.power_on_hours(), Some(10842));
+    assert_eq!(snapshots[1].data.power_on_hours(), Some(11154));
+}
+
+#[test]
+fn record_smart_compares_with_the_previous_visit() {
+    let dir = tempfile::tempdir().unwrap();
+    let history = History::open(&dir.path().join("history.redb")).unwrap();
+    let hdd = data("smartctl_sata_hdd.stdout");
+
+    assert_eq!(history.record_smart("/dev/sda", &hdd).unwrap(), None);
+    history.add_smart("ZFL1ABCD", &snapshot(30, hdd.clone())).unwrap();
+
+    let delta = history.record_smart("/dev/sda", &worn(hdd)).unwrap().unwrap();
+    assert_eq!(delta.hours_added, Some(312));
+    assert!(delta.degrading());
+    assert_eq!(history.smart_snapshots("ZFL1ABCD").unwrap().len(), 3);
+}
+
+#[test]
+fn drives_without_a_serial_are_not_recorded() {
+    let dir = tempfile::tempdir().unwrap();
+    let history = History::open(&dir.path().join("history.redb")).unwrap();
+    assert_eq!(history.record_smart("/dev/sdz", &SmartData::default()).unwrap(), None);
+    assert!(history.smart_snapshots("").unwrap().is_empty());
+}
+
+#[test]
+fn delta_lists_grown_wear_counters() {
+    let hdd = data("smartctl_sata_hdd.stdout");
+    let delta = smart_delta(&snapshot(30, hdd.clone()), &worn(hdd), &GradingConfig::default());
+
+    assert_eq!(delta.hours_added, Some(312));
+    assert_eq!(delta.grade_before, Grade::Great);
+    assert!(delta.grade_after > delta.grade_before);
+    assert_eq!(
+        delta.changes,
+        vec![
+            CounterChange { name: "Reallocated sectors", before: 0, after: 8 },
+            CounterChange { name: "Pending sectors", before: 0, after: 2 },
+        ]
+    );
+    assert!(delta.degrading());
+    assert!(delta.lines().contains(&"Reallocated sectors 0 → 8 (+8)".to_string()));
+}
+
+#[test]
+fn unchanged_drive_is_not_degrading() {
+    let nvme = data("smartctl_nvme.stdout");
+    let delta = smart_delta(&snapshot(7, nvme.clone()), &nvme, &GradingConfig::default());
+    assert!(delta.changes.is_empty());
+    assert!(!delta.degrading());
+    assert_eq!(delta.lines(), vec!["+0 power-on hours".to_string()]);
+}
+
+#[test]
+fn baseline_skips_reads_from_the_same_visit() {
+    let hdd = data("smartctl_sata_hdd.stdout");
+    let now = Local::now();
+    let mut recent = snapshot(0, hdd.clone());
+    recent.taken = now - Duration::minutes(10);
+    let snapshots = vec![snapshot(30, hdd.clone()), snapshot(5, hdd), recent];
+
+    let found = baseline(&snapshots, now).unwrap();
+    assert_eq!(found.taken, snapshots[1].taken);
+    assert!(baseline(&snapshots[2..], now).is_none());
+}
+
+#[test]
+fn certificates_are_kept_per_serial() {
+    let dir = tempfile::tempdir().unwrap();
+    let history = History::open(&dir.path().join("history.redb")).unwrap();
+    assert!(history.certificates("ZFL1ABCD").unwrap().is_empty());
+
+    let json = r#"{"id":"WC-20261018-100000-0A1B2C3D","issued_at":"2026-10-18T10:05:00+00:00","operator":"alice",
+        "device":"/dev/sdb","model":"ST2000DM008-2FR102","serial":"ZFL1ABCD","capacity_bytes":2000398934016,
+        "method":"Overwrite","nist_level":"Clear","passes":1,"patterns":["zeros"],
+        "started_at":"2026-10-18T08:00:00+00:00","finished_at":"2026-10-18T10:00:00+00:00","verified":true,
+        "verification":"All 1000 sampled sectors verified wiped","note":null,"smart_grade":"A",
+        "smart_power_on_hours":"12345","tool_version":"electronics_tui_tester 0.1.0","content_hash":""}"#;
+    let mut certificate: Certificate = serde_json::from_str(json).unwrap();
+    certificate.content_hash = certificate.compute_hash();
+    history.add_certificate(&certificate).unwrap();
+    let mut other = certificate.clone();
+    other.serial = "ZFL1ABCE".to_string();
+    history.add_certificate(&other).unwrap();
+
+    assert_eq!(history.certificates("ZFL1ABCD").unwrap(), vec![certificate]);
+    assert!(history.certificates("ZFL1ABC").unwrap().is_empty());
+}
+
+#[test]
+fn inspections_are_kept_per_serial() {
+    let dir = tempfile::tempdir().unwrap();
+    let history = History::open(&dir.path().join("history.redb")).unwrap();
+    assert!(history.inspections("ZFL1ABCD").unwrap().is_empty());
+
+    let inspection = Inspection {
+        device: "/dev/sdb".to_string(),
+        taken: Local::now(),
+        size_bytes: 2000398934016,
+        table: TableKind::Gpt,
+        partitions: vec![Partition {
+            number: 1,
+            start: 1 << 20,
+            size: 2000397885440,
+            kind: "Microsoft basic data".to_string(),
+            filesystem: Some(Filesystem::Ntfs),
+        }],
+        filesystem: None,
+        os: Vec::new(),
+        samples: 1024,
+        nonzero: 380,
+        unreadable: 0,
+    };
+    history.add_inspection("ZFL1ABCD", &inspection).unwrap();
+    history.add_inspection("ZFL1ABCE", &inspection).unwrap();
+
+    let stored = history.inspections("ZFL1ABCD").unwrap();
+    assert_eq!(stored.len(), 1);
+    assert_eq!(stored[0].partitions, inspection.partitions);
+    assert_eq!(stored[0].taken, inspection.taken);
+    assert!(stored[0].holds_data());
+}
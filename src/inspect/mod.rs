@@ -0,0 +1,662 @@
+use chrono::{DateTime, Local};
+use once_cell::sync::Lazy;
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Gauge, Paragraph, Row, Table, Wrap},
+    Frame,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io;
+use std::sync::Mutex;
+
+use crate::history;
+use crate::jobs::{self, cancelled_error, JobId};
+use crate::session::{self, TestResult, TestStatus};
+use crate::smart::{format_capacity, Drive};
+use crate::surface_scan::{DeviceSurface, Surface};
+use crate::theme::bordered_block;
+use crate::wipe::overwrite::{self, SAMPLE_SIZE};
+
+/// Blocks of `SAMPLE_SIZE` read for the non-zero estimate.
+pub const SAMPLES: usize = 1024;
+/// Bytes read from the start of a volume to identify it.
+const HEAD: usize = 4096;
+/// GPT entries past this are ignored; tools create 128.
+const MAX_GPT_ENTRIES: u32 = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TableKind {
+    None,
+    Mbr,
+    Gpt,
+}
+
+impl TableKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            TableKind::None => "none",
+            TableKind::Mbr => "MBR",
+            TableKind::Gpt => "GPT",
+        }
+    }
+}
+
+/// Filesystems and encrypted containers recognised by their signature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Filesystem {
+    Ntfs,
+    BitLocker,
+    ExFat,
+    Fat,
+    Ext4,
+    Ext,
+    Apfs,
+    Luks,
+}
+
+impl Filesystem {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Filesystem::Ntfs => "NTFS",
+            Filesystem::BitLocker => "BitLocker",
+            Filesystem::ExFat => "exFAT",
+            Filesystem::Fat => "FAT",
+            Filesystem::Ext4 => "ext4",
+            Filesystem::Ext => "ext2/3",
+            Filesystem::Apfs => "APFS",
+            Filesystem::Luks => "LUKS",
+        }
+    }
+}
+
+fn le16(b: &[u8], at: usize) -> u16 {
+    b.get(at..at + 2).map_or(0, |s| u16::from_le_bytes([s[0], s[1]]))
+}
+
+fn le32(b: &[u8], at: usize) -> u32 {
+    b.get(at..at + 4)
+        .map_or(0, |s| u32::from_le_bytes(s.try_into().unwrap()))
+}
+
+fn le64(b: &[u8], at: usize) -> u64 {
+    b.get(at..at + 8)
+        .map_or(0, |s| u64::from_le_bytes(s.try_into().unwrap()))
+}
+
+/// Identifies a volume from its first bytes (`HEAD` of them for ext).
+pub fn detect_filesystem(head: &[u8]) -> Option<Filesystem> {
+    let at = |offset: usize, magic: &[u8]| head.get(offset..offset + magic.len()) == Some(magic);
+    if at(0, b"LUKS\xba\xbe") {
+        Some(Filesystem::Luks)
+    } else if at(3, b"-FVE-FS-") {
+        Some(Filesystem::BitLocker)
+    } else if at(3, b"NTFS    ") {
+        Some(Filesystem::Ntfs)
+    } else if at(3, b"EXFAT   ") {
+        Some(Filesystem::ExFat)
+    } else if at(32, b"NXSB") {
+        Some(Filesystem::Apfs)
+    } else if at(82, b"FAT32   ") || at(54, b"FAT16   ") || at(54, b"FAT12   ") {
+        Some(Filesystem::Fat)
+    } else if le16(head, 1024 + 56) == 0xef53 {
+        // The extents feature is what mkfs.ext4 sets and ext2/3 lack.
+        match le32(head, 1024 + 0x60) & 0x40 {
+            0 => Some(Filesystem::Ext),
+            _ => Some(Filesystem::Ext4),
+        }
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Partition {
+    pub number: u32,
+    /// Offset and length in bytes.
+    pub start: u64,
+    pub size: u64,
+    /// Type as the table names it, e.g. "Microsoft basic data" or "Linux (0x83)".
+    pub kind: String,
+    pub filesystem: Option<Filesystem>,
+}
+
+const GPT_TYPES: &[(&str, &str)] = &[
+    ("C12A7328-F81F-11D2-BA4B-00A0C93EC93B", "EFI system"),
+    ("E3C9E316-0B5C-4DB8-817D-F92DF00215AE", "Microsoft reserved"),
+    ("EBD0A0A2-B9E5-4433-87C0-68B6B72699C7", "Microsoft basic data"),
+    ("DE94BBA4-06D1-4D40-A16A-BFD50179D6AC", "Windows recovery"),
+    ("0FC63DAF-8483-4772-8E79-3D69D8477DE4", "Linux filesystem"),
+    ("4F68BCE3-E8CD-4DB1-96E7-FBCAF984B709", "Linux root (x86-64)"),
+    ("0657FD6D-A4AB-43C4-84E5-0933C84B4F4F", "Linux swap"),
+    ("E6D6D379-F507-44C2-A23C-238F2A3DF928", "Linux LVM"),
+    ("CA7D7CCB-63ED-4C53-861C-1742536059CC", "Linux LUKS"),
+    ("7C3457EF-0000-11AA-AA11-00306543ECAC", "Apple APFS"),
+    ("48465300-0000-11AA-AA11-00306543ECAC", "Apple HFS+"),
+];
+
+const MBR_TYPES: &[(u8, &str)] = &[
+    (0x01, "FAT12"),
+    (0x04, "FAT16"),
+    (0x05, "Extended"),
+    (0x06, "FAT16"),
+    (0x07, "NTFS/exFAT"),
+    (0x0b, "FAT32"),
+    (0x0c, "FAT32"),
+    (0x0e, "FAT16"),
+    (0x0f, "Extended"),
+    (0x27, "Windows recovery"),
+    (0x82, "Linux swap"),
+    (0x83, "Linux"),
+    (0x8e, "Linux LVM"),
+    (0xaf, "Apple HFS+"),
+    (0xee, "GPT protective"),
+    (0xef, "EFI system"),
+];
+
+/// GUIDs are stored with their first three fields little-endian.
+fn guid_text(b: &[u8]) -> String {
+    let tail: String = b[10..16].iter().map(|x| format!("{:02X}", x)).collect();
+    format!(
+        "{:08X}-{:04X}-{:04X}-{:02X}{:02X}-{}",
+        le32(b, 0),
+        le16(b, 4),
+        le16(b, 6),
+        b[8],
+        b[9],
+        tail
+    )
+}
+
+fn read(surface: &dyn Surface, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+    let len = len.min(surface.size().saturating_sub(offset) as usize);
+    let mut buf = vec![0u8; len];
+    surface.read_at(&mut buf, offset)?;
+    Ok(buf)
+}
+
+fn read_gpt(surface: &dyn Surface, header: &[u8], sector: u64) -> io::Result<Vec<Partition>> {
+    let count = le32(header, 80).min(MAX_GPT_ENTRIES) as usize;
+    let entry_size = le32(header, 84) as usize;
+    if !(128..=4096).contains(&entry_size) {
+        return Ok(Vec::new());
+    }
+    let entries = read(surface, le64(header, 72).saturating_mul(sector), count * entry_size)?;
+    Ok(entries
+        .chunks_exact(entry_size)
+        .enumerate()
+        .filter(|(_, entry)| entry[..16].iter().any(|b| *b != 0))
+        .map(|(i, entry)| {
+            let guid = guid_text(&entry[..16]);
+            let (first, last) = (le64(entry, 32), le64(entry, 40));
+            Partition {
+                number: i as u32 + 1,
+                start: first.saturating_mul(sector),
+                size: last.saturating_add(1).saturating_sub(first).saturating_mul(sector),
+                kind: GPT_TYPES
+                    .iter()
+                    .find(|(known, _)| *known == guid)
+                    .map_or(guid.clone(), |(_, name)| name.to_string()),
+                filesystem: None,
+            }
+        })
+        .collect())
+}
+
+/// The four primary MBR entries; `None` when sector 0 is not a valid MBR.
+/// Logical partitions inside an extended one are not followed.
+fn read_mbr(mbr: &[u8]) -> Option<Vec<Partition>> {
+    if mbr.get(510..512) != Some(&[0x55, 0xaa][..]) {
+        return None;
+    }
+    let entries: Vec<&[u8]> = mbr[446..510].chunks_exact(16).collect();
+    if entries.iter().any(|e| e[0] != 0 && e[0] != 0x80) {
+        return None;
+    }
+    let partitions: Vec<Partition> = entries
+        .iter()
+        .enumerate()
+        .filter(|(_, e)| e[4] != 0 && le32(e, 12) != 0)
+        .map(|(i, e)| Partition {
+            number: i as u32 + 1,
+            start: le32(e, 8) as u64 * 512,
+            size: le32(e, 12) as u64 * 512,
+            kind: match MBR_TYPES.iter().find(|(id, _)| *id == e[4]) {
+                Some((id, name)) => format!("{} (0x{:02x})", name, id),
+                None => format!("MBR type 0x{:02x}", e[4]),
+            },
+            filesystem: None,
+        })
+        .collect();
+    (!partitions.is_empty()).then_some(partitions)
+}
+
+/// Finds the partition table. The logical sector size is taken from where the
+/// GPT header sits; MBR entries are read as 512-byte sectors.
+pub fn read_partitions(surface: &dyn Surface) -> io::Result<(TableKind, Vec<Partition>)> {
+    for sector in [512, 4096] {
+        let header = read(surface, sector, 92)?;
+        if header.len() == 92 && header.starts_with(b"EFI PART") {
+            return Ok((TableKind::Gpt, read_gpt(surface, &header, sector)?));
+        }
+    }
+    match read_mbr(&read(surface, 0, 512)?) {
+        Some(partitions) => Ok((TableKind::Mbr, partitions)),
+        None => Ok((TableKind::None, Vec::new())),
+    }
+}
+
+/// Partition types and volumes that give an operating system away.
+const OS_PARTITIONS: &[(&str, &str)] = &[
+    ("Microsoft reserved", "Windows"),
+    ("Windows recovery", "Windows"),
+    ("NTFS/exFAT", "Windows"),
+    ("Apple APFS", "macOS"),
+    ("Apple HFS+", "macOS"),
+    ("Linux root", "Linux"),
+    ("Linux swap", "Linux"),
+];
+
+/// Operating systems the disk shows traces of, each with the evidence. This
+/// is a guess from partition types and volume signatures; no files are read.
+pub fn detect_os(partitions: &[Partition], whole: Option<Filesystem>) -> Vec<String> {
+    let mut found: Vec<(&str, String)> = Vec::new();
+    let mut add = |os: &'static str, evidence: String| {
+        if !found.iter().any(|(known, _)| *known == os) {
+            found.push((os, evidence));
+        }
+    };
+    for partition in partitions {
+        if let Some((_, os)) = OS_PARTITIONS.iter().find(|(kind, _)| partition.kind.starts_with(kind)) {
+            add(os, format!("{} partition", partition.kind));
+        }
+    }
+    for filesystem in partitions.iter().filter_map(|p| p.filesystem).chain(whole) {
+        match filesystem {
+            Filesystem::BitLocker | Filesystem::Ntfs => add("Windows", format!("{} volume", filesystem.label())),
+            Filesystem::Apfs => add("macOS", "APFS container".to_string()),
+            Filesystem::Ext4 | Filesystem::Ext | Filesystem::Luks => {
+                add("Linux", format!("{} volume", filesystem.label()))
+            }
+            _ => {}
+        }
+    }
+    found
+        .into_iter()
+        .map(|(os, evidence)| format!("{} ({})", os, evidence))
+        .collect()
+}
+
+/// What an incoming drive holds, taken before it is wiped.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Inspection {
+    pub device: String,
+    pub taken: DateTime<Local>,
+    pub size_bytes: u64,
+    pub table: TableKind,
+    pub partitions: Vec<Partition>,
+    /// A volume written straight onto the disk, without a partition table.
+    pub filesystem: Option<Filesystem>,
+    pub os: Vec<String>,
+    pub samples: usize,
+    pub nonzero: usize,
+    pub unreadable: usize,
+}
+
+impl Inspection {
+    pub fn filesystems(&self) -> Vec<Filesystem> {
+        let mut found = Vec::new();
+        for filesystem in self
+            .filesystem
+            .into_iter()
+            .chain(self.partitions.iter().filter_map(|p| p.filesystem))
+        {
+            if !found.contains(&filesystem) {
+                found.push(filesystem);
+            }
+        }
+        found
+    }
+
+    pub fn nonzero_percent(&self) -> f64 {
+        match self.samples - self.unreadable {
+            0 => 0.0,
+            read => self.nonzero as f64 * 100.0 / read as f64,
+        }
+    }
+
+    /// Estimated bytes holding something other than zeros.
+    pub fn nonzero_bytes(&self) -> u64 {
+        (self.size_bytes as f64 * self.nonzero_percent() / 100.0) as u64
+    }
+
+    pub fn holds_data(&self) -> bool {
+        self.table != TableKind::None || self.filesystem.is_some() || self.nonzero > 0
+    }
+
+    pub fn summary(&self) -> String {
+        let sampled = format!(
+            "{} of {} sampled blocks non-zero ({:.1}%, about {})",
+            self.nonzero,
+            self.samples - self.unreadable,
+            self.nonzero_percent(),
+            format_capacity(self.nonzero_bytes())
+        );
+        if !self.holds_data() {
+            return format!("Blank: no partition table or filesystem, {}", sampled);
+        }
+        let mut parts = Vec::new();
+        if self.table != TableKind::None {
+            parts.push(format!(
+                "{} table with {} partitions",
+                self.table.label(),
+                self.partitions.len()
+            ));
+        }
+        let filesystems: Vec<&str> = self.filesystems().iter().map(Filesystem::label).collect();
+        if !filesystems.is_empty() {
+            parts.push(filesystems.join(", "));
+        }
+        parts.extend(self.os.iter().cloned());
+        parts.push(sampled);
+        format!("Holds data: {}", parts.join("; "))
+    }
+}
+
+/// Reads the partition table, volume signatures and `SAMPLES` spread-out
+/// blocks. Nothing is written. `progress` returns false to stop.
+pub fn inspect(
+    surface: &dyn Surface,
+    device: &str,
+    seed: u64,
+    progress: &mut dyn FnMut(u16) -> bool,
+) -> io::Result<Inspection> {
+    let taken = Local::now();
+    let head = read(surface, 0, HEAD)?;
+    let filesystem = detect_filesystem(&head);
+    let (table, mut partitions) = match filesystem {
+        Some(_) => (TableKind::None, Vec::new()),
+        None => read_partitions(surface)?,
+    };
+    for partition in &mut partitions {
+        partition.filesystem = match read(surface, partition.start, HEAD) {
+            Ok(head) => detect_filesystem(&head),
+            Err(e) => {
+                log::warn!("{}: cannot read partition {}: {}", device, partition.number, e);
+                None
+            }
+        };
+    }
+
+    let offsets = overwrite::sample_offsets(surface.size(), SAMPLES, seed);
+    let (mut nonzero, mut unreadable) = (0, 0);
+    for (i, &offset) in offsets.iter().enumerate() {
+        if !progress((i * 100 / offsets.len()) as u16) {
+            return Err(cancelled_error());
+        }
+        match read(surface, offset, SAMPLE_SIZE as usize) {
+            Ok(block) if block.iter().any(|b| *b != 0) => nonzero += 1,
+            Ok(_) => {}
+            Err(_) => unreadable += 1,
+        }
+    }
+    progress(100);
+
+    Ok(Inspection {
+        device: device.to_string(),
+        taken,
+        size_bytes: surface.size(),
+        os: detect_os(&partitions, filesystem),
+        table,
+        partitions,
+        filesystem,
+        samples: offsets.len(),
+        nonzero,
+        unreadable,
+    })
+}
+
+/// A blank drive passes; one still holding data is a warning to wipe it.
+pub fn inspection_result(inspection: &Inspection) -> TestResult {
+    let mut raw = format!("{}\n", inspection.summary());
+    for p in &inspection.partitions {
+        raw.push_str(&format!(
+            "partition {}: {} at {} bytes, {} bytes, {}\n",
+            p.number,
+            p.kind,
+            p.start,
+            p.size,
+            p.filesystem.map_or("no known filesystem", |f| f.label())
+        ));
+    }
+    let filesystems: Vec<&str> = inspection.filesystems().iter().map(Filesystem::label).collect();
+    TestResult::new("Inspection", &inspection.device)
+        .started(inspection.taken)
+        .status(if inspection.holds_data() {
+            TestStatus::Warn
+        } else {
+            TestStatus::Pass
+        })
+        .metric("partition_table", inspection.table.label())
+        .metric("partitions", inspection.partitions.len())
+        .metric("filesystems", filesystems.join(", "))
+        .metric("os", inspection.os.join(", "))
+        .metric("samples", inspection.samples)
+        .metric("nonzero_samples", inspection.nonzero)
+        .metric("unreadable_samples", inspection.unreadable)
+        .metric("nonzero_percent", format!("{:.1}", inspection.nonzero_percent()))
+        .raw_output(raw)
+        .finish()
+}
+
+#[derive(Debug, Clone, Default)]
+struct InspectState {
+    device: String,
+    percent: u16,
+    report: Option<Inspection>,
+    error: Option<String>,
+}
+
+static INSPECT_JOB: Lazy<Mutex<Option<JobId>>> = Lazy::new(|| Mutex::new(None));
+static INSPECT_STATE: Lazy<Mutex<InspectState>> = Lazy::new(|| Mutex::new(InspectState::default()));
+/// Latest inspection per drive serial (or path, without one), looked up in
+/// the history the first time the disk menu shows the drive.
+static INTAKE: Lazy<Mutex<HashMap<String, Option<Inspection>>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+fn intake_key(drive: &Drive) -> String {
+    drive.serial.clone().unwrap_or_else(|| drive.path.clone())
+}
+
+/// The drive's most recent intake inspection, from this run or the history.
+pub fn latest(drive: &Drive) -> Option<Inspection> {
+    let mut intake = INTAKE.lock().unwrap();
+    intake
+        .entry(intake_key(drive))
+        .or_insert_with(|| {
+            let serial = drive.serial.as_deref()?;
+            history::get()?.inspections(serial).ok()?.pop()
+        })
+        .clone()
+}
+
+pub fn is_running() -> bool {
+    INSPECT_JOB.lock().unwrap().is_some_and(jobs::is_running)
+}
+
+/// Starts a read-only inspection of `drive` unless one is already running.
+pub fn start_inspection(drive: &Drive) {
+    if is_running() {
+        log::warn!("An inspection is already running");
+        return;
+    }
+    let device = drive.path.clone();
+    let surface = match DeviceSurface::open(&device) {
+        Ok(surface) => surface,
+        Err(e) => {
+            *INSPECT_STATE.lock().unwrap() = InspectState {
+                device: device.clone(),
+                error: Some(format!("Cannot open {}: {}", device, e)),
+                ..InspectState::default()
+            };
+            return;
+        }
+    };
+    *INSPECT_STATE.lock().unwrap() = InspectState {
+        device: device.clone(),
+        ..InspectState::default()
+    };
+    log::info!("Inspecting {} for leftover data", device);
+
+    let serial = drive.serial.clone();
+    let key = intake_key(drive);
+    let id = jobs::spawn(&format!("Inspect {}", device), move |ctx| async move {
+        let token = ctx.token();
+        let job = ctx.clone();
+        let name = device.clone();
+        let outcome = tokio::task::spawn_blocking(move || {
+            let seed = Local::now().timestamp_nanos_opt().unwrap_or_default() as u64;
+            inspect(&surface, &name, seed, &mut |percent| {
+                job.progress(percent);
+                INSPECT_STATE.lock().unwrap().percent = percent;
+                !token.is_cancelled()
+            })
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+
+        match outcome {
+            Ok(report) => {
+                let summary = report.summary();
+                log::info!("Inspection of {}: {}", device, summary);
+                session::record(inspection_result(&report));
+                match (history::get(), &serial) {
+                    (Some(history), Some(serial)) => {
+                        if let Err(e) = history.add_inspection(serial, &report) {
+                            log::error!("{}", e);
+                        }
+                    }
+                    _ => log::warn!("Inspection of {} was not stored in the drive history", device),
+                }
+                INTAKE.lock().unwrap().insert(key, Some(report.clone()));
+                INSPECT_STATE.lock().unwrap().report = Some(report);
+                Ok(summary)
+            }
+            Err(e) => {
+                let message = format!("Inspection of {} stopped: {}", device, e);
+                log::warn!("{}", message);
+                INSPECT_STATE.lock().unwrap().error = Some(message.clone());
+                Err(message)
+            }
+        }
+    });
+    *INSPECT_JOB.lock().unwrap() = Some(id);
+}
+
+pub fn cancel_inspection() {
+    if let Some(id) = *INSPECT_JOB.lock().unwrap() {
+        jobs::cancel(id);
+    }
+}
+
+pub fn draw_inspection(f: &mut Frame) {
+    let state = INSPECT_STATE.lock().unwrap().clone();
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Length(3),
+            Constraint::Min(5),
+            Constraint::Length(7),
+        ])
+        .split(f.area());
+
+    let size = state.report.as_ref().map(|r| format_capacity(r.size_bytes));
+    let header = Paragraph::new(match size {
+        Some(size) => format!("{} ({}), read only", state.device, size),
+        None => format!("{}, read only", state.device),
+    })
+    .block(bordered_block("Intake Inspection"));
+    f.render_widget(header, chunks[0]);
+
+    let gauge = Gauge::default()
+        .block(bordered_block("Sampling"))
+        .gauge_style(Style::default().fg(Color::Green).bg(Color::Black))
+        .percent(state.percent.min(100));
+    f.render_widget(gauge, chunks[1]);
+
+    let (table_title, rows) = match &state.report {
+        Some(report) => (
+            format!("Partitions ({})", report.table.label()),
+            report
+                .partitions
+                .iter()
+                .map(|p| {
+                    Row::new(vec![
+                        p.number.to_string(),
+                        format_capacity(p.start),
+                        format_capacity(p.size),
+                        p.kind.clone(),
+                        p.filesystem.map_or("", |fs| fs.label()).to_string(),
+                    ])
+                })
+                .collect(),
+        ),
+        None => ("Partitions".to_string(), Vec::new()),
+    };
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(3),
+            Constraint::Length(10),
+            Constraint::Length(10),
+            Constraint::Percentage(50),
+            Constraint::Length(10),
+        ],
+    )
+    .header(
+        Row::new(vec!["#", "Start", "Size", "Type", "Filesystem"])
+            .style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+    )
+    .block(bordered_block(&table_title));
+    f.render_widget(table, chunks[2]);
+
+    let mut lines = Vec::new();
+    match (&state.error, &state.report) {
+        (Some(e), _) => lines.push(Line::from(Span::styled(e.clone(), Style::default().fg(Color::Red)))),
+        (None, Some(report)) => {
+            let color = if report.holds_data() {
+                Color::Yellow
+            } else {
+                Color::Green
+            };
+            lines.push(Line::from(Span::styled(report.summary(), Style::default().fg(color))));
+            if let Some(filesystem) = report.filesystem {
+                lines.push(Line::from(format!("Whole-disk volume: {}", filesystem.label())));
+            }
+            let os = if report.os.is_empty() {
+                "none detected".to_string()
+            } else {
+                report.os.join(", ")
+            };
+            lines.push(Line::from(format!("Operating systems: {}", os)));
+        }
+        (None, None) => lines.push(Line::from(Span::styled(
+            "Inspecting...",
+            Style::default().fg(Color::Cyan),
+        ))),
+    }
+    lines.push(Line::from(
+        "c: cancel, Esc: back (the inspection keeps running), j: jobs",
+    ));
+    let result = Paragraph::new(lines)
+        .block(bordered_block("Result"))
+        .wrap(Wrap { trim: true });
+    f.render_widget(result, chunks[3]);
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,202 @@
+use super::*;
+use std::fs::File;
+use std::os::unix::fs::FileExt;
+
+const MIB: u64 = 1 << 20;
+
+/// A sparse image file that tests write signatures into.
+struct Image {
+    _dir: tempfile::TempDir,
+    path: String,
+    file: File,
+}
+
+impl Image {
+    fn new(size: u64) -> Self {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("disk.img");
+        let file = File::create(&path).unwrap();
+        file.set_len(size).unwrap();
+        Self {
+            path: path.to_string_lossy().into_owned(),
+            _dir: dir,
+            file,
+        }
+    }
+
+    fn put(&self, offset: u64, bytes: &[u8]) {
+        self.file.write_all_at(bytes, offset).unwrap();
+    }
+
+    fn inspect(&self) -> Inspection {
+        inspect(&DeviceSurface::open(&self.path).unwrap(), "disk.img", 7, &mut |_| true).unwrap()
+    }
+
+    fn ntfs(&self, offset: u64) {
+        self.put(offset + 3, b"NTFS    ");
+    }
+
+    fn ext4(&self, offset: u64) {
+        self.put(offset + 1024 + 56, &0xef53u16.to_le_bytes());
+        self.put(offset + 1024 + 0x60, &0x2c2u32.to_le_bytes());
+    }
+}
+
+/// On-disk bytes of a GUID written in its usual text form.
+fn guid_bytes(text: &str) -> [u8; 16] {
+    let hex: String = text.chars().filter(|c| *c != '-').collect();
+    let mut b: Vec<u8> = (0..16)
+        .map(|i| u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).unwrap())
+        .collect();
+    b[0..4].reverse();
+    b[4..6].reverse();
+    b[6..8].reverse();
+    b.try_into().unwrap()
+}
+
+fn gpt_entry(kind: &str, first: u64, last: u64) -> Vec<u8> {
+    let mut entry = vec![0u8; 128];
+    entry[..16].copy_from_slice(&guid_bytes(kind));
+    entry[16] = 1;
+    entry[32..40].copy_from_slice(&first.to_le_bytes());
+    entry[40..48].copy_from_slice(&last.to_le_bytes());
+    entry
+}
+
+fn mbr_entry(kind: u8, start: u32, sectors: u32) -> [u8; 16] {
+    let mut entry = [0u8; 16];
+    entry[4] = kind;
+    entry[8..12].copy_from_slice(&start.to_le_bytes());
+    entry[12..16].copy_from_slice(&sectors.to_le_bytes());
+    entry
+}
+
+fn windows_image() -> Image {
+    let image = Image::new(64 * MIB);
+    image.put(446, &mbr_entry(0xee, 1, u32::MAX));
+    image.put(510, &[0x55, 0xaa]);
+
+    let mut header = vec![0u8; 92];
+    header[..8].copy_from_slice(b"EFI PART");
+    header[72..80].copy_from_slice(&2u64.to_le_bytes());
+    header[80..84].copy_from_slice(&128u32.to_le_bytes());
+    header[84..88].copy_from_slice(&128u32.to_le_bytes());
+    image.put(512, &header);
+
+    let entries = [
+        gpt_entry("C12A7328-F81F-11D2-BA4B-00A0C93EC93B", 2048, 4095),
+        gpt_entry("E3C9E316-0B5C-4DB8-817D-F92DF00215AE", 4096, 8191),
+        gpt_entry("EBD0A0A2-B9E5-4433-87C0-68B6B72699C7", 8192, 98303),
+        gpt_entry("DE94BBA4-06D1-4D40-A16A-BFD50179D6AC", 98304, 129023),
+    ];
+    image.put(1024, &entries.concat());
+    image.put(2048 * 512 + 82, b"FAT32   ");
+    image.ntfs(8192 * 512);
+    image.ntfs(98304 * 512);
+    image
+}
+
+#[test]
+fn blank_drive_passes() {
+    let inspection = Image::new(16 * MIB).inspect();
+    assert_eq!(inspection.table, TableKind::None);
+    assert!(inspection.partitions.is_empty() && inspection.os.is_empty());
+    assert_eq!((inspection.samples, inspection.nonzero), (SAMPLES, 0));
+    assert!(!inspection.holds_data());
+    assert!(inspection.summary().starts_with("Blank"));
+    assert_eq!(inspection_result(&inspection).status, TestStatus::Pass);
+}
+
+#[test]
+fn reads_gpt_partitions_and_detects_windows() {
+    let inspection = windows_image().inspect();
+    assert_eq!(inspection.table, TableKind::Gpt);
+    let kinds: Vec<&str> = inspection.partitions.iter().map(|p| p.kind.as_str()).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            "EFI system",
+            "Microsoft reserved",
+            "Microsoft basic data",
+            "Windows recovery"
+        ]
+    );
+    assert_eq!(inspection.partitions[2].start, 8192 * 512);
+    assert_eq!(inspection.partitions[2].size, 90112 * 512);
+    let filesystems: Vec<Option<Filesystem>> = inspection.partitions.iter().map(|p| p.filesystem).collect();
+    assert_eq!(
+        filesystems,
+        vec![
+            Some(Filesystem::Fat),
+            None,
+            Some(Filesystem::Ntfs),
+            Some(Filesystem::Ntfs)
+        ]
+    );
+    assert_eq!(inspection.os, vec!["Windows (Microsoft reserved partition)"]);
+    assert!(inspection.nonzero > 0);
+
+    let result = inspection_result(&inspection);
+    assert_eq!(result.status, TestStatus::Warn);
+    assert_eq!(result.metrics["partition_table"], "GPT");
+    assert_eq!(result.metrics["filesystems"], "FAT, NTFS");
+}
+
+#[test]
+fn reads_mbr_partitions_with_linux_volumes() {
+    let image = Image::new(32 * MIB);
+    image.put(446, &mbr_entry(0x83, 2048, 20480));
+    image.put(462, &mbr_entry(0x83, 22528, 20480));
+    image.put(510, &[0x55, 0xaa]);
+    image.ext4(2048 * 512);
+    image.put(22528 * 512, b"LUKS\xba\xbe");
+
+    let inspection = image.inspect();
+    assert_eq!(inspection.table, TableKind::Mbr);
+    assert_eq!(inspection.partitions.len(), 2);
+    assert_eq!(inspection.partitions[0].kind, "Linux (0x83)");
+    assert_eq!(inspection.filesystems(), vec![Filesystem::Ext4, Filesystem::Luks]);
+    assert_eq!(inspection.os, vec!["Linux (ext4 volume)"]);
+}
+
+#[test]
+fn ntfs_on_mbr_counts_as_windows() {
+    let image = Image::new(32 * MIB);
+    image.put(446, &mbr_entry(0x07, 2048, 40960));
+    image.put(510, &[0x55, 0xaa]);
+    image.ntfs(2048 * 512);
+
+    let inspection = image.inspect();
+    assert_eq!(inspection.partitions[0].filesystem, Some(Filesystem::Ntfs));
+    assert_eq!(inspection.os, vec!["Windows (NTFS/exFAT (0x07) partition)"]);
+    assert_eq!(detect_os(&[], Some(Filesystem::Ntfs)), vec!["Windows (NTFS volume)"]);
+}
+
+#[test]
+fn whole_disk_volumes_are_recognised() {
+    let mut head = vec![0u8; HEAD];
+    head[3..11].copy_from_slice(b"-FVE-FS-");
+    assert_eq!(detect_filesystem(&head), Some(Filesystem::BitLocker));
+
+    let mut head = vec![0u8; HEAD];
+    head[32..36].copy_from_slice(b"NXSB");
+    assert_eq!(detect_filesystem(&head), Some(Filesystem::Apfs));
+    assert_eq!(detect_os(&[], Some(Filesystem::Apfs)), vec!["macOS (APFS container)"]);
+
+    // An NTFS volume boot sector also ends in 55 AA but is not an MBR.
+    let image = Image::new(8 * MIB);
+    image.ntfs(0);
+    image.put(510, &[0x55, 0xaa]);
+    let inspection = image.inspect();
+    assert_eq!(inspection.table, TableKind::None);
+    assert_eq!(inspection.filesystem, Some(Filesystem::Ntfs));
+    assert!(inspection.holds_data());
+}
+
+#[test]
+fn cancelled_inspection_stops() {
+    let image = Image::new(8 * MIB);
+    let surface = DeviceSurface::open(&image.path).unwrap();
+    let error = inspect(&surface, "disk.img", 7, &mut |percent| percent < 50).unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::Interrupted);
+}
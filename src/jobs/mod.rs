@@ -0,0 +1,436 @@
+use chrono::{DateTime, Local};
+use once_cell::sync::Lazy;
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span, Text},
+    widgets::{Paragraph, Row, Table, TableState, Wrap},
+    Frame,
+};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::io;
+use std::process::Stdio;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::{Child, Command};
+use tokio::runtime::Runtime;
+use tokio_util::sync::CancellationToken;
+
+use crate::command::CommandOutput;
+use crate::theme::{bordered_block, highlight_style};
+
+pub type JobId = u64;
+
+/// Output lines kept per job for the jobs panel.
+const OUTPUT_LINES: usize = 500;
+/// Finished jobs kept in the panel before the oldest are dropped.
+const FINISHED_JOBS: usize = 50;
+/// How long a cancelled command gets to exit after SIGTERM before SIGKILL.
+const TERMINATE_GRACE: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobState {
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+impl JobState {
+    pub fn label(&self) -> &'static str {
+        match self {
+            JobState::Running => "RUNNING",
+            JobState::Succeeded => "DONE",
+            JobState::Failed => "FAILED",
+            JobState::Cancelled => "CANCELLED",
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            JobState::Running => Color::Cyan,
+            JobState::Succeeded => Color::Green,
+            JobState::Failed => Color::Red,
+            JobState::Cancelled => Color::Yellow,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct JobInfo {
+    pub id: JobId,
+    pub name: String,
+    pub state: JobState,
+    /// Percent complete, when the job can tell.
+    pub progress: Option<u16>,
+    /// Final message once the job has finished.
+    pub summary: String,
+    pub output: VecDeque<String>,
+    pub started_at: DateTime<Local>,
+    pub finished_at: Option<DateTime<Local>>,
+}
+
+struct JobEntry {
+    info: JobInfo,
+    cancel: CancellationToken,
+}
+
+static RUNTIME: Lazy<Runtime> = Lazy::new(|| {
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .thread_name("job")
+        .build()
+        .expect("failed to start the job runtime")
+});
+static JOBS: Lazy<Mutex<Vec<JobEntry>>> = Lazy::new(|| Mutex::new(Vec::new()));
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+static SELECTED_JOB: Lazy<Mutex<usize>> = Lazy::new(|| Mutex::new(0));
+
+fn with_job(id: JobId, update: impl FnOnce(&mut JobInfo)) {
+    if let Some(entry) = JOBS.lock().unwrap().iter_mut().find(|e| e.info.id == id) {
+        update(&mut entry.info);
+    }
+}
+
+pub fn cancelled_error() -> io::Error {
+    io::Error::new(io::ErrorKind::Interrupted, "cancelled by operator")
+}
+
+/// Handle a running job uses to report progress and watch for cancellation.
+#[derive(Clone)]
+pub struct JobContext {
+    id: JobId,
+    cancel: CancellationToken,
+}
+
+impl JobContext {
+    pub fn progress(&self, percent: u16) {
+        with_job(self.id, |job| job.progress = Some(percent.min(100)));
+    }
+
+    pub fn output(&self, line: impl Into<String>) {
+        let line = line.into();
+        with_job(self.id, |job| {
+            if job.output.len() >= OUTPUT_LINES {
+                job.output.pop_front();
+            }
+            job.output.push_back(line);
+        });
+    }
+
+    /// Token for blocking work that has to poll for cancellation itself.
+    pub fn token(&self) -> CancellationToken {
+        self.cancel.clone()
+    }
+
+    /// Sleeps, returning early with an `Interrupted` error if cancelled.
+    pub async fn sleep(&self, duration: Duration) -> io::Result<()> {
+        tokio::select! {
+            _ = self.cancel.cancelled() => Err(cancelled_error()),
+            _ = tokio::time::sleep(duration) => Ok(()),
+        }
+    }
+
+    /// Runs a command in its own process group, streaming each output line to
+    /// the job. Cancelling the job terminates the whole group, so children of a
+    /// shell script (gphoto2, pacman, ...) stop too.
+    pub async fn run_command(&self, program: &str, args: &[&str]) -> io::Result<CommandOutput> {
+        let mut child = Command::new(program)
+            .args(args)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .process_group(0)
+            .kill_on_drop(true)
+            .spawn()?;
+        log::debug!("job {} started {} (pid {:?})", self.id, program, child.id());
+
+        let mut stdout_lines = BufReader::new(child.stdout.take().expect("piped stdout")).lines();
+        let mut stderr_lines = BufReader::new(child.stderr.take().expect("piped stderr")).lines();
+        let mut stdout = String::new();
+        let mut stderr = String::new();
+        let mut stdout_open = true;
+        let mut stderr_open = true;
+
+        while stdout_open || stderr_open {
+            tokio::select! {
+                _ = self.cancel.cancelled() => {
+                    terminate(&mut child).await;
+                    return Err(cancelled_error());
+                }
+                line = stdout_lines.next_line(), if stdout_open => match line? {
+                    Some(line) => {
+                        self.output(line.clone());
+                        stdout.push_str(&line);
+                        stdout.push('\n');
+                    }
+                    None => stdout_open = false,
+                },
+                line = stderr_lines.next_line(), if stderr_open => match line? {
+                    Some(line) => {
+                        self.output(line.clone());
+                        stderr.push_str(&line);
+                        stderr.push('\n');
+                    }
+                    None => stderr_open = false,
+                },
+            }
+        }
+
+        let status = tokio::select! {
+            _ = self.cancel.cancelled() => {
+                terminate(&mut child).await;
+                return Err(cancelled_error());
+            }
+            status = child.wait() => status?,
+        };
+
+        Ok(CommandOutput {
+            status: status.code(),
+            stdout,
+            stderr,
+        })
+    }
+}
+
+fn signal_group(pgid: u32, signal: libc::c_int) {
+    // Negative pid addresses the whole process group.
+    unsafe {
+        libc::kill(-(pgid as libc::pid_t), signal);
+    }
+}
+
+/// SIGTERM to the process group, then SIGKILL for anything still alive after the grace period.
+async fn terminate(child: &mut Child) {
+    let Some(pgid) = child.id() else { return };
+    log::info!("Terminating process group {}", pgid);
+    signal_group(pgid, libc::SIGTERM);
+    if tokio::time::timeout(TERMINATE_GRACE, child.wait()).await.is_err() {
+        log::warn!("Process group {} ignored SIGTERM, killing it", pgid);
+    }
+    // Also catches grandchildren that outlived their parent shell.
+    signal_group(pgid, libc::SIGKILL);
+    let _ = child.wait().await;
+}
+
+/// Starts a job on the background runtime. The job returns a summary on
+/// success or an error message on failure.
+pub fn spawn<F, Fut>(name: &str, job: F) -> JobId
+where
+    F: FnOnce(JobContext) -> Fut + Send + 'static,
+    Fut: Future<Output = Result<String, String>> + Send + 'static,
+{
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    let cancel = CancellationToken::new();
+    let context = JobContext {
+        id,
+        cancel: cancel.clone(),
+    };
+
+    {
+        let mut jobs = JOBS.lock().unwrap();
+        jobs.push(JobEntry {
+            info: JobInfo {
+                id,
+                name: name.to_string(),
+                state: JobState::Running,
+                progress: None,
+                summary: String::new(),
+                output: VecDeque::new(),
+                started_at: Local::now(),
+                finished_at: None,
+            },
+            cancel: cancel.clone(),
+        });
+        prune_finished(&mut jobs);
+    }
+    log::info!("Job {} started: {}", id, name);
+
+    let task = RUNTIME.spawn(async move { job(context).await });
+    RUNTIME.spawn(async move {
+        let (state, summary) = match task.await {
+            _ if cancel.is_cancelled() => (JobState::Cancelled, "Cancelled by operator".to_string()),
+            Ok(Ok(summary)) => (JobState::Succeeded, summary),
+            Ok(Err(message)) => (JobState::Failed, message),
+            Err(e) => (JobState::Failed, format!("Job crashed: {}", e)),
+        };
+        log::info!("Job {} finished: {} {}", id, state.label(), summary);
+        with_job(id, |job| {
+            job.state = state;
+            job.summary = summary;
+            job.finished_at = Some(Local::now());
+            if state == JobState::Succeeded {
+                job.progress = Some(100);
+            }
+        });
+    });
+
+    id
+}
+
+fn prune_finished(jobs: &mut Vec<JobEntry>) {
+    let finished = jobs.iter().filter(|e| e.info.state != JobState::Running).count();
+    let mut excess = finished.saturating_sub(FINISHED_JOBS);
+    jobs.retain(|e| {
+        if excess > 0 && e.info.state != JobState::Running {
+            excess -= 1;
+            false
+        } else {
+            true
+        }
+    });
+}
+
+pub fn get(id: JobId) -> Option<JobInfo> {
+    JOBS.lock()
+        .unwrap()
+        .iter()
+        .find(|e| e.info.id == id)
+        .map(|e| e.info.clone())
+}
+
+pub fn is_running(id: JobId) -> bool {
+    get(id).is_some_and(|job| job.state == JobState::Running)
+}
+
+pub fn snapshot() -> Vec<JobInfo> {
+    JOBS.lock().unwrap().iter().map(|e| e.info.clone()).collect()
+}
+
+pub fn running_count() -> usize {
+    JOBS.lock()
+        .unwrap()
+        .iter()
+        .filter(|e| e.info.state == JobState::Running)
+        .count()
+}
+
+pub fn cancel(id: JobId) {
+    if let Some(entry) = JOBS.lock().unwrap().iter().find(|e| e.info.id == id) {
+        if entry.info.state == JobState::Running {
+            log::info!("Cancelling job {}: {}", id, entry.info.name);
+            entry.cancel.cancel();
+        }
+    }
+}
+
+/// Cancels every running job and waits up to `timeout` for them to stop.
+pub fn shutdown(timeout: Duration) {
+    for entry in JOBS.lock().unwrap().iter() {
+        entry.cancel.cancel();
+    }
+    let deadline = Instant::now() + timeout;
+    while running_count() > 0 && Instant::now() < deadline {
+        std::thread::sleep(Duration::from_millis(50));
+    }
+}
+
+pub fn enter_jobs_panel() {
+    *SELECTED_JOB.lock().unwrap() = 0;
+}
+
+pub fn previous_job() {
+    let mut index = SELECTED_JOB.lock().unwrap();
+    *index = index.saturating_sub(1);
+}
+
+pub fn next_job() {
+    let count = JOBS.lock().unwrap().len();
+    let mut index = SELECTED_JOB.lock().unwrap();
+    if *index < count.saturating_sub(1) {
+        *index += 1;
+    }
+}
+
+/// Newest jobs first, as shown in the panel.
+fn panel_jobs() -> Vec<JobInfo> {
+    let mut jobs = snapshot();
+    jobs.reverse();
+    jobs
+}
+
+pub fn cancel_selected_job() {
+    let index = *SELECTED_JOB.lock().unwrap();
+    if let Some(job) = panel_jobs().get(index) {
+        cancel(job.id);
+    }
+}
+
+/// The last `lines` output lines of a job, for screens that show its progress.
+pub fn output_tail(job: &JobInfo, lines: usize) -> Vec<Line<'static>> {
+    let skip = job.output.len().saturating_sub(lines);
+    job.output.iter().skip(skip).map(|l| Line::raw(l.clone())).collect()
+}
+
+fn elapsed(job: &JobInfo) -> String {
+    let end = job.finished_at.unwrap_or_else(Local::now);
+    let secs = (end - job.started_at).num_seconds().max(0);
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
+pub fn draw_jobs_panel(f: &mut Frame) {
+    let jobs = panel_jobs();
+    let selected = (*SELECTED_JOB.lock().unwrap()).min(jobs.len().saturating_sub(1));
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([Constraint::Percentage(40), Constraint::Min(5), Constraint::Length(3)])
+        .split(f.area());
+
+    let rows: Vec<Row> = jobs
+        .iter()
+        .map(|job| {
+            Row::new(vec![
+                Span::raw(job.id.to_string()),
+                Span::raw(job.name.clone()),
+                Span::styled(job.state.label(), Style::default().fg(job.state.color())),
+                Span::raw(job.progress.map_or("-".to_string(), |p| format!("{}%", p))),
+                Span::raw(elapsed(job)),
+                Span::raw(job.summary.clone()),
+            ])
+        })
+        .collect();
+
+    let mut state = TableState::default();
+    state.select((!jobs.is_empty()).then_some(selected));
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(4),
+            Constraint::Length(24),
+            Constraint::Length(10),
+            Constraint::Length(6),
+            Constraint::Length(7),
+            Constraint::Min(10),
+        ],
+    )
+    .header(
+        Row::new(vec!["#", "Job", "State", "Done", "Time", "Result"])
+            .style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+    )
+    .row_highlight_style(highlight_style())
+    .block(bordered_block("Jobs"));
+
+    let output_lines = jobs
+        .get(selected)
+        .map(|job| output_tail(job, chunks[1].height.saturating_sub(2) as usize))
+        .unwrap_or_default();
+    let output = Paragraph::new(Text::from(output_lines))
+        .block(bordered_block("Output"))
+        .wrap(Wrap { trim: false });
+
+    let help = Paragraph::new("↑/↓: select job   c: cancel selected job   Esc: back")
+        .block(bordered_block("Instructions"));
+
+    f.render_stateful_widget(table, chunks[0], &mut state);
+    f.render_widget(output, chunks[1]);
+    f.render_widget(help, chunks[2]);
+}
+
+#[cfg(test)]
+mod tests;
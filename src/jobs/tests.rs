@@ -0,0 +1,94 @@
+use super::*;
+use std::fs;
+
+fn wait_for(id: JobId) -> JobInfo {
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while is_running(id) {
+        assert!(Instant::now() < deadline, "job {} did not finish", id);
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    get(id).unwrap()
+}
+
+fn wait_until(condition: impl Fn() -> bool) {
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while !condition() {
+        assert!(Instant::now() < deadline, "condition not met in time");
+        std::thread::sleep(Duration::from_millis(20));
+    }
+}
+
+/// True once a process is gone or only a zombie waiting to be reaped.
+fn process_gone(pid: &str) -> bool {
+    match fs::read_to_string(format!("/proc/{}/stat", pid)) {
+        Ok(stat) => stat.rsplit(')').next().unwrap_or("").trim_start().starts_with('Z'),
+        Err(_) => true,
+    }
+}
+
+#[test]
+fn streams_command_output() {
+    let id = spawn("echo", |ctx| async move {
+        let out = ctx
+            .run_command("sh", &["-c", "echo one; echo two >&2; exit 3"])
+            .await
+            .map_err(|e| e.to_string())?;
+        Ok(format!("exit {:?}", out.status))
+    });
+
+    let job = wait_for(id);
+    assert_eq!(job.state, JobState::Succeeded);
+    assert_eq!(job.summary, "exit Some(3)");
+    assert_eq!(job.progress, Some(100));
+    let mut output: Vec<&str> = job.output.iter().map(String::as_str).collect();
+    output.sort();
+    assert_eq!(output, vec!["one", "two"]);
+}
+
+#[test]
+fn error_marks_job_failed() {
+    let id = spawn("fails", |_| async { Err("no camera".to_string()) });
+    let job = wait_for(id);
+    assert_eq!(job.state, JobState::Failed);
+    assert_eq!(job.summary, "no camera");
+}
+
+#[test]
+fn cancel_stops_sleep() {
+    let id = spawn("sleeper", |ctx| async move {
+        ctx.sleep(Duration::from_secs(30)).await.map_err(|e| e.to_string())?;
+        Ok("slept".to_string())
+    });
+    cancel(id);
+    assert_eq!(wait_for(id).state, JobState::Cancelled);
+}
+
+#[test]
+fn cancel_kills_the_whole_process_group() {
+    let id = spawn("script", |ctx| async move {
+        match ctx.run_command("sh", &["-c", "sleep 30 & echo $!; wait"]).await {
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => Err("interrupted".to_string()),
+            other => Ok(format!("{:?}", other)),
+        }
+    });
+
+    wait_until(|| get(id).is_some_and(|job| !job.output.is_empty()));
+    let grandchild = get(id).unwrap().output[0].clone();
+
+    cancel(id);
+    let job = wait_for(id);
+    assert_eq!(job.state, JobState::Cancelled);
+    wait_until(|| process_gone(&grandchild));
+}
+
+#[test]
+fn reports_progress() {
+    let id = spawn("progress", |ctx| async move {
+        ctx.progress(40);
+        ctx.sleep(Duration::from_secs(30)).await.map_err(|e| e.to_string())?;
+        Ok(String::new())
+    });
+    wait_until(|| get(id).and_then(|job| job.progress) == Some(40));
+    cancel(id);
+    wait_for(id);
+}
@@ -2,45 +2,133 @@ use ratatui::{
     layout::{Constraint, Layout},
     style::{Color, Style},
     text::Span,
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Wrap},
     Frame,
 };
 
+use chrono::{DateTime, Local};
+use crossterm::event::KeyCode;
 use once_cell::sync::Lazy;
+use std::collections::BTreeSet;
 use std::sync::Mutex;
-use std::process::Command;
+
+use crate::command::{CommandRunner, SystemRunner};
+use crate::session::{self, TestResult, TestStatus};
 
 static DEVICES: Lazy<Mutex<Vec<String>>> = Lazy::new(|| Mutex::new(vec![]));
 static DEVICE_INDEX: Lazy<Mutex<usize>> = Lazy::new(|| Mutex::new(0));
-static ACTIVE: Lazy<Mutex<bool>> = Lazy::new(|| Mutex::new(false));
 static MESSAGE: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new(String::new()));
+static CAPTURE_STARTED: Lazy<Mutex<Option<DateTime<Local>>>> = Lazy::new(|| Mutex::new(None));
+static PRESSED_KEYS: Lazy<Mutex<BTreeSet<String>>> = Lazy::new(|| Mutex::new(BTreeSet::new()));
 
-pub fn check_keyboard_test_active() -> bool {
-    *ACTIVE.lock().unwrap()
-}
+/// Keys every laptop and desktop keyboard must have for a full-coverage pass.
+const EXPECTED_KEYS: &[&str] = &[
+    "a", "b", "c", "d", "e", "f", "g", "h", "i", "j", "k", "l", "m", "n", "o", "p", "q", "r",
+    "s", "t", "u", "v", "w", "x", "y", "z", "0", "1", "2", "3", "4", "5", "6", "7", "8", "9",
+    "Space", "Enter", "Backspace", "Tab", "Esc", "Up", "Down", "Left", "Right",
+];
 
 pub fn exit_keyboard_test() {
-    *ACTIVE.lock().unwrap() = false;
     *MESSAGE.lock().unwrap() = String::new();
+    *CAPTURE_STARTED.lock().unwrap() = None;
+    PRESSED_KEYS.lock().unwrap().clear();
 }
 
 pub fn enter_keyboard_test() {
-    *DEVICES.lock().unwrap() = fetch_keyboard_devices();
+    *DEVICES.lock().unwrap() = fetch_keyboard_devices(&SystemRunner);
     *DEVICE_INDEX.lock().unwrap() = 0;
-    *ACTIVE.lock().unwrap() = true;
-    *MESSAGE.lock().unwrap() = "Select your keyboard device. Key response testing coming soon.".to_string();
+    *MESSAGE.lock().unwrap() = "Select your keyboard device and press Enter to start the key test.".to_string();
 }
 
-/// Called on Enter key — eventually this could verify input mapping
+/// Called on Enter key — starts capturing every key press until Esc.
 pub fn run_keyboard_test() {
-    let devices = DEVICES.lock().unwrap();
-    let index = *DEVICE_INDEX.lock().unwrap();
+    PRESSED_KEYS.lock().unwrap().clear();
+    *CAPTURE_STARTED.lock().unwrap() = Some(Local::now());
+    *MESSAGE.lock().unwrap() = "Press every key on the keyboard. Press Esc when done.".to_string();
+}
+
+pub fn is_capturing() -> bool {
+    CAPTURE_STARTED.lock().unwrap().is_some()
+}
 
-    let fallback = "No device selected.".to_string();
-    let selected = devices.get(index).unwrap_or(&fallback);
+/// Records a key during capture; Esc ends the capture and records the result.
+pub fn record_key(key: KeyCode) {
+    if let Some(name) = key_name(key) {
+        PRESSED_KEYS.lock().unwrap().insert(name);
+    }
+    if key == KeyCode::Esc {
+        finish_keyboard_test();
+    }
+}
 
-    let mut msg = MESSAGE.lock().unwrap();
-    *msg = format!("Starting test on: {}\n(Feature under development)", selected);
+fn key_name(key: KeyCode) -> Option<String> {
+    match key {
+        KeyCode::Char(' ') => Some("Space".to_string()),
+        KeyCode::Char(c) => Some(c.to_lowercase().to_string()),
+        KeyCode::Enter => Some("Enter".to_string()),
+        KeyCode::Backspace => Some("Backspace".to_string()),
+        KeyCode::Tab | KeyCode::BackTab => Some("Tab".to_string()),
+        KeyCode::Esc => Some("Esc".to_string()),
+        KeyCode::Up => Some("Up".to_string()),
+        KeyCode::Down => Some("Down".to_string()),
+        KeyCode::Left => Some("Left".to_string()),
+        KeyCode::Right => Some("Right".to_string()),
+        KeyCode::F(n) => Some(format!("F{}", n)),
+        KeyCode::Delete => Some("Delete".to_string()),
+        KeyCode::Home => Some("Home".to_string()),
+        KeyCode::End => Some("End".to_string()),
+        KeyCode::PageUp => Some("PageUp".to_string()),
+        KeyCode::PageDown => Some("PageDown".to_string()),
+        KeyCode::Insert => Some("Insert".to_string()),
+        _ => None,
+    }
+}
+
+fn missing_keys() -> Vec<&'static str> {
+    let pressed = PRESSED_KEYS.lock().unwrap();
+    EXPECTED_KEYS
+        .iter()
+        .filter(|k| !pressed.contains(**k))
+        .copied()
+        .collect()
+}
+
+fn finish_keyboard_test() {
+    let started = CAPTURE_STARTED.lock().unwrap().take().unwrap_or_else(Local::now);
+    let devices = DEVICES.lock().unwrap();
+    let device = devices
+        .get(*DEVICE_INDEX.lock().unwrap())
+        .cloned()
+        .unwrap_or_else(|| "keyboard".to_string());
+
+    let missing = missing_keys();
+    let covered = EXPECTED_KEYS.len() - missing.len();
+    let coverage = covered as f64 / EXPECTED_KEYS.len() as f64 * 100.0;
+    let status = if missing.is_empty() { TestStatus::Pass } else { TestStatus::Warn };
+    let pressed: Vec<String> = PRESSED_KEYS.lock().unwrap().iter().cloned().collect();
+
+    session::record(
+        TestResult::new("Keyboard", &device)
+            .started(started)
+            .status(status)
+            .metric("coverage_pct", format!("{:.0}", coverage))
+            .metric("keys_pressed", pressed.len())
+            .metric("missing", missing.join(" "))
+            .raw_output(pressed.join(" "))
+            .finish(),
+    );
+
+    *MESSAGE.lock().unwrap() = if missing.is_empty() {
+        format!("All {} keys registered. Keyboard test passed.", EXPECTED_KEYS.len())
+    } else {
+        format!(
+            "Coverage {:.0}% ({}/{}). Missing: {}",
+            coverage,
+            covered,
+            EXPECTED_KEYS.len(),
+            missing.join(" ")
+        )
+    };
 }
 
 pub fn increment_device_selection() {
@@ -62,7 +150,18 @@ pub fn draw_keyboard_test(f: &mut Frame) {
     let size = f.area();
     let devices = DEVICES.lock().unwrap();
     let selected = *DEVICE_INDEX.lock().unwrap();
-    let message = MESSAGE.lock().unwrap();
+    let mut message = MESSAGE.lock().unwrap().clone();
+
+    if is_capturing() {
+        let missing = missing_keys();
+        message = format!(
+            "{}\n\nRegistered {}/{} keys. Still missing: {}",
+            message,
+            EXPECTED_KEYS.len() - missing.len(),
+            EXPECTED_KEYS.len(),
+            missing.join(" ")
+        );
+    }
 
     let chunks = Layout::default()
         .constraints([Constraint::Length(3), Constraint::Min(1)])
@@ -82,22 +181,29 @@ pub fn draw_keyboard_test(f: &mut Frame) {
         .highlight_style(Style::default().bg(Color::White).fg(Color::Black))
         .highlight_symbol("▶ ");
 
-    let info = Paragraph::new(Span::raw(message.as_str()))
-        .block(Block::default().title("Keyboard Test Info").borders(Borders::ALL));
+    let info = Paragraph::new(message)
+        .block(Block::default().title("Keyboard Test Info").borders(Borders::ALL))
+        .wrap(Wrap { trim: false });
 
     f.render_stateful_widget(list, chunks[0], &mut state);
     f.render_widget(info, chunks[1]);
 }
 
-fn fetch_keyboard_devices() -> Vec<String> {
-    let output = Command::new("lsusb")
-        .output()
-        .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
-        .unwrap_or_else(|_| "Failed to run lsusb".to_string());
-
-    output
+/// Keeps the `lsusb` lines that describe a keyboard.
+pub fn filter_keyboard_devices(lsusb: &str) -> Vec<String> {
+    lsusb
         .lines()
         .filter(|line| line.to_lowercase().contains("keyboard"))
         .map(String::from)
         .collect()
-}
\ No newline at end of file
+}
+
+fn fetch_keyboard_devices(runner: &dyn CommandRunner) -> Vec<String> {
+    runner
+        .run("lsusb", &[])
+        .map(|o| filter_keyboard_devices(&o.stdout))
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests;
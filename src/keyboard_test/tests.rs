@@ -0,0 +1,25 @@
+use super::*;
+use crate::command::FixtureRunner;
+
+#[test]
+fn filters_keyboards_from_lsusb() {
+    let runner = FixtureRunner::new().with("lsusb", "lsusb");
+    let devices = fetch_keyboard_devices(&runner);
+    assert_eq!(devices.len(), 2);
+    assert!(devices[0].contains("Logitech, Inc. Keyboard K120"));
+    assert!(devices[1].contains("SiGma Micro Keyboard"));
+}
+
+#[test]
+fn missing_lsusb_gives_no_devices() {
+    assert!(fetch_keyboard_devices(&FixtureRunner::new()).is_empty());
+}
+
+#[test]
+fn names_keys() {
+    assert_eq!(key_name(KeyCode::Char('A')).as_deref(), Some("a"));
+    assert_eq!(key_name(KeyCode::Char(' ')).as_deref(), Some("Space"));
+    assert_eq!(key_name(KeyCode::BackTab).as_deref(), Some("Tab"));
+    assert_eq!(key_name(KeyCode::F(5)).as_deref(), Some("F5"));
+    assert_eq!(key_name(KeyCode::Null), None);
+}
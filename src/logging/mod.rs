@@ -0,0 +1,329 @@
+use chrono::{DateTime, Local};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use once_cell::sync::Lazy;
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Style},
+    text::{Line, Span, Text},
+    widgets::Paragraph,
+    Frame,
+};
+use serde_json::json;
+use std::collections::{BTreeSet, VecDeque};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::config;
+use crate::theme::{bordered_block, info_box};
+
+/// Entries kept in memory for the log viewer; the file keeps everything.
+const BUFFER_CAPACITY: usize = 5000;
+const LOG_PREFIX: &str = "session-";
+
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub time: DateTime<Local>,
+    pub level: Level,
+    pub module: String,
+    pub message: String,
+}
+
+impl LogEntry {
+    /// One JSON object per line, so old logs can be grepped or loaded with jq.
+    pub fn to_json_line(&self) -> String {
+        json!({
+            "ts": self.time.to_rfc3339(),
+            "level": self.level.as_str(),
+            "module": self.module,
+            "message": self.message,
+        })
+        .to_string()
+    }
+}
+
+static BUFFER: Lazy<Mutex<VecDeque<LogEntry>>> = Lazy::new(|| Mutex::new(VecDeque::new()));
+static LOG_PATH: Lazy<Mutex<Option<PathBuf>>> = Lazy::new(|| Mutex::new(None));
+
+struct SessionLogger {
+    file: Mutex<Option<File>>,
+    level: LevelFilter,
+}
+
+impl Log for SessionLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let entry = LogEntry {
+            time: Local::now(),
+            level: record.level(),
+            module: module_name(record.target()),
+            message: record.args().to_string(),
+        };
+
+        if let Some(file) = self.file.lock().unwrap().as_mut() {
+            let _ = writeln!(file, "{}", entry.to_json_line());
+        }
+
+        let mut buffer = BUFFER.lock().unwrap();
+        if buffer.len() >= BUFFER_CAPACITY {
+            buffer.pop_front();
+        }
+        buffer.push_back(entry);
+    }
+
+    fn flush(&self) {
+        if let Some(file) = self.file.lock().unwrap().as_mut() {
+            let _ = file.flush();
+        }
+    }
+}
+
+/// Short module name for a log target: `electronics_tui_tester::smart::json` → `smart`.
+pub fn module_name(target: &str) -> String {
+    let crate_name = env!("CARGO_CRATE_NAME");
+    match target.strip_prefix(crate_name) {
+        Some("") => "main".to_string(),
+        Some(rest) => rest
+            .trim_start_matches("::")
+            .split("::")
+            .next()
+            .unwrap_or("main")
+            .to_string(),
+        None => target.split("::").next().unwrap_or(target).to_string(),
+    }
+}
+
+pub fn log_dir() -> PathBuf {
+    if let Some(dir) = &config::get().logging.dir {
+        return dir.clone();
+    }
+    dirs::state_dir()
+        .or_else(dirs::data_local_dir)
+        .map(|dir| dir.join("electronics_tui_tester").join("logs"))
+        .unwrap_or_else(|| PathBuf::from("logs"))
+}
+
+/// Deletes the oldest session logs so that at most `keep` remain.
+pub fn prune_old_logs(dir: &Path, keep: usize) -> io::Result<()> {
+    let mut logs: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| {
+            path.file_name()
+                .map(|n| n.to_string_lossy())
+                .is_some_and(|n| n.starts_with(LOG_PREFIX) && n.ends_with(".log"))
+        })
+        .collect();
+    // Names carry a sortable timestamp, so name order is age order.
+    logs.sort();
+
+    let excess = logs.len().saturating_sub(keep);
+    for path in logs.into_iter().take(excess) {
+        fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+fn parse_level(level: &str) -> LevelFilter {
+    level.parse().unwrap_or(LevelFilter::Info)
+}
+
+/// Starts a new session log file and routes the `log` macros to it and to the
+/// in-app viewer. Returns the path of the new file.
+pub fn init() -> io::Result<PathBuf> {
+    let settings = &config::get().logging;
+    let dir = log_dir();
+    fs::create_dir_all(&dir)?;
+    prune_old_logs(&dir, settings.keep_sessions.saturating_sub(1))?;
+
+    let path = dir.join(format!(
+        "{}{}-{}.log",
+        LOG_PREFIX,
+        Local::now().format("%Y%m%d-%H%M%S"),
+        std::process::id()
+    ));
+    let file = OpenOptions::new().create(true).append(true).open(&path)?;
+
+    let level = parse_level(&settings.level);
+    let logger = SessionLogger {
+        file: Mutex::new(Some(file)),
+        level,
+    };
+    log::set_boxed_logger(Box::new(logger))
+        .map_err(|e| io::Error::new(io::ErrorKind::AlreadyExists, e.to_string()))?;
+    log::set_max_level(level);
+
+    *LOG_PATH.lock().unwrap() = Some(path.clone());
+    install_panic_hook();
+    log::info!("Session log started, version {}", env!("CARGO_PKG_VERSION"));
+    Ok(path)
+}
+
+/// Panics inside the TUI are otherwise lost with the alternate screen.
+fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        log::error!("panic: {}", info);
+        log::logger().flush();
+        default_hook(info);
+    }));
+}
+
+pub fn entries() -> Vec<LogEntry> {
+    BUFFER.lock().unwrap().iter().cloned().collect()
+}
+
+/// Entries from `module` (or every module) at `min_level` severity or worse.
+pub fn filter_entries<'a>(entries: &'a [LogEntry], module: Option<&str>, min_level: Level) -> Vec<&'a LogEntry> {
+    entries
+        .iter()
+        .filter(|e| e.level <= min_level)
+        .filter(|e| module.is_none_or(|m| e.module == m))
+        .collect()
+}
+
+pub fn modules(entries: &[LogEntry]) -> Vec<String> {
+    entries
+        .iter()
+        .map(|e| e.module.clone())
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+struct ViewerState {
+    module: Option<String>,
+    min_level: Level,
+    /// Lines scrolled up from the newest entry; 0 follows new output.
+    offset: usize,
+}
+
+static VIEWER: Lazy<Mutex<ViewerState>> = Lazy::new(|| {
+    Mutex::new(ViewerState {
+        module: None,
+        min_level: Level::Trace,
+        offset: 0,
+    })
+});
+
+pub fn enter_log_viewer() {
+    VIEWER.lock().unwrap().offset = 0;
+}
+
+/// Steps the module filter through "all" and every module that has logged.
+pub fn cycle_module_filter() {
+    let modules = modules(&entries());
+    let mut viewer = VIEWER.lock().unwrap();
+    viewer.module = match &viewer.module {
+        None => modules.first().cloned(),
+        Some(current) => modules
+            .iter()
+            .position(|m| m == current)
+            .and_then(|i| modules.get(i + 1))
+            .cloned(),
+    };
+    viewer.offset = 0;
+}
+
+/// Steps the severity filter: all → debug → info → warn → error → all.
+pub fn cycle_level_filter() {
+    let mut viewer = VIEWER.lock().unwrap();
+    viewer.min_level = match viewer.min_level {
+        Level::Trace => Level::Debug,
+        Level::Debug => Level::Info,
+        Level::Info => Level::Warn,
+        Level::Warn => Level::Error,
+        Level::Error => Level::Trace,
+    };
+    viewer.offset = 0;
+}
+
+pub fn scroll_up() {
+    VIEWER.lock().unwrap().offset += 1;
+}
+
+pub fn scroll_down() {
+    let mut viewer = VIEWER.lock().unwrap();
+    viewer.offset = viewer.offset.saturating_sub(1);
+}
+
+pub fn follow() {
+    VIEWER.lock().unwrap().offset = 0;
+}
+
+fn level_color(level: Level) -> Color {
+    match level {
+        Level::Error => Color::Red,
+        Level::Warn => Color::Yellow,
+        Level::Info => Color::Green,
+        Level::Debug => Color::Cyan,
+        Level::Trace => Color::Gray,
+    }
+}
+
+pub fn draw_log_viewer(f: &mut Frame) {
+    let all = entries();
+    let mut viewer = VIEWER.lock().unwrap();
+    let visible = filter_entries(&all, viewer.module.as_deref(), viewer.min_level);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([Constraint::Length(5), Constraint::Min(3), Constraint::Length(3)])
+        .split(f.area());
+
+    let module_label = viewer.module.clone().unwrap_or_else(|| "all".to_string());
+    let level_label = match viewer.min_level {
+        Level::Trace => "all".to_string(),
+        level => format!("{} and worse", level),
+    };
+    let path = LOG_PATH
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|| "not writing to a file".to_string());
+    let header = Paragraph::new(Text::from(vec![
+        info_box("File", &path),
+        info_box("Module", &module_label),
+        info_box("Severity", &level_label),
+    ]))
+    .block(bordered_block("Session Log"));
+
+    let height = chunks[1].height.saturating_sub(2) as usize;
+    let max_offset = visible.len().saturating_sub(height);
+    viewer.offset = viewer.offset.min(max_offset);
+    let end = visible.len() - viewer.offset;
+    let start = end.saturating_sub(height);
+
+    let lines: Vec<Line> = visible[start..end]
+        .iter()
+        .map(|e| {
+            Line::from(vec![
+                Span::styled(e.time.format("%H:%M:%S ").to_string(), Style::default().fg(Color::DarkGray)),
+                Span::styled(format!("{:<5} ", e.level), Style::default().fg(level_color(e.level))),
+                Span::styled(format!("{:<14} ", e.module), Style::default().fg(Color::Magenta)),
+                Span::raw(e.message.clone()),
+            ])
+        })
+        .collect();
+    let title = format!("Entries ({} of {})", visible.len(), all.len());
+    let body = Paragraph::new(Text::from(lines)).block(bordered_block(&title));
+
+    let help = Paragraph::new("m: module filter   l: severity filter   ↑/↓: scroll   End: follow   Esc: back")
+        .block(bordered_block("Instructions"));
+
+    f.render_widget(header, chunks[0]);
+    f.render_widget(body, chunks[1]);
+    f.render_widget(help, chunks[2]);
+}
+
+#[cfg(test)]
+mod tests;
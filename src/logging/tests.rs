@@ -0,0 +1,79 @@
+use super::*;
+use std::fs;
+
+fn entry(level: Level, module: &str, message: &str) -> LogEntry {
+    LogEntry {
+        time: Local::now(),
+        level,
+        module: module.to_string(),
+        message: message.to_string(),
+    }
+}
+
+#[test]
+fn shortens_module_targets() {
+    assert_eq!(module_name("electronics_tui_tester::smart"), "smart");
+    assert_eq!(module_name("electronics_tui_tester::menu::disk"), "menu");
+    assert_eq!(module_name("electronics_tui_tester"), "main");
+    assert_eq!(module_name("rodio::decoder"), "rodio");
+}
+
+#[test]
+fn filters_by_module_and_severity() {
+    let entries = vec![
+        entry(Level::Info, "smart", "read /dev/sda"),
+        entry(Level::Error, "smart", "smartctl missing"),
+        entry(Level::Warn, "audio_test", "test.wav missing"),
+        entry(Level::Debug, "nvidia_drivers", "running aura"),
+    ];
+
+    assert_eq!(filter_entries(&entries, None, Level::Trace).len(), 4);
+    assert_eq!(filter_entries(&entries, None, Level::Warn).len(), 2);
+    assert_eq!(filter_entries(&entries, Some("smart"), Level::Trace).len(), 2);
+
+    let errors = filter_entries(&entries, Some("smart"), Level::Error);
+    assert_eq!(errors.len(), 1);
+    assert_eq!(errors[0].message, "smartctl missing");
+
+    assert_eq!(modules(&entries), vec!["audio_test", "nvidia_drivers", "smart"]);
+}
+
+#[test]
+fn writes_json_lines() {
+    let line = entry(Level::Warn, "audio_test", "say \"hi\"").to_json_line();
+    let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+    assert_eq!(value["level"], "WARN");
+    assert_eq!(value["module"], "audio_test");
+    assert_eq!(value["message"], "say \"hi\"");
+}
+
+#[test]
+fn prunes_oldest_session_logs() {
+    let dir = tempfile::tempdir().unwrap();
+    for name in [
+        "session-20240101-090000-1.log",
+        "session-20240102-090000-1.log",
+        "session-20240103-090000-1.log",
+        "notes.txt",
+    ] {
+        fs::write(dir.path().join(name), "").unwrap();
+    }
+
+    prune_old_logs(dir.path(), 2).unwrap();
+
+    let mut left: Vec<String> = fs::read_dir(dir.path())
+        .unwrap()
+        .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+        .collect();
+    left.sort();
+    assert_eq!(
+        left,
+        vec!["notes.txt", "session-20240102-090000-1.log", "session-20240103-090000-1.log"]
+    );
+}
+
+#[test]
+fn unknown_level_defaults_to_info() {
+    assert_eq!(parse_level("warn"), LevelFilter::Warn);
+    assert_eq!(parse_level("loud"), LevelFilter::Info);
+}
@@ -1,7 +1,7 @@
 use std::{io, time::Duration};
 
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyEventKind},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -11,20 +11,56 @@ use ratatui::{
     Terminal,
 };
 
+mod app;
+mod cli;
+mod command;
+mod config;
 mod theme;
 mod audio_test;
+mod benchmark;
+mod capacity_test;
+mod confirm;
 mod gamepad_test;
 mod gpu_detect;
 mod gpu_test;
+mod history;
+mod inspect;
+mod jobs;
 mod keyboard_test;
+mod logging;
 mod menu;
 mod nvidia_drivers;
 mod photo_exporter;
+mod preflight;
+mod report;
+mod session;
 mod smart;
 mod stability_test;
 mod stress_test;
+mod surface_scan;
+mod wipe;
+mod workflow;
+
+use app::App;
+use clap::Parser;
+use cli::Cli;
 
 fn main() -> io::Result<()> {
+    let cli = Cli::parse();
+    if let Err(e) = config::init(cli.config.as_deref()) {
+        eprintln!("{}", e);
+        std::process::exit(cli::EXIT_ERROR);
+    }
+    if let Err(e) = logging::init() {
+        eprintln!("Logging disabled: {}", e);
+    }
+
+    if let Some(command) = cli.command {
+        std::process::exit(cli::run(command, cli.json));
+    }
+
+    preflight::run_preflight();
+
     enable_raw_mode()?;
     let mut stdout = io::stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
@@ -32,100 +68,32 @@ fn main() -> io::Result<()> {
     let mut terminal = Terminal::new(backend)?;
 
     let result = run_app(&mut terminal);
+    // Quitting must not leave exports or installs running without a UI.
+    jobs::shutdown(Duration::from_secs(5));
 
     disable_raw_mode()?;
     execute!(terminal.backend_mut(), LeaveAlternateScreen, DisableMouseCapture)?;
     terminal.show_cursor()?;
 
+    log::logger().flush();
     result
 }
 
 fn run_app<B: ratatui::backend::Backend>(terminal: &mut Terminal<B>) -> io::Result<()> {
-    loop {
-        terminal.draw(|f| {
-            if menu::disk::check_disk_select() {
-                menu::disk::draw_disk_selection(f);
-            } else if menu::input::check_input_select() {
-                menu::input::draw_input_selection(f);
-            } else if menu::gpu::check_driver_select() {
-                menu::gpu::draw_driver_menu(f);
-            } else if nvidia_drivers::check_driver_installing() {
-                nvidia_drivers::draw_driver_install_output(f);
-            } else if smart::check_smart_active() {
-                menu::disk::draw_smart_output(f);
-            } else if photo_exporter::check_export_active() {
-                photo_exporter::draw_photo_export_progress(f);
-            } else if stress_test::check_stress_active() {
-                stress_test::draw_stress_test_popup(f);
-            } else {
-                menu::draw_main_menu(f);
-            }
-        })?;
+    let mut app = App::new();
+
+    while app.is_running() {
+        app.tick();
+        terminal.draw(|f| app.draw(f))?;
 
         if event::poll(Duration::from_millis(100))? {
             if let Event::Key(key) = event::read()? {
-                match key.code {
-                    KeyCode::Char('q') => {
-                        if smart::check_smart_active() {
-                            smart::exit_smart_output();
-                        } else if menu::disk::check_disk_select() {
-                            menu::disk::exit_disk_selection();
-                        } else if menu::input::check_input_select() {
-                            menu::input::exit_input_selection();
-                        } else if menu::gpu::check_driver_select() {
-                            menu::gpu::exit_driver_selection_menu();
-                        } else if photo_exporter::check_export_active() {
-                            photo_exporter::exit_export();
-                        } else if stress_test::check_stress_active() {
-                            stress_test::stop_stress_test();
-                        } else {
-                            break;
-                        }
-                    }
-                    KeyCode::Up => {
-                        if menu::disk::check_disk_select() {
-                            menu::disk::decrement_disk_selection();
-                        } else if menu::input::check_input_select() {
-                            menu::input::decrement_input_selection();
-                        } else if menu::gpu::check_driver_select() {
-                            menu::gpu::decrement_driver_selection_menu();
-                        } else {
-                            menu::decrement_menu();
-                        }
-                    }
-                    KeyCode::Down => {
-                        if menu::disk::check_disk_select() {
-                            menu::disk::increment_disk_selection();
-                        } else if menu::input::check_input_select() {
-                            menu::input::increment_input_selection();
-                        } else if menu::gpu::check_driver_select() {
-                            menu::gpu::increment_driver_selection_menu();
-                        } else {
-                            menu::increment_menu();
-                        }
-                    }
-                    KeyCode::Enter => {
-                        if menu::disk::check_disk_select() {
-                            menu::disk::run_selected_disk_smart();
-                        } else if menu::input::check_input_select() {
-                            // Input selection logic will go here.
-                        } else if menu::gpu::check_driver_select() {
-                            menu::gpu::install_selected_driver_menu();
-                        } else {
-                            menu::handle_main_menu_enter();
-                        }
-                    }
-                    KeyCode::Char('s') => {
-                        stress_test::start_stress_test();
-                    }
-                    KeyCode::Char('t') => {
-                        stability_test::start_stability_test();
-                    }
-                    _ => {}
+                if key.kind == KeyEventKind::Press {
+                    app.handle_key(key.code);
                 }
             }
         }
     }
 
     Ok(())
-}
\ No newline at end of file
+}
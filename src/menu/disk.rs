@@ -1,27 +1,61 @@
 use ratatui::{
     layout::{Constraint, Direction, Layout},
-    style::{Color, Style},
-    text::Span,
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph},
+    style::{Color, Modifier, Style},
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState, Wrap},
     Frame,
 };
 
-use crate::smart;
-
-pub fn check_disk_select() -> bool {
-    smart::check_disk_selection_active()
-}
+use crate::benchmark;
+use crate::capacity_test;
+use crate::inspect;
+use crate::smart::{self, format_capacity};
+use crate::surface_scan;
+use crate::wipe;
 
 pub fn draw_disk_selection(f: &mut Frame) {
     let drives = smart::get_drive_list();
     let selected_index = smart::get_selected_drive_index();
+    let boot_disk = smart::boot_disk();
 
-    let items: Vec<ListItem> = drives
+    let rows: Vec<Row> = drives
         .iter()
-        .map(|drive| ListItem::new(Span::raw(drive.clone())))
+        .map(|drive| {
+            let is_boot = drive.is_boot_disk();
+            let flag = if is_boot {
+                Cell::from("SYSTEM").style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD))
+            } else if drive.in_use() {
+                Cell::from("IN USE").style(Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+            } else if smart::is_new_drive(&drive.path) {
+                Cell::from("NEW").style(Style::default().fg(Color::Green).add_modifier(Modifier::BOLD))
+            } else {
+                Cell::from("")
+            };
+            let intake = match inspect::latest(drive) {
+                Some(inspection) if inspection.holds_data() => Cell::from("data").style(Style::default().fg(Color::Yellow)),
+                Some(_) => Cell::from("blank").style(Style::default().fg(Color::Green)),
+                None => Cell::from(""),
+            };
+            let row = Row::new(vec![
+                Cell::from(drive.path.clone()),
+                Cell::from(format_capacity(drive.size)),
+                Cell::from(drive.model_text().to_string()),
+                Cell::from(drive.serial.clone().unwrap_or_default()),
+                Cell::from(drive.transport.clone().unwrap_or_default()),
+                Cell::from(drive.media()),
+                Cell::from(if drive.removable { "yes" } else { "" }),
+                intake,
+                flag,
+            ]);
+            if is_boot {
+                row.style(Style::default().fg(Color::Red))
+            } else {
+                row
+            }
+        })
         .collect();
 
-    let mut state = ListState::default();
+    let mut state = TableState::default();
     state.select(Some(selected_index));
 
     let size = f.area();
@@ -29,25 +63,72 @@ pub fn draw_disk_selection(f: &mut Frame) {
         .direction(Direction::Vertical)
         .margin(2)
         .constraints([
-            Constraint::Length(3),
             Constraint::Min(1),
+            Constraint::Length(6),
         ])
         .split(size);
 
-    let list = List::new(items)
-        .block(Block::default().borders(Borders::ALL).title("Select Drive for SMART Test"))
-        .highlight_style(Style::default().bg(Color::White).fg(Color::Black))
-        .highlight_symbol("▶ ");
+    let title = if smart::showing_virtual_drives() {
+        "Select Drive (including loop/zram)"
+    } else {
+        "Select Drive"
+    };
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(14),
+            Constraint::Length(10),
+            Constraint::Percentage(35),
+            Constraint::Length(20),
+            Constraint::Length(9),
+            Constraint::Length(5),
+            Constraint::Length(9),
+            Constraint::Length(6),
+            Constraint::Length(7),
+        ],
+    )
+    .header(
+        Row::new(vec!["Device", "Size", "Model", "Serial", "Transport", "Type", "Removable", "Intake", ""])
+            .style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+    )
+    .block(Block::default().borders(Borders::ALL).title(title))
+    .row_highlight_style(Style::default().bg(Color::White).fg(Color::Black))
+    .highlight_symbol("▶ ");
 
-    let info = Paragraph::new(Span::raw("Use ↑/↓ to navigate, Enter to begin test, q to cancel"))
-        .block(Block::default().borders(Borders::ALL).title("Instructions"));
+    let instructions = if drives.is_empty() {
+        "No drives found. Check the log viewer for lsblk errors. v: show loop/zram, Esc to go back"
+    } else {
+        "↑/↓ navigate, Enter: SMART test, a: read all drives, b: benchmark, s: surface scan, i: inspect for leftover data, f: fake capacity test, w: wipe drive, v: show/hide loop/zram, Esc: back"
+    };
+    let boot_line = match &boot_disk {
+        Some(disk) => Line::from(Span::styled(
+            format!("{} is this workstation's boot disk. Do not wipe or test it.", disk),
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        )),
+        None => Line::from(Span::styled(
+            "Boot disk unknown: check which drive this workstation runs from before wiping.",
+            Style::default().fg(Color::Yellow),
+        )),
+    };
+    let mut lines = vec![boot_line];
+    if let Some(drive) = drives.get(selected_index).filter(|d| d.in_use()) {
+        let reasons: Vec<String> = drive.system_use.iter().map(|u| u.to_string()).collect();
+        lines.push(Line::from(Span::styled(
+            format!("{} {}: wipe and capacity test are refused.", drive.path, reasons.join(", ")),
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+    lines.push(Line::from(instructions));
+    let info = Paragraph::new(Text::from(lines))
+        .block(Block::default().borders(Borders::ALL).title("Instructions"))
+        .wrap(Wrap { trim: true });
 
-    f.render_stateful_widget(list, chunks[0], &mut state);
+    f.render_stateful_widget(table, chunks[0], &mut state);
     f.render_widget(info, chunks[1]);
 }
 
-pub fn exit_disk_selection() {
-    smart::exit_disk_selection();
+pub fn toggle_virtual_drives() {
+    smart::toggle_virtual_drives();
 }
 
 pub fn decrement_disk_selection() {
@@ -62,6 +143,56 @@ pub fn run_selected_disk_smart() {
     smart::run_smart_test_on_selected_drive();
 }
 
+/// Device path of the highlighted drive.
+fn selected_device() -> Option<String> {
+    smart::selected_drive().map(|drive| drive.path)
+}
+
+/// Opens the wipe confirmation for the highlighted drive; false when none is.
+pub fn wipe_selected_disk() -> bool {
+    let Some(device) = selected_device() else {
+        return false;
+    };
+    wipe::enter_wipe_confirm(&device);
+    true
+}
+
+/// Benchmarks the highlighted drive; false when none is.
+pub fn benchmark_selected_disk() -> bool {
+    let Some(device) = selected_device() else {
+        return false;
+    };
+    benchmark::start_benchmark(&device);
+    true
+}
+
+/// Opens the capacity test confirmation for the highlighted drive; false when none is.
+pub fn capacity_test_selected_disk() -> bool {
+    let Some(device) = selected_device() else {
+        return false;
+    };
+    capacity_test::enter_capacity_confirm(&device);
+    true
+}
+
+/// Starts a read-only leftover-data inspection of the highlighted drive; false when none is.
+pub fn inspect_selected_disk() -> bool {
+    let Some(drive) = smart::selected_drive() else {
+        return false;
+    };
+    inspect::start_inspection(&drive);
+    true
+}
+
+/// Starts a read-only surface scan of the highlighted drive; false when none is.
+pub fn scan_selected_disk() -> bool {
+    let Some(device) = selected_device() else {
+        return false;
+    };
+    surface_scan::start_scan(&device);
+    true
+}
+
 pub fn draw_smart_output(f: &mut Frame) {
     smart::draw_smart_output(f);
 }
\ No newline at end of file
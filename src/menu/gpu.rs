@@ -12,9 +12,7 @@ use std::sync::Mutex;
 use crate::nvidia_drivers::{
     get_driver_list,
     get_driver_index,
-    check_driver_selection,
-    check_driver_installing,
-    draw_driver_install_output,
+    enter_driver_selection as reset_driver_index,
     install_selected_driver,
     reset_driver_state,
     increment_driver_selection,
@@ -25,18 +23,10 @@ pub static DRIVER_LIST: Lazy<Mutex<Vec<String>>> = Lazy::new(|| Mutex::new(vec![
 
 pub fn enter_driver_selection() {
     *DRIVER_LIST.lock().unwrap() = get_driver_list();
-}
-
-pub fn check_driver_select() -> bool {
-    check_driver_selection()
+    reset_driver_index();
 }
 
 pub fn draw_driver_menu(f: &mut Frame) {
-    if check_driver_installing() {
-        draw_driver_install_output(f);
-        return;
-    }
-
     let drivers = DRIVER_LIST.lock().unwrap();
     let index = get_driver_index();
 
@@ -51,7 +41,7 @@ pub fn draw_driver_menu(f: &mut Frame) {
     let layout = Layout::default()
         .direction(Direction::Vertical)
         .margin(2)
-        .constraints([Constraint::Length(3), Constraint::Min(1)])
+        .constraints([Constraint::Min(1), Constraint::Length(3)])
         .split(f.area());
 
     let list = List::new(items)
@@ -60,7 +50,7 @@ pub fn draw_driver_menu(f: &mut Frame) {
         .highlight_symbol("▶ ");
 
     let info = Paragraph::new(Span::raw(
-        "Use ↑/↓ to choose driver. Press Enter to install. Press q to cancel.",
+        "Use ↑/↓ to choose driver. Press Enter to install. Press Esc to go back.",
     ))
     .block(Block::default().borders(Borders::ALL).title("Instructions"));
 
@@ -1,31 +1,31 @@
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout},
-    style::{Color, Modifier, Style},
+    style::{Color, Style},
     text::{Line, Span, Text},
-    widgets::{Block, Borders, Paragraph},
+    widgets::Paragraph,
     Frame,
 };
 
-use crossterm::event::KeyCode;
-
 use once_cell::sync::Lazy;
 use std::sync::Mutex;
 
+use crate::app::{Screen, Transition};
+use crate::capacity_test;
+use crate::jobs;
+use crate::logging;
 use crate::photo_exporter;
-use crate::smart;
-use crate::smart::{enter_disk_selection, scroll_up as smart_scroll_up, scroll_down as smart_scroll_down};
+use crate::preflight::{self, Feature};
+use crate::smart::enter_disk_selection;
+use crate::wipe;
+use crate::workflow;
 use crate::keyboard_test::enter_keyboard_test;
 use crate::gamepad_test::enter_gamepad_test;
 use crate::audio_test::enter_audio_test;
-use crate::nvidia_drivers::{exit_driver_selection};
-use crate::nvidia_drivers;
-use crate::gpu_test::{check_test_active, draw_gpu_testing};
-use crate::stress_test::{check_stress_active, draw_stress_test_popup, stop_stress_test};
-use crate::menu::input::launch_selected_test;
+use crate::gpu_test::enter_gpu_test;
+use crate::theme::{bordered_block, highlight_style};
 
 pub mod disk;
 pub mod gpu;
-pub mod input;
 
 static MENU_OPTIONS: Lazy<Vec<&str>> = Lazy::new(|| {
     vec![
@@ -37,37 +37,82 @@ static MENU_OPTIONS: Lazy<Vec<&str>> = Lazy::new(|| {
         "Keyboard Test",              // 5
         "Gamepad Test",               // 6
         "Audio Test",                 // 7
-        "Exit",                       // 8
+        "Run Workflow",               // 8
+        "Session Summary",            // 9
+        "Dependency Check",           // 10
+        "Running Jobs",               // 11
+        "View Logs",                  // 12
+        "Exit",                       // 13
     ]
 });
 
 static MENU_INDEX: Lazy<Mutex<usize>> = Lazy::new(|| Mutex::new(0));
+static MENU_MESSAGE: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new(String::new()));
+
+/// The external-tool feature behind a menu entry, if any.
+fn menu_feature(index: usize) -> Option<Feature> {
+    match index {
+        0 => Some(Feature::Smart),
+        1 | 2 => Some(Feature::GpuTest),
+        3 => Some(Feature::PhotoExport),
+        4 => Some(Feature::DriverInstall),
+        5 => Some(Feature::KeyboardTest),
+        6 => Some(Feature::GamepadTest),
+        _ => None,
+    }
+}
+
+/// Tools missing for the entry at `index`; empty when it can run.
+fn missing_tools(index: usize) -> Vec<String> {
+    menu_feature(index)
+        .map(preflight::missing_tools)
+        .unwrap_or_default()
+}
 
 pub fn draw_main_menu(f: &mut Frame) {
     let area = f.area();
     let selected = *MENU_INDEX.lock().unwrap();
 
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Min(3), Constraint::Length(3)])
+        .split(area);
+
     let mut lines = Vec::new();
     for (i, option) in MENU_OPTIONS.iter().enumerate() {
         let prefix = if i == selected { "▶ " } else { "  " };
+        let missing = missing_tools(i);
 
         let style = if i == selected {
-            Style::default()
-                .fg(Color::Black)
-                .bg(Color::White)
-                .add_modifier(Modifier::BOLD)
+            highlight_style()
+        } else if !missing.is_empty() {
+            Style::default().fg(Color::DarkGray)
         } else {
             Style::default()
         };
 
-        lines.push(Line::from(Span::styled(format!("{}{}", prefix, option), style)));
+        let text = if missing.is_empty() {
+            format!("{}{}", prefix, option)
+        } else {
+            format!("{}{} (unavailable: {} missing)", prefix, option, missing.join(", "))
+        };
+        lines.push(Line::from(Span::styled(text, style)));
     }
 
+    let title = match jobs::running_count() {
+        0 => "Main Menu".to_string(),
+        1 => "Main Menu (1 job running, press j)".to_string(),
+        n => format!("Main Menu ({} jobs running, press j)", n),
+    };
     let paragraph = Paragraph::new(Text::from(lines))
-        .block(Block::default().title("Main Menu").borders(Borders::ALL))
+        .block(bordered_block(&title))
         .alignment(Alignment::Left);
 
-    f.render_widget(paragraph, area);
+    let message = MENU_MESSAGE.lock().unwrap().clone();
+    let info = Paragraph::new(message).block(bordered_block("Status"));
+
+    f.render_widget(paragraph, chunks[0]);
+    f.render_widget(info, chunks[1]);
 }
 
 pub fn increment_menu() {
@@ -84,98 +129,89 @@ pub fn decrement_menu() {
     }
 }
 
-pub fn handle_main_menu_enter() {
-    let index = *MENU_INDEX.lock().unwrap();
-    match index {
-        0 => enter_disk_selection(),
-        1 => gpu::enter_driver_selection(),
-        2 => gpu::enter_driver_selection(),
-        3 => photo_exporter::run_photo_exporter(),
-        4 => nvidia_drivers::enter_driver_selection(),
-        5 => enter_keyboard_test(),
-        6 => enter_gamepad_test(),
-        7 => enter_audio_test(),
-        8 => std::process::exit(0),
-        _ => {}
-    }
+/// Quits unless a wipe or capacity test is running: cancelling one halfway
+/// leaves a drive in an unknown state with no report.
+pub fn quit() -> Transition {
+    let running = if wipe::is_running() {
+        "A wipe"
+    } else if capacity_test::is_running() {
+        "A capacity test"
+    } else {
+        return Transition::Quit;
+    };
+    *MENU_MESSAGE.lock().unwrap() = format!(
+        "{} is still running. Wait for it to finish, or cancel it from the jobs panel (j), before quitting.",
+        running
+    );
+    Transition::Stay
 }
 
-pub fn draw_conditional_screens(f: &mut Frame) {
-    if smart::check_smart_active() {
-        smart::draw_smart_output(f);
-    } else if check_test_active() {
-        draw_gpu_testing(f);
-    } else if check_stress_active() {
-        draw_stress_test_popup(f);
-    } else if disk::check_disk_select() {
-        disk::draw_disk_selection(f);
-    } else if input::check_input_select() {
-        input::draw_input_selection(f);
-    } else if nvidia_drivers::check_driver_selection() {
-        gpu::draw_driver_menu(f);
-    } else if photo_exporter::check_export_active() {
-        photo_exporter::draw_photo_export_progress(f);
-    } else {
-        draw_main_menu(f);
+pub fn handle_main_menu_enter() -> Transition {
+    let index = *MENU_INDEX.lock().unwrap();
+    MENU_MESSAGE.lock().unwrap().clear();
+
+    let missing = missing_tools(index);
+    if !missing.is_empty() {
+        *MENU_MESSAGE.lock().unwrap() = format!(
+            "{} needs {}, which was not found on PATH. Install it, then re-run Dependency Check.",
+            MENU_OPTIONS[index],
+            missing.join(" and ")
+        );
+        return Transition::Stay;
     }
-}
 
-pub fn handle_key_press(key: KeyCode) {
-    match key {
-        KeyCode::Char('q') => {
-            if smart::check_smart_active() {
-                smart::exit_smart_output();
-            } else if disk::check_disk_select() {
-                disk::exit_disk_selection();
-            } else if input::check_input_select() {
-                input::exit_input_selection();
-            } else if nvidia_drivers::check_driver_selection() {
-                exit_driver_selection();
-            } else if photo_exporter::check_export_active() {
-                photo_exporter::exit_export();
-            } else if check_stress_active() {
-                stop_stress_test();
-            } else {
-                std::process::exit(0);
-            }
+    match index {
+        0 => {
+            enter_disk_selection();
+            Transition::Push(Screen::DiskSelect)
+        }
+        1 => {
+            enter_gpu_test("AMD");
+            Transition::Push(Screen::GpuTest)
+        }
+        2 => {
+            enter_gpu_test("NVIDIA");
+            Transition::Push(Screen::GpuTest)
         }
-        KeyCode::Up => {
-            if smart::check_smart_active() {
-                smart_scroll_up();
-            } else if disk::check_disk_select() {
-                disk::decrement_disk_selection();
-            } else if input::check_input_select() {
-                input::decrement_input_selection();
-            } else if nvidia_drivers::check_driver_selection() {
-                gpu::decrement_driver_selection_menu();
-            } else {
-                decrement_menu();
-            }
+        3 => {
+            photo_exporter::run_photo_exporter();
+            Transition::Push(Screen::PhotoExport)
         }
-        KeyCode::Down => {
-            if smart::check_smart_active() {
-                smart_scroll_down();
-            } else if disk::check_disk_select() {
-                disk::increment_disk_selection();
-            } else if input::check_input_select() {
-                input::increment_input_selection();
-            } else if nvidia_drivers::check_driver_selection() {
-                gpu::increment_driver_selection_menu();
-            } else {
-                increment_menu();
-            }
+        4 => {
+            gpu::enter_driver_selection();
+            Transition::Push(Screen::DriverSelect)
         }
-        KeyCode::Enter => {
-            if disk::check_disk_select() {
-                disk::run_selected_disk_smart();
-            } else if input::check_input_select() {
-                launch_selected_test();
-            } else if nvidia_drivers::check_driver_selection() {
-                gpu::install_selected_driver_menu();
-            } else {
-                handle_main_menu_enter();
-            }
+        5 => {
+            enter_keyboard_test();
+            Transition::Push(Screen::KeyboardTest)
         }
-        _ => {}
+        6 => {
+            enter_gamepad_test();
+            Transition::Push(Screen::GamepadTest)
+        }
+        7 => {
+            enter_audio_test();
+            Transition::Push(Screen::AudioTest)
+        }
+        8 if workflow::is_active() => Transition::Push(Screen::Workflow),
+        8 => {
+            workflow::enter_workflow_select();
+            Transition::Push(Screen::WorkflowSelect)
+        }
+        9 => Transition::Push(Screen::SessionSummary),
+        10 => {
+            preflight::run_preflight();
+            Transition::Push(Screen::Preflight)
+        }
+        11 => {
+            jobs::enter_jobs_panel();
+            Transition::Push(Screen::Jobs)
+        }
+        12 => {
+            logging::enter_log_viewer();
+            Transition::Push(Screen::LogViewer)
+        }
+        13 => quit(),
+        _ => Transition::Stay,
     }
-}
\ No newline at end of file
+}
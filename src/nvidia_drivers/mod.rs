@@ -1,56 +1,44 @@
 use ratatui::{
     layout::{Constraint, Direction, Layout},
     style::{Color, Style},
-    text::Span,
-    widgets::{Block, Borders, Gauge, Paragraph},
+    text::{Line, Text},
+    widgets::{Block, Borders, Gauge, Paragraph, Wrap},
     Frame,
 };
 
 use once_cell::sync::Lazy;
-use std::{
-    process::Command,
-    sync::Mutex,
-    thread,
-    time::{Duration, Instant},
-};
+use chrono::{DateTime, Local};
+use std::{io, sync::Mutex};
+
+use crate::command::{CommandOutput, CommandRunner};
+use crate::config;
+use crate::jobs::{self, JobId, JobState};
+use crate::session::{self, TestResult, TestStatus};
 
-static DRIVER_SELECTION_ACTIVE: Lazy<Mutex<bool>> = Lazy::new(|| Mutex::new(false));
 static SELECTED_DRIVER_INDEX: Lazy<Mutex<usize>> = Lazy::new(|| Mutex::new(0));
-static INSTALL_IN_PROGRESS: Lazy<Mutex<bool>> = Lazy::new(|| Mutex::new(false));
-static INSTALL_PROGRESS: Lazy<Mutex<u16>> = Lazy::new(|| Mutex::new(0));
+static INSTALL_JOB: Lazy<Mutex<Option<JobId>>> = Lazy::new(|| Mutex::new(None));
 static INSTALL_MESSAGE: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new(String::new()));
 
+/// Display labels for the driver packages configured for this station.
 pub fn get_driver_list() -> Vec<String> {
-    vec![
-        "nvidia (stable)".to_string(),
-        "nvidia-beta".to_string(),
-        "nvidia-open".to_string(),
-        "nvidia-390xx".to_string(),
-    ]
+    config::get()
+        .drivers
+        .packages
+        .iter()
+        .map(|d| d.label.clone())
+        .collect()
 }
 
 pub fn get_driver_index() -> usize {
     *SELECTED_DRIVER_INDEX.lock().unwrap()
 }
 
-pub fn check_driver_selection() -> bool {
-    *DRIVER_SELECTION_ACTIVE.lock().unwrap()
-}
-
-pub fn check_driver_installing() -> bool {
-    *INSTALL_IN_PROGRESS.lock().unwrap()
-}
-
 pub fn enter_driver_selection() {
-    *DRIVER_SELECTION_ACTIVE.lock().unwrap() = true;
     *SELECTED_DRIVER_INDEX.lock().unwrap() = 0;
     *INSTALL_MESSAGE.lock().unwrap() = "Select a driver to install".to_string();
 }
 
 pub fn exit_driver_selection() {
-    *DRIVER_SELECTION_ACTIVE.lock().unwrap() = false;
-    *INSTALL_IN_PROGRESS.lock().unwrap() = false;
-    *INSTALL_PROGRESS.lock().unwrap() = 0;
     *INSTALL_MESSAGE.lock().unwrap() = String::new();
 }
 
@@ -69,56 +57,88 @@ pub fn decrement_driver_selection() {
     }
 }
 
-pub fn install_selected_driver() {
-    *INSTALL_IN_PROGRESS.lock().unwrap() = true;
-    *INSTALL_PROGRESS.lock().unwrap() = 0;
-    *INSTALL_MESSAGE.lock().unwrap() = "Installing driver...".to_string();
-
-    let driver_name = get_driver_list()[*SELECTED_DRIVER_INDEX.lock().unwrap()].clone();
-
-    thread::spawn(move || {
-        let start = Instant::now();
-        *INSTALL_MESSAGE.lock().unwrap() = format!("Installing: {}", driver_name);
+/// Installs a driver package with the configured install command (aura by
+/// default), then runs the post-install command (mkinitcpio by default).
+pub fn install_driver(runner: &dyn CommandRunner, package: &str) -> io::Result<CommandOutput> {
+    log::info!("Installing driver package {}", package);
+    let output = runner.run("bash", &["-c", &install_script(package)]);
+    match &output {
+        Ok(out) if out.success() => log::info!("Installed {}", package),
+        Ok(out) => log::error!(
+            "Installing {} exited with {:?}: {}",
+            package,
+            out.status,
+            out.stderr.trim()
+        ),
+        Err(e) => log::error!("Could not run the driver install for {}: {}", package, e),
+    }
+    output
+}
 
-        let output = Command::new("bash")
-            .arg("-c")
-            .arg(format!(
-                r#"
+fn install_script(package: &str) -> String {
+    let drivers = &config::get().drivers;
+    let install = drivers.install_command.replace("{package}", package);
+    format!(
+        r#"
 set -e
-sudo aura -A --noconfirm {}
-sudo mkinitcpio -P
+{}
+{}
 "#,
-                driver_name
-            ))
-            .output();
+        install, drivers.post_install_command
+    )
+}
 
-        for i in 0..=100 {
-            *INSTALL_PROGRESS.lock().unwrap() = i;
-            thread::sleep(Duration::from_millis(40));
-        }
+/// Builds the session result for one driver install attempt.
+pub fn install_result(driver_name: &str, output: &io::Result<CommandOutput>, started: DateTime<Local>) -> TestResult {
+    let result = TestResult::new("Driver install", driver_name).started(started);
+    match output {
+        Ok(out) => result
+            .status(if out.success() { TestStatus::Pass } else { TestStatus::Fail })
+            .metric("exit_code", out.status.unwrap_or(-1))
+            .raw_output(format!("{}{}", out.stdout, out.stderr))
+            .finish(),
+        Err(e) => result.raw_output(format!("Driver install failed: {}", e)).finish(),
+    }
+}
+
+/// Starts installing the selected package as a background job.
+pub fn install_selected_driver() {
+    let mut job = INSTALL_JOB.lock().unwrap();
+    if job.is_some_and(jobs::is_running) {
+        *INSTALL_MESSAGE.lock().unwrap() = "A driver install is already running.".to_string();
+        return;
+    }
+
+    let index = *SELECTED_DRIVER_INDEX.lock().unwrap();
+    let Some(driver_name) = config::get().drivers.packages.get(index).map(|d| d.package.clone()) else {
+        *INSTALL_MESSAGE.lock().unwrap() = "No driver packages configured.".to_string();
+        return;
+    };
+    *INSTALL_MESSAGE.lock().unwrap() = format!("Installing: {}", driver_name);
+
+    *job = Some(jobs::spawn(&format!("Driver install {}", driver_name), move |ctx| async move {
+        let started = Local::now();
+        let output = ctx.run_command("bash", &["-c", &install_script(&driver_name)]).await;
+        session::record(install_result(&driver_name, &output, started));
 
         match output {
-            Ok(out) => {
-                let summary = format!(
-                    "Driver installed successfully in {:.1}s\n\n{}\n\nReboot required.",
-                    start.elapsed().as_secs_f32(),
-                    String::from_utf8_lossy(&out.stdout)
-                );
-                *INSTALL_MESSAGE.lock().unwrap() = summary;
-            }
-            Err(e) => {
-                *INSTALL_MESSAGE.lock().unwrap() = format!("Driver install failed: {}", e);
-            }
+            Ok(out) if out.success() => Ok("Driver installed. Reboot required.".to_string()),
+            Ok(out) => Err(format!("Install exited with code {}", out.status.unwrap_or(-1))),
+            Err(e) => Err(format!("Driver install failed: {}", e)),
         }
+    }));
+}
 
-        *INSTALL_IN_PROGRESS.lock().unwrap() = true;
-    });
+pub fn cancel_install() {
+    if let Some(id) = *INSTALL_JOB.lock().unwrap() {
+        jobs::cancel(id);
+    }
 }
 
 pub fn draw_driver_install_output(f: &mut Frame) {
     let area = f.area();
-    let progress = *INSTALL_PROGRESS.lock().unwrap();
-    let message = INSTALL_MESSAGE.lock().unwrap();
+    let job = INSTALL_JOB.lock().unwrap().and_then(jobs::get);
+    let message = INSTALL_MESSAGE.lock().unwrap().clone();
 
     let layout = Layout::default()
         .direction(Direction::Vertical)
@@ -126,13 +146,33 @@ pub fn draw_driver_install_output(f: &mut Frame) {
         .constraints([Constraint::Length(3), Constraint::Min(1)])
         .split(area);
 
+    let (percent, label, color) = match &job {
+        Some(j) if j.state == JobState::Running => (0, "Installing...".to_string(), Color::Yellow),
+        Some(j) => (100, j.state.label().to_string(), j.state.color()),
+        None => (0, "Not started".to_string(), Color::Gray),
+    };
     let gauge = Gauge::default()
         .block(Block::default().borders(Borders::ALL).title("Install Progress"))
-        .gauge_style(Style::default().fg(Color::Green).bg(Color::Black))
-        .percent(progress);
+        .gauge_style(Style::default().fg(color).bg(Color::Black))
+        .label(label)
+        .percent(percent);
+
+    let mut lines = vec![
+        Line::raw("Press 'c' to cancel the install. Esc leaves it running in the background."),
+        Line::raw(message),
+    ];
+    if let Some(j) = &job {
+        if !j.summary.is_empty() {
+            lines.push(Line::raw(j.summary.clone()));
+        }
+        lines.push(Line::raw(""));
+        let room = (layout[1].height as usize).saturating_sub(2 + lines.len());
+        lines.extend(jobs::output_tail(j, room));
+    }
 
-    let paragraph = Paragraph::new(Span::raw(message.clone()))
-        .block(Block::default().title("Status").borders(Borders::ALL));
+    let paragraph = Paragraph::new(Text::from(lines))
+        .block(Block::default().title("Status").borders(Borders::ALL))
+        .wrap(Wrap { trim: false });
 
     f.render_widget(gauge, layout[0]);
     f.render_widget(paragraph, layout[1]);
@@ -2,109 +2,163 @@
 use ratatui::{
     layout::{Constraint, Layout},
     style::{Color, Style},
-    text::{Line, Span, Text},
+    text::{Line, Text},
     widgets::{Block, Borders, Gauge, Paragraph},
     Frame,
 };
-use std::{process::Command, sync::Mutex, thread, time::Duration};
+use std::{io, sync::Mutex};
 use once_cell::sync::Lazy;
+use chrono::{DateTime, Local};
 
-pub static EXPORT_ACTIVE: Lazy<Mutex<bool>> = Lazy::new(|| Mutex::new(false));
-pub static EXPORT_PROGRESS: Lazy<Mutex<u16>> = Lazy::new(|| Mutex::new(0));
-pub static EXPORT_MESSAGE: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new(String::new()));
+use crate::command::{CommandOutput, CommandRunner};
+use crate::config;
+use crate::jobs::{self, JobId, JobState};
+use crate::session::{self, TestResult, TestStatus};
 
-pub fn check_export_active() -> bool {
-    *EXPORT_ACTIVE.lock().unwrap()
-}
+static EXPORT_JOB: Lazy<Mutex<Option<JobId>>> = Lazy::new(|| Mutex::new(None));
+static EXPORT_MESSAGE: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new(String::new()));
 
 pub fn exit_export() {
-    *EXPORT_ACTIVE.lock().unwrap() = false;
-    *EXPORT_PROGRESS.lock().unwrap() = 0;
-    *EXPORT_MESSAGE.lock().unwrap() = String::new();
+    EXPORT_MESSAGE.lock().unwrap().clear();
 }
 
-pub fn run_photo_exporter() {
-    *EXPORT_ACTIVE.lock().unwrap() = true;
-    *EXPORT_PROGRESS.lock().unwrap() = 0;
-    *EXPORT_MESSAGE.lock().unwrap() = "Preparing to export photos...".to_string();
-
-    thread::spawn(|| {
-        let base_path = "/home/ecom/Pictures/ebay";
-        let default_start = 84;
-
-        let output = Command::new("bash")
-            .arg("-c")
-            .arg(format!(
-                r#"
-cd "{0}" || exit 1
-last_num=$(ls -d SW* 2>/dev/null | grep -E '^SW[0-9]{{3}}$' | sed 's/SW//' | sort -n | tail -n 1)
+/// `bash` arguments for the export script, filled in from the station config.
+fn export_args() -> Vec<String> {
+    let photos = &config::get().photos;
+    vec![
+        "-c".to_string(),
+        EXPORT_SCRIPT.to_string(),
+        "export_photos".to_string(),
+        photos.base_path.to_string_lossy().to_string(),
+        photos.folder_prefix.clone(),
+        photos.start_number.to_string(),
+    ]
+}
+
+/// Creates the next `SWxxx` folder (prefix and starting number come from the
+/// station config) and pulls every photo off the camera into it.
+pub fn export_photos(runner: &dyn CommandRunner) -> io::Result<CommandOutput> {
+    let args = export_args();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    log::info!("Exporting photos under {}", args[3]);
+    let output = runner.run("bash", &args);
+    match &output {
+        Ok(out) if out.success() => log::info!("Photo export finished"),
+        Ok(out) => log::error!("Photo export exited with {:?}: {}", out.status, out.stderr.trim()),
+        Err(e) => log::error!("Could not run the photo export: {}", e),
+    }
+    output
+}
+
+const EXPORT_SCRIPT: &str = r#"
+base_path="$1"
+prefix="$2"
+default_start="$3"
+cd "$base_path" || exit 1
+last_num=$(ls -d "$prefix"* 2>/dev/null | grep -E "^${prefix}[0-9]{3,}$" | sed "s/^${prefix}//" | sort -n | tail -n 1)
 if [[ -z "$last_num" ]]; then
-  next_num={1}
+  next_num=$default_start
 else
   next_num=$((10#$last_num + 1))
 fi
-if (( next_num >= 100 )); then
-  new_folder="SW$next_num"
-else
-  new_folder=$(printf "SW%03d" "$next_num")
-fi
+new_folder=$(printf "%s%03d" "$prefix" "$next_num")
 mkdir "$new_folder" && cd "$new_folder" || exit 1
+echo "Export folder: $PWD"
 gphoto2 --get-all-files
 cd ..
-                "#,
-                base_path, default_start
-            ))
-            .output();
-
-        for i in 0..=100 {
-            *EXPORT_PROGRESS.lock().unwrap() = i;
-            thread::sleep(Duration::from_millis(20));
+"#;
+
+/// Builds the session result for one export attempt.
+pub fn export_result(output: &io::Result<CommandOutput>, started: DateTime<Local>) -> TestResult {
+    let result = TestResult::new("Photo export", "camera").started(started);
+    match output {
+        Ok(out) => {
+            let result = match out.stdout.lines().find_map(|l| l.strip_prefix("Export folder: ")) {
+                Some(folder) => result.metric("folder", folder.trim()),
+                None => result,
+            };
+            result
+                .status(if out.success() { TestStatus::Pass } else { TestStatus::Fail })
+                .metric("exit_code", out.status.unwrap_or(-1))
+                .raw_output(format!("{}{}", out.stdout, out.stderr))
+                .finish()
         }
+        Err(e) => result.raw_output(format!("Photo export failed: {}", e)).finish(),
+    }
+}
+
+/// Starts the export as a background job unless one is already running.
+pub fn run_photo_exporter() {
+    let mut job = EXPORT_JOB.lock().unwrap();
+    if job.is_some_and(jobs::is_running) {
+        *EXPORT_MESSAGE.lock().unwrap() = "An export is already running.".to_string();
+        return;
+    }
+    EXPORT_MESSAGE.lock().unwrap().clear();
+
+    *job = Some(jobs::spawn("Photo export", |ctx| async move {
+        let started = Local::now();
+        let args = export_args();
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let output = ctx.run_command("bash", &args).await;
+
+        let result = export_result(&output, started);
+        let folder = result.metrics.get("folder").cloned().unwrap_or_default();
+        session::record(result);
 
         match output {
-            Ok(out) => {
-                *EXPORT_MESSAGE.lock().unwrap() = format!(
-                    "Photo export complete:\n{}",
-                    String::from_utf8_lossy(&out.stdout)
-                );
-            }
-            Err(e) => {
-                *EXPORT_MESSAGE.lock().unwrap() = format!("Photo export failed: {}", e);
-            }
+            Ok(out) if out.success() => Ok(format!("Saved to {}", folder)),
+            Ok(out) => Err(format!("Export exited with code {}", out.status.unwrap_or(-1))),
+            Err(e) => Err(format!("Photo export failed: {}", e)),
         }
-    });
+    }));
+}
+
+pub fn cancel_export() {
+    if let Some(id) = *EXPORT_JOB.lock().unwrap() {
+        jobs::cancel(id);
+    }
 }
 
 pub fn draw_photo_export_progress(f: &mut Frame) {
     let size = f.area();
-    let progress = *EXPORT_PROGRESS.lock().unwrap();
-    let message = EXPORT_MESSAGE.lock().unwrap();
+    let job = EXPORT_JOB.lock().unwrap().and_then(jobs::get);
+    let note = EXPORT_MESSAGE.lock().unwrap().clone();
 
     let layout = Layout::default()
         .constraints([Constraint::Length(3), Constraint::Min(1)].as_ref())
         .margin(2)
         .split(size);
 
+    // gphoto2 does not say how many files are coming, so count what it saved.
+    let saved = job
+        .as_ref()
+        .map_or(0, |j| j.output.iter().filter(|l| l.starts_with("Saving file as")).count());
+    let (percent, label, color) = match &job {
+        Some(j) if j.state == JobState::Running => (0, format!("{} files saved...", saved), Color::Yellow),
+        Some(j) => (100, format!("{} ({} files)", j.state.label(), saved), j.state.color()),
+        None => (0, "Not started".to_string(), Color::Gray),
+    };
     let gauge = Gauge::default()
         .block(Block::default().title("Export Progress").borders(Borders::ALL))
-        .gauge_style(
-            Style::default()
-                .fg(if progress < 50 {
-                    Color::Red
-                } else if progress < 80 {
-                    Color::Yellow
-                } else {
-                    Color::Green
-                })
-                .bg(Color::Black),
-        )
-        .label(format!("{}%", progress))
-        .percent(progress);
-
-    let lines: Vec<Line> = message
-        .lines()
-        .map(|line| Line::raw(line.to_string()))
-        .collect();
+        .gauge_style(Style::default().fg(color).bg(Color::Black))
+        .label(label)
+        .percent(percent);
+
+    let mut lines = vec![Line::raw(
+        "Press 'c' to cancel the export. Esc leaves it running in the background.",
+    )];
+    if !note.is_empty() {
+        lines.push(Line::raw(note));
+    }
+    if let Some(j) = &job {
+        if !j.summary.is_empty() {
+            lines.push(Line::raw(j.summary.clone()));
+        }
+        lines.push(Line::raw(""));
+        let room = (layout[1].height as usize).saturating_sub(2 + lines.len());
+        lines.extend(jobs::output_tail(j, room));
+    }
 
     let paragraph = Paragraph::new(Text::from(lines))
         .block(Block::default().borders(Borders::ALL).title("Status"))
@@ -112,4 +166,4 @@ pub fn draw_photo_export_progress(f: &mut Frame) {
 
     f.render_widget(gauge, layout[0]);
     f.render_widget(paragraph, layout[1]);
-}
\ No newline at end of file
+}
@@ -0,0 +1,204 @@
+use once_cell::sync::Lazy;
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::Span,
+    widgets::{Paragraph, Row, Table},
+    Frame,
+};
+use serde::Serialize;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use crate::command::{CommandRunner, SystemRunner};
+use crate::config;
+use crate::theme::bordered_block;
+
+/// Menu features that depend on external tools.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Feature {
+    Smart,
+    GpuTest,
+    PhotoExport,
+    DriverInstall,
+    KeyboardTest,
+    GamepadTest,
+}
+
+impl Feature {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Feature::Smart => "SMART test",
+            Feature::GpuTest => "GPU tests",
+            Feature::PhotoExport => "Photo exporter",
+            Feature::DriverInstall => "Driver installer",
+            Feature::KeyboardTest => "Keyboard test",
+            Feature::GamepadTest => "Gamepad test",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ToolStatus {
+    pub name: String,
+    pub path: Option<PathBuf>,
+    pub version: Option<String>,
+    pub features: Vec<Feature>,
+}
+
+impl ToolStatus {
+    pub fn found(&self) -> bool {
+        self.path.is_some()
+    }
+}
+
+/// Program name of a configured shell command, skipping a leading `sudo`.
+pub fn command_program(command: &str) -> Option<String> {
+    command
+        .split_whitespace()
+        .find(|word| *word != "sudo" && !word.contains('='))
+        .map(String::from)
+}
+
+/// Every tool the app shells out to, with the features that need it. The
+/// driver tools follow the station config, so a pacman station checks pacman.
+/// `hdparm` and `nvme` are left out: without them a wipe falls back to
+/// overwriting and its plan says why.
+pub fn required_tools() -> Vec<(String, Vec<Feature>)> {
+    let drivers = &config::get().drivers;
+    let mut tools: Vec<(String, Vec<Feature>)> = vec![
+        ("lsblk".to_string(), vec![Feature::Smart]),
+        ("smartctl".to_string(), vec![Feature::Smart]),
+        ("lspci".to_string(), vec![Feature::GpuTest]),
+        ("lsusb".to_string(), vec![Feature::KeyboardTest, Feature::GamepadTest]),
+        ("gphoto2".to_string(), vec![Feature::PhotoExport]),
+        ("bash".to_string(), vec![Feature::PhotoExport, Feature::DriverInstall]),
+    ];
+
+    for command in [&drivers.install_command, &drivers.post_install_command] {
+        let Some(program) = command_program(command) else { continue };
+        match tools.iter_mut().find(|(name, _)| *name == program) {
+            Some((_, features)) if !features.contains(&Feature::DriverInstall) => {
+                features.push(Feature::DriverInstall)
+            }
+            Some(_) => {}
+            None => tools.push((program, vec![Feature::DriverInstall])),
+        }
+    }
+    tools
+}
+
+/// First non-empty line of `<tool> --version`; some tools print it on stderr.
+pub fn tool_version(runner: &dyn CommandRunner, tool: &str) -> Option<String> {
+    let output = runner.run(tool, &["--version"]).ok()?;
+    output
+        .stdout
+        .lines()
+        .chain(output.stderr.lines())
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .map(String::from)
+}
+
+pub fn check_tools(
+    runner: &dyn CommandRunner,
+    tools: Vec<(String, Vec<Feature>)>,
+    lookup: impl Fn(&str) -> Option<PathBuf>,
+) -> Vec<ToolStatus> {
+    tools
+        .into_iter()
+        .map(|(name, features)| {
+            let path = lookup(&name);
+            let version = path.as_ref().and_then(|_| tool_version(runner, &name));
+            ToolStatus {
+                name,
+                path,
+                version,
+                features,
+            }
+        })
+        .collect()
+}
+
+/// Tools from `statuses` that are missing and needed by `feature`.
+pub fn missing_for(statuses: &[ToolStatus], feature: Feature) -> Vec<String> {
+    statuses
+        .iter()
+        .filter(|t| !t.found() && t.features.contains(&feature))
+        .map(|t| t.name.clone())
+        .collect()
+}
+
+static RESULTS: Lazy<Mutex<Vec<ToolStatus>>> = Lazy::new(|| Mutex::new(Vec::new()));
+
+/// Looks every tool up on `PATH` and keeps the results for the menu.
+pub fn run_preflight() -> Vec<ToolStatus> {
+    let statuses = check_tools(&SystemRunner, required_tools(), |tool| which::which(tool).ok());
+    for tool in statuses.iter().filter(|t| !t.found()) {
+        let features: Vec<&str> = tool.features.iter().map(Feature::label).collect();
+        log::warn!("{} not found; disables {}", tool.name, features.join(", "));
+    }
+    *RESULTS.lock().unwrap() = statuses.clone();
+    statuses
+}
+
+pub fn results() -> Vec<ToolStatus> {
+    RESULTS.lock().unwrap().clone()
+}
+
+/// Missing tools that keep `feature` from running, from the last preflight.
+pub fn missing_tools(feature: Feature) -> Vec<String> {
+    missing_for(&RESULTS.lock().unwrap(), feature)
+}
+
+pub fn draw_preflight(f: &mut Frame) {
+    let statuses = results();
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([Constraint::Min(3), Constraint::Length(3)])
+        .split(f.area());
+
+    let rows: Vec<Row> = statuses
+        .iter()
+        .map(|tool| {
+            let (label, color) = if tool.found() {
+                ("found", Color::Green)
+            } else {
+                ("MISSING", Color::Red)
+            };
+            let features: Vec<&str> = tool.features.iter().map(Feature::label).collect();
+            Row::new(vec![
+                Span::raw(tool.name.clone()),
+                Span::styled(label, Style::default().fg(color)),
+                Span::raw(tool.version.clone().unwrap_or_else(|| "-".to_string())),
+                Span::raw(features.join(", ")),
+            ])
+        })
+        .collect();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(12),
+            Constraint::Length(8),
+            Constraint::Percentage(45),
+            Constraint::Min(10),
+        ],
+    )
+    .header(
+        Row::new(vec!["Tool", "Status", "Version", "Needed for"])
+            .style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+    )
+    .block(bordered_block("Dependency Check"));
+
+    let info = Paragraph::new("Press 'r' to check again. Press Esc to go back.")
+        .block(bordered_block("Instructions"));
+
+    f.render_widget(table, chunks[0]);
+    f.render_widget(info, chunks[1]);
+}
+
+#[cfg(test)]
+mod tests;
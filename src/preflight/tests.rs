@@ -0,0 +1,59 @@
+use super::*;
+use crate::command::FixtureRunner;
+
+fn tools() -> Vec<(String, Vec<Feature>)> {
+    vec![
+        ("lsblk".to_string(), vec![Feature::Smart]),
+        ("smartctl".to_string(), vec![Feature::Smart]),
+        ("gphoto2".to_string(), vec![Feature::PhotoExport]),
+        ("lsusb".to_string(), vec![Feature::KeyboardTest, Feature::GamepadTest]),
+    ]
+}
+
+#[test]
+fn reports_found_tools_with_versions() {
+    let runner = FixtureRunner::new()
+        .with("lsblk --version", "lsblk_version")
+        .with("smartctl --version", "smartctl_version")
+        .with("gphoto2 --version", "gphoto2_version");
+    let statuses = check_tools(&runner, tools(), |tool| {
+        (tool != "lsusb").then(|| PathBuf::from("/usr/bin").join(tool))
+    });
+
+    assert_eq!(statuses[0].version.as_deref(), Some("lsblk from util-linux 2.39.3"));
+    assert!(statuses[1].version.as_deref().unwrap().starts_with("smartctl 7.4"));
+    // gphoto2 prints its version on stderr.
+    assert_eq!(statuses[2].version.as_deref(), Some("gphoto2 2.5.28"));
+    assert!(!statuses[3].found());
+    assert_eq!(statuses[3].version, None);
+    assert!(!runner.calls().contains(&"lsusb --version".to_string()));
+}
+
+#[test]
+fn missing_tool_gates_its_features() {
+    let statuses = check_tools(&FixtureRunner::new(), tools(), |tool| {
+        (tool != "smartctl" && tool != "lsusb").then(|| PathBuf::from(tool))
+    });
+
+    assert_eq!(missing_for(&statuses, Feature::Smart), vec!["smartctl"]);
+    assert_eq!(missing_for(&statuses, Feature::GamepadTest), vec!["lsusb"]);
+    assert!(missing_for(&statuses, Feature::PhotoExport).is_empty());
+}
+
+#[test]
+fn finds_program_in_configured_command() {
+    assert_eq!(command_program("sudo aura -A --noconfirm {package}").as_deref(), Some("aura"));
+    assert_eq!(command_program("mkinitcpio -P").as_deref(), Some("mkinitcpio"));
+    assert_eq!(command_program("sudo LANG=C pacman -S {package}").as_deref(), Some("pacman"));
+    assert_eq!(command_program("   "), None);
+}
+
+#[test]
+fn default_config_checks_aura_and_mkinitcpio() {
+    let tools = required_tools();
+    let names: Vec<&str> = tools.iter().map(|(name, _)| name.as_str()).collect();
+    assert_eq!(
+        names,
+        vec!["lsblk", "smartctl", "lspci", "lsusb", "gphoto2", "bash", "aura", "mkinitcpio"]
+    );
+}
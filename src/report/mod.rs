@@ -0,0 +1,225 @@
+use chrono::Local;
+use once_cell::sync::Lazy;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::command::SystemRunner;
+use crate::config;
+use crate::gpu_detect::detect_gpu_type;
+use crate::session::{self, Session, TestResult, TestStatus};
+
+pub static REPORT_MESSAGE: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new(String::new()));
+
+pub const STYLE: &str = r#"
+body { font-family: "DejaVu Sans", Arial, sans-serif; margin: 2em; color: #222; }
+h1 { margin-bottom: 0; }
+.subtitle { color: #666; margin-top: 0.2em; }
+table { border-collapse: collapse; width: 100%; margin-bottom: 1.5em; }
+th, td { border: 1px solid #bbb; padding: 0.35em 0.6em; text-align: left; vertical-align: top; }
+th { background: #f0f0f0; }
+.status { font-weight: bold; }
+.pass { color: #1b7f2a; }
+.fail { color: #b71c1c; }
+.warn { color: #b26a00; }
+.skipped { color: #666; }
+.overall { font-size: 1.4em; padding: 0.4em 0.8em; border: 2px solid currentColor; display: inline-block; }
+footer { margin-top: 2em; font-size: 0.85em; color: #666; }
+@media print { body { margin: 0.5in; } }
+"#;
+
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn status_class(status: TestStatus) -> &'static str {
+    match status {
+        TestStatus::Pass => "pass",
+        TestStatus::Fail => "fail",
+        TestStatus::Warn => "warn",
+        TestStatus::Skipped => "skipped",
+    }
+}
+
+fn status_cell(status: TestStatus) -> String {
+    format!(
+        r#"<span class="status {}">{}</span>"#,
+        status_class(status),
+        status.label()
+    )
+}
+
+fn metric<'a>(result: &'a TestResult, key: &str) -> &'a str {
+    result.metrics.get(key).map(String::as_str).unwrap_or("Unknown")
+}
+
+fn smart_section(session: &Session) -> String {
+    let drives: Vec<&TestResult> = session.results.iter().filter(|r| r.test == "SMART").collect();
+    if drives.is_empty() {
+        return "<p>No drives were tested.</p>".to_string();
+    }
+
+    let mut html = String::from(
+        "<table><tr><th>Device</th><th>Model</th><th>Family</th><th>Capacity</th>\
+         <th>Temperature (°C)</th><th>Power-on hours</th><th>Health</th><th>Result</th></tr>",
+    );
+    for drive in drives {
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            escape(&drive.device),
+            escape(metric(drive, "model")),
+            escape(metric(drive, "family")),
+            escape(metric(drive, "capacity")),
+            escape(metric(drive, "temperature_c")),
+            escape(metric(drive, "power_on_hours")),
+            escape(metric(drive, "health")),
+            status_cell(drive.status),
+        ));
+    }
+    html.push_str("</table>");
+    html
+}
+
+fn results_section(session: &Session) -> String {
+    if session.results.is_empty() {
+        return "<p>No tests were recorded.</p>".to_string();
+    }
+
+    let mut html = String::from(
+        "<table><tr><th>Test</th><th>Device</th><th>Result</th><th>Started</th>\
+         <th>Finished</th><th>Details</th></tr>",
+    );
+    for result in &session.results {
+        let details = result
+            .metrics
+            .iter()
+            .map(|(k, v)| format!("{}: {}", escape(k), escape(v)))
+            .collect::<Vec<_>>()
+            .join("<br>");
+        html.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+            escape(&result.test),
+            escape(&result.device),
+            status_cell(result.status),
+            result.started_at.format("%Y-%m-%d %H:%M:%S"),
+            result.finished_at.format("%Y-%m-%d %H:%M:%S"),
+            details,
+        ));
+    }
+    html.push_str("</table>");
+    html
+}
+
+fn outcome(session: &Session, test: &str) -> String {
+    match session.find(test) {
+        Some(result) => status_cell(result.status),
+        None => "Not tested".to_string(),
+    }
+}
+
+/// Renders a self-contained, printable HTML certificate for one unit.
+pub fn render_html(session: &Session, unit: &str, gpu_type: &str) -> String {
+    let overall = session.overall_status();
+
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>Test Report {unit}</title>
+<style>{style}</style>
+</head>
+<body>
+<h1>Refurbishment Test Report</h1>
+<p class="subtitle">Unit {unit}</p>
+<p class="overall {overall_class}">Overall: {overall_label}</p>
+
+<h2>Unit</h2>
+<table>
+<tr><th>Unit</th><td>{unit}</td></tr>
+<tr><th>Operator</th><td>{operator}</td></tr>
+<tr><th>Session started</th><td>{started}</td></tr>
+<tr><th>Report generated</th><td>{generated}</td></tr>
+<tr><th>GPU type</th><td>{gpu_type}</td></tr>
+<tr><th>Keyboard test</th><td>{keyboard}</td></tr>
+<tr><th>Audio test</th><td>{audio}</td></tr>
+</table>
+
+<h2>Drives</h2>
+{smart}
+
+<h2>All Tests</h2>
+{results}
+
+<footer>Generated by electronics_tui_tester {version}</footer>
+</body>
+</html>
+"#,
+        unit = escape(unit),
+        style = STYLE,
+        overall_class = status_class(overall),
+        overall_label = overall.label(),
+        operator = escape(&session.operator),
+        started = session.started_at.format("%Y-%m-%d %H:%M:%S"),
+        generated = Local::now().format("%Y-%m-%d %H:%M:%S"),
+        gpu_type = escape(gpu_type),
+        keyboard = outcome(session, "Keyboard"),
+        audio = outcome(session, "Audio"),
+        smart = smart_section(session),
+        results = results_section(session),
+        version = env!("CARGO_PKG_VERSION"),
+    )
+}
+
+/// Where the report goes: beside the unit's `SWxxx` photo folder when one was
+/// exported this session, otherwise a timestamped file in the photo base folder.
+pub fn report_path(session: &Session) -> (String, PathBuf) {
+    let folder = session
+        .find("Photo export")
+        .and_then(|r| r.metrics.get("folder"))
+        .map(PathBuf::from);
+
+    match folder {
+        Some(folder) => {
+            let unit = folder
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| "unit".to_string());
+            let parent = folder
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_else(|| config::get().photos.base_path.clone());
+            let path = parent.join(format!("{}-report.html", unit));
+            (unit, path)
+        }
+        None => {
+            let unit = format!("unsaved-{}", session.started_at.format("%Y%m%d-%H%M%S"));
+            let path = config::get().photos.base_path.join(format!("{}-report.html", unit));
+            (unit, path)
+        }
+    }
+}
+
+pub fn save_report(session: &Session) -> io::Result<PathBuf> {
+    let (unit, path) = report_path(session);
+    let gpu_type = format!("{:?}", detect_gpu_type(&SystemRunner));
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, render_html(session, &unit, &gpu_type))?;
+    Ok(path)
+}
+
+/// Saves the report for the current session and keeps the outcome for display.
+pub fn generate_current_report() {
+    let message = match save_report(&session::current()) {
+        Ok(path) => format!("Report saved to {}", path.display()),
+        Err(e) => format!("Failed to save report: {}", e),
+    };
+    log::info!("{}", message);
+    *REPORT_MESSAGE.lock().unwrap() = message;
+}
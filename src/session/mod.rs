@@ -0,0 +1,254 @@
+use chrono::{DateTime, Local};
+use once_cell::sync::Lazy;
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span, Text},
+    widgets::{Paragraph, Row, Table},
+    Frame,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+use crate::config;
+use crate::report::REPORT_MESSAGE;
+use crate::theme::{bordered_block, info_box};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TestStatus {
+    Pass,
+    Fail,
+    Warn,
+    Skipped,
+}
+
+impl TestStatus {
+    pub fn label(&self) -> &'static str {
+        match self {
+            TestStatus::Pass => "PASS",
+            TestStatus::Fail => "FAIL",
+            TestStatus::Warn => "WARN",
+            TestStatus::Skipped => "SKIPPED",
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            TestStatus::Pass => Color::Green,
+            TestStatus::Fail => Color::Red,
+            TestStatus::Warn => Color::Yellow,
+            TestStatus::Skipped => Color::Gray,
+        }
+    }
+}
+
+/// The outcome of one test run against one device.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestResult {
+    pub test: String,
+    pub device: String,
+    pub status: TestStatus,
+    pub metrics: BTreeMap<String, String>,
+    pub started_at: DateTime<Local>,
+    pub finished_at: DateTime<Local>,
+    pub raw_output: String,
+}
+
+impl TestResult {
+    pub fn new(test: &str, device: &str) -> Self {
+        let now = Local::now();
+        Self {
+            test: test.to_string(),
+            device: device.to_string(),
+            status: TestStatus::Skipped,
+            metrics: BTreeMap::new(),
+            started_at: now,
+            finished_at: now,
+            raw_output: String::new(),
+        }
+    }
+
+    pub fn started(mut self, at: DateTime<Local>) -> Self {
+        self.started_at = at;
+        self
+    }
+
+    pub fn status(mut self, status: TestStatus) -> Self {
+        self.status = status;
+        self
+    }
+
+    pub fn metric(mut self, key: &str, value: impl ToString) -> Self {
+        self.metrics.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    pub fn raw_output(mut self, output: impl Into<String>) -> Self {
+        self.raw_output = output.into();
+        self
+    }
+
+    /// Stamps the finish time; call this last when building a result.
+    pub fn finish(mut self) -> Self {
+        self.finished_at = Local::now();
+        self
+    }
+}
+
+/// Everything recorded for the unit currently on the bench.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Session {
+    pub operator: String,
+    pub started_at: DateTime<Local>,
+    pub results: Vec<TestResult>,
+}
+
+impl Session {
+    pub fn new(operator: &str) -> Self {
+        Self {
+            operator: operator.to_string(),
+            started_at: Local::now(),
+            results: Vec::new(),
+        }
+    }
+
+    /// Adds a result, replacing an earlier run of the same test on the same device.
+    pub fn record(&mut self, result: TestResult) {
+        self.results
+            .retain(|r| !(r.test == result.test && r.device == result.device));
+        self.results.push(result);
+    }
+
+    /// Changes the result of `test` on `device` in place; false when there is none.
+    pub fn update(&mut self, test: &str, device: &str, change: impl FnOnce(&mut TestResult)) -> bool {
+        match self.results.iter_mut().find(|r| r.test == test && r.device == device) {
+            Some(result) => {
+                change(result);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn find(&self, test: &str) -> Option<&TestResult> {
+        self.results.iter().rev().find(|r| r.test == test)
+    }
+
+    /// Worst status across all results: any failure fails the unit.
+    pub fn overall_status(&self) -> TestStatus {
+        let statuses: Vec<TestStatus> = self.results.iter().map(|r| r.status).collect();
+        if statuses.contains(&TestStatus::Fail) {
+            TestStatus::Fail
+        } else if statuses.contains(&TestStatus::Warn) {
+            TestStatus::Warn
+        } else if statuses.contains(&TestStatus::Pass) {
+            TestStatus::Pass
+        } else {
+            TestStatus::Skipped
+        }
+    }
+}
+
+pub static SESSION: Lazy<Mutex<Session>> = Lazy::new(|| Mutex::new(Session::new(&default_operator())));
+
+fn default_operator() -> String {
+    let operator = &config::get().session.operator;
+    if !operator.is_empty() {
+        return operator.clone();
+    }
+    std::env::var("USER").unwrap_or_else(|_| "unknown".to_string())
+}
+
+pub fn record(result: TestResult) {
+    SESSION.lock().unwrap().record(result);
+}
+
+pub fn update(test: &str, device: &str, change: impl FnOnce(&mut TestResult)) -> bool {
+    SESSION.lock().unwrap().update(test, device, change)
+}
+
+pub fn current() -> Session {
+    SESSION.lock().unwrap().clone()
+}
+
+/// Starts a fresh session for the next unit, keeping the operator.
+pub fn reset() {
+    let mut session = SESSION.lock().unwrap();
+    let operator = session.operator.clone();
+    *session = Session::new(&operator);
+    REPORT_MESSAGE.lock().unwrap().clear();
+}
+
+pub fn draw_session_summary(f: &mut Frame) {
+    let session = current();
+    let overall = session.overall_status();
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([Constraint::Length(5), Constraint::Min(3), Constraint::Length(4)])
+        .split(f.area());
+
+    let started = session.started_at.format("%Y-%m-%d %H:%M:%S").to_string();
+    let header = Paragraph::new(Text::from(vec![
+        info_box("Operator", &session.operator),
+        info_box("Started", &started),
+        Line::from(vec![
+            Span::raw("Overall: "),
+            Span::styled(
+                overall.label(),
+                Style::default().fg(overall.color()).add_modifier(Modifier::BOLD),
+            ),
+        ]),
+    ]))
+    .block(bordered_block("Unit Under Test"));
+
+    let rows: Vec<Row> = session
+        .results
+        .iter()
+        .map(|r| {
+            let metrics = r
+                .metrics
+                .iter()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>()
+                .join(", ");
+            Row::new(vec![
+                Span::raw(r.test.clone()),
+                Span::raw(r.device.clone()),
+                Span::styled(r.status.label(), Style::default().fg(r.status.color())),
+                Span::raw(r.finished_at.format("%H:%M:%S").to_string()),
+                Span::raw(metrics),
+            ])
+        })
+        .collect();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(16),
+            Constraint::Length(20),
+            Constraint::Length(8),
+            Constraint::Length(9),
+            Constraint::Min(10),
+        ],
+    )
+    .header(
+        Row::new(vec!["Test", "Device", "Status", "Finished", "Metrics"])
+            .style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+    )
+    .block(bordered_block("Results"));
+
+    let report_message = REPORT_MESSAGE.lock().unwrap().clone();
+    let info = Paragraph::new(Text::from(vec![
+        Line::raw("Press 'r' to save the HTML report, 'n' to start a new unit. Press Esc to go back."),
+        Line::raw(report_message),
+    ]))
+    .block(bordered_block("Instructions"));
+
+    f.render_widget(header, chunks[0]);
+    f.render_widget(table, chunks[1]);
+    f.render_widget(info, chunks[2]);
+}
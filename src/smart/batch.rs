@@ -0,0 +1,352 @@
+use chrono::Local;
+use once_cell::sync::Lazy;
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::Span,
+    widgets::{Paragraph, Row, Table, TableState},
+    Frame,
+};
+use serde::Serialize;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use super::{read_smart, record_reading, SmartData, SmartSummary, SMART_DATA, SMART_DELTA, SMART_OUTPUT, SMART_SCROLL};
+use crate::command::SystemRunner;
+use crate::config;
+use crate::history::SmartDelta;
+use crate::jobs::{self, JobId};
+use crate::session::{self, TestResult};
+use crate::theme::{bordered_block, highlight_style};
+
+#[derive(Debug, Clone)]
+pub enum RowState {
+    Reading,
+    Done(Box<SmartData>),
+    Error(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct BatchRow {
+    pub device: String,
+    pub state: RowState,
+    /// Change since the drive's previous visit, once read.
+    pub delta: Option<SmartDelta>,
+}
+
+/// One exported row. Unreadable drives keep their device and error.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BatchRecord {
+    pub device: String,
+    pub model: String,
+    pub serial: String,
+    pub capacity: String,
+    pub power_on_hours: String,
+    pub temperature_c: String,
+    pub grade: String,
+    pub reasons: Vec<String>,
+    pub degrading: bool,
+    pub since_last_test: Vec<String>,
+    pub error: Option<String>,
+}
+
+impl BatchRecord {
+    pub fn from_row(row: &BatchRow) -> Self {
+        let summary = match &row.state {
+            RowState::Done(data) => SmartSummary::from_data(data),
+            _ => SmartSummary::from_data(&SmartData::default()),
+        };
+        let error = match &row.state {
+            RowState::Done(_) => None,
+            RowState::Reading => Some("Not read".to_string()),
+            RowState::Error(e) => Some(e.clone()),
+        };
+        BatchRecord {
+            device: row.device.clone(),
+            model: summary.model,
+            serial: summary.serial,
+            capacity: summary.capacity,
+            power_on_hours: summary.power_on_hours,
+            temperature_c: summary.temperature,
+            grade: summary.health,
+            reasons: summary.reasons,
+            degrading: row.delta.as_ref().is_some_and(SmartDelta::degrading),
+            since_last_test: row.delta.as_ref().map(SmartDelta::lines).unwrap_or_default(),
+            error,
+        }
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub fn to_csv(records: &[BatchRecord]) -> String {
+    let mut csv = String::from("device,model,serial,capacity,power_on_hours,temperature_c,grade,reasons,degrading,since_last_test,error\n");
+    for r in records {
+        let degrading = r.degrading.to_string();
+        let fields = [
+            r.device.as_str(),
+            &r.model,
+            &r.serial,
+            &r.capacity,
+            &r.power_on_hours,
+            &r.temperature_c,
+            &r.grade,
+            &r.reasons.join("; "),
+            &degrading,
+            &r.since_last_test.join("; "),
+            r.error.as_deref().unwrap_or(""),
+        ];
+        let line: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+        csv.push_str(&line.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// `$XDG_DATA_HOME/electronics_tui_tester/batches`, unless the config says otherwise.
+pub fn export_dir() -> PathBuf {
+    if let Some(dir) = &config::get().smart.export_dir {
+        return dir.clone();
+    }
+    dirs::data_local_dir()
+        .map(|dir| dir.join("electronics_tui_tester").join("batches"))
+        .unwrap_or_else(|| PathBuf::from("batches"))
+}
+
+/// Writes `smart-batch-<time>.csv` and `.json` into `dir` and returns both paths.
+pub fn export(records: &[BatchRecord], dir: &Path) -> io::Result<(PathBuf, PathBuf)> {
+    fs::create_dir_all(dir)?;
+    let stem = format!("smart-batch-{}", Local::now().format("%Y%m%d-%H%M%S"));
+    let csv_path = dir.join(format!("{}.csv", stem));
+    let json_path = dir.join(format!("{}.json", stem));
+    fs::write(&csv_path, to_csv(records))?;
+    let json = serde_json::to_string_pretty(records).map_err(io::Error::other)?;
+    fs::write(&json_path, json)?;
+    Ok((csv_path, json_path))
+}
+
+static ROWS: Lazy<Mutex<Vec<BatchRow>>> = Lazy::new(|| Mutex::new(Vec::new()));
+static BATCH_JOB: Lazy<Mutex<Option<JobId>>> = Lazy::new(|| Mutex::new(None));
+static SELECTED_ROW: Lazy<Mutex<usize>> = Lazy::new(|| Mutex::new(0));
+static BATCH_MESSAGE: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new(String::new()));
+
+fn set_row(device: &str, state: RowState, delta: Option<SmartDelta>) {
+    if let Some(row) = ROWS.lock().unwrap().iter_mut().find(|r| r.device == device) {
+        row.state = state;
+        row.delta = delta;
+    }
+}
+
+/// Reads every device at once in a background job, recording one SMART
+/// result per drive. Returns false while another batch is still running.
+pub fn start_batch(devices: Vec<String>) -> bool {
+    let mut job = BATCH_JOB.lock().unwrap();
+    if job.is_some_and(jobs::is_running) {
+        return false;
+    }
+
+    *ROWS.lock().unwrap() = devices
+        .iter()
+        .map(|device| BatchRow {
+            device: device.clone(),
+            state: RowState::Reading,
+            delta: None,
+        })
+        .collect();
+    *SELECTED_ROW.lock().unwrap() = 0;
+    BATCH_MESSAGE.lock().unwrap().clear();
+
+    let name = format!("SMART batch ({} drives)", devices.len());
+    *job = Some(jobs::spawn(&name, move |ctx| async move {
+        let total = devices.len().max(1);
+        let mut tasks = tokio::task::JoinSet::new();
+        for device in devices {
+            tasks.spawn_blocking(move || {
+                let data = read_smart(&SystemRunner, &device);
+                (device, data)
+            });
+        }
+
+        let token = ctx.token();
+        let mut done = 0;
+        loop {
+            let next = tokio::select! {
+                _ = token.cancelled() => break,
+                next = tasks.join_next() => next,
+            };
+            let Some(joined) = next else { break };
+            let (device, data) = joined.map_err(|e| e.to_string())?;
+
+            let (state, delta) = match data {
+                Ok(data) => {
+                    let delta = record_reading(&device, &data);
+                    let degrading = if delta.as_ref().is_some_and(SmartDelta::degrading) {
+                        ", degrading"
+                    } else {
+                        ""
+                    };
+                    let grade = SmartSummary::from_data(&data).health;
+                    ctx.output(format!("{}: {}{}", device, grade, degrading));
+                    (RowState::Done(Box::new(data)), delta)
+                }
+                Err(e) => {
+                    let message = format!("Failed to run smartctl: {}", e);
+                    ctx.output(format!("{}: {}", device, message));
+                    session::record(TestResult::new("SMART", &device).raw_output(message.clone()).finish());
+                    (RowState::Error(message), None)
+                }
+            };
+            set_row(&device, state, delta);
+            done += 1;
+            ctx.progress((done * 100 / total) as u16);
+        }
+
+        for row in ROWS.lock().unwrap().iter_mut() {
+            if matches!(row.state, RowState::Reading) {
+                row.state = RowState::Error("Cancelled".to_string());
+            }
+        }
+        Ok(format!("Read {} of {} drives", done, total))
+    }));
+    true
+}
+
+/// Batch over every drive in the disk list.
+pub fn start_batch_from_list() {
+    let devices = super::get_drive_list().into_iter().map(|drive| drive.path).collect();
+    if !start_batch(devices) {
+        *BATCH_MESSAGE.lock().unwrap() = "A batch is already running.".to_string();
+    }
+}
+
+pub fn cancel_batch() {
+    if let Some(id) = *BATCH_JOB.lock().unwrap() {
+        jobs::cancel(id);
+    }
+}
+
+pub fn previous_row() {
+    let mut index = SELECTED_ROW.lock().unwrap();
+    *index = index.saturating_sub(1);
+}
+
+pub fn next_row() {
+    let count = ROWS.lock().unwrap().len();
+    let mut index = SELECTED_ROW.lock().unwrap();
+    if *index < count.saturating_sub(1) {
+        *index += 1;
+    }
+}
+
+/// Loads the selected drive into the SMART detail view. False while it is still being read.
+pub fn open_selected_row() -> bool {
+    let index = *SELECTED_ROW.lock().unwrap();
+    let Some(row) = ROWS.lock().unwrap().get(index).cloned() else {
+        return false;
+    };
+    *SMART_SCROLL.lock().unwrap() = 0;
+    *SMART_DELTA.lock().unwrap() = row.delta;
+    match row.state {
+        RowState::Reading => return false,
+        RowState::Done(data) => *SMART_DATA.lock().unwrap() = Some(*data),
+        RowState::Error(e) => {
+            *SMART_DATA.lock().unwrap() = None;
+            *SMART_OUTPUT.lock().unwrap() = e;
+        }
+    }
+    true
+}
+
+pub fn export_batch() {
+    let records: Vec<BatchRecord> = ROWS.lock().unwrap().iter().map(BatchRecord::from_row).collect();
+    let message = match export(&records, &export_dir()) {
+        Ok((csv, json)) => format!("Exported {} and {}", csv.display(), json.display()),
+        Err(e) => format!("Export failed: {}", e),
+    };
+    log::info!("{}", message);
+    *BATCH_MESSAGE.lock().unwrap() = message;
+}
+
+pub fn draw_batch(f: &mut Frame) {
+    let rows = ROWS.lock().unwrap().clone();
+    let selected = *SELECTED_ROW.lock().unwrap();
+    let job = BATCH_JOB.lock().unwrap().and_then(jobs::get);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([Constraint::Min(3), Constraint::Length(4)])
+        .split(f.area());
+
+    let table_rows: Vec<Row> = rows
+        .iter()
+        .map(|row| {
+            let record = BatchRecord::from_row(row);
+            let (grade, color) = match &row.state {
+                RowState::Reading => ("reading...".to_string(), Color::Cyan),
+                RowState::Error(_) => ("error".to_string(), Color::Red),
+                RowState::Done(data) => {
+                    let summary = SmartSummary::from_data(data);
+                    match record.degrading {
+                        true => (format!("{} (degrading)", summary.health), Color::Red),
+                        false => (summary.health, summary.grade.color()),
+                    }
+                }
+            };
+            Row::new(vec![
+                Span::raw(record.device),
+                Span::raw(record.model),
+                Span::raw(record.serial),
+                Span::raw(record.capacity),
+                Span::raw(record.power_on_hours),
+                Span::styled(grade, Style::default().fg(color)),
+            ])
+        })
+        .collect();
+
+    let title = match &job {
+        Some(job) if job.state == jobs::JobState::Running => {
+            format!("SMART Batch ({}%)", job.progress.unwrap_or(0))
+        }
+        _ => format!("SMART Batch ({} drives)", rows.len()),
+    };
+    let table = Table::new(
+        table_rows,
+        [
+            Constraint::Length(14),
+            Constraint::Percentage(30),
+            Constraint::Length(20),
+            Constraint::Length(11),
+            Constraint::Length(8),
+            Constraint::Min(8),
+        ],
+    )
+    .header(
+        Row::new(vec!["Device", "Model", "Serial", "Capacity", "Hours", "Grade"])
+            .style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+    )
+    .row_highlight_style(highlight_style())
+    .highlight_symbol("▶ ")
+    .block(bordered_block(&title));
+
+    let mut state = TableState::default();
+    state.select(Some(selected));
+
+    let message = BATCH_MESSAGE.lock().unwrap().clone();
+    let info = Paragraph::new(vec![
+        "↑/↓ select, Enter: details, e: export CSV/JSON, c: cancel, Esc: back".into(),
+        message.into(),
+    ])
+    .block(bordered_block("Instructions"));
+
+    f.render_stateful_widget(table, chunks[0], &mut state);
+    f.render_widget(info, chunks[1]);
+}
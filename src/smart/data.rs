@@ -0,0 +1,318 @@
+use serde::{Deserialize, Serialize};
+
+/// The parts of `smartctl -a --json` the app uses. smartctl leaves out
+/// whole sections that don't apply to a device (no ATA table on NVMe, no
+/// NVMe log on SATA), so nearly everything is optional.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SmartData {
+    pub smartctl: SmartctlInfo,
+    pub device: DeviceInfo,
+    pub model_family: Option<String>,
+    pub model_name: Option<String>,
+    pub scsi_model_name: Option<String>,
+    pub serial_number: Option<String>,
+    pub firmware_version: Option<String>,
+    pub user_capacity: Option<Capacity>,
+    pub nvme_total_capacity: Option<u64>,
+    pub logical_block_size: Option<u32>,
+    pub rotation_rate: Option<u32>,
+    pub smart_status: Option<SmartStatus>,
+    pub temperature: Option<Temperature>,
+    pub power_on_time: Option<PowerOnTime>,
+    pub power_cycle_count: Option<u64>,
+    pub ata_smart_data: Option<AtaSmartData>,
+    pub ata_smart_attributes: Option<AtaAttributes>,
+    pub ata_smart_self_test_log: Option<AtaSelfTestLog>,
+    pub nvme_smart_health_information_log: Option<NvmeHealthLog>,
+    pub nvme_self_test_log: Option<NvmeSelfTestLog>,
+    /// The JSON this was parsed from, kept for the session record.
+    #[serde(skip)]
+    pub raw: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SmartctlInfo {
+    pub exit_status: i32,
+    pub messages: Vec<SmartctlMessage>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SmartctlMessage {
+    pub string: String,
+    pub severity: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeviceInfo {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub device_type: String,
+    pub protocol: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Capacity {
+    pub blocks: u64,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SmartStatus {
+    pub passed: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Temperature {
+    pub current: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PowerOnTime {
+    pub hours: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AtaSmartData {
+    pub self_test: AtaSelfTestState,
+}
+
+/// The self-test currently running (or last run) and the drive's recommended durations.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AtaSelfTestState {
+    pub status: SelfTestStatus,
+    pub polling_minutes: PollingMinutes,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PollingMinutes {
+    pub short: Option<u64>,
+    pub extended: Option<u64>,
+    pub conveyance: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AtaAttributes {
+    pub table: Vec<AtaAttribute>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AtaAttribute {
+    pub id: u8,
+    pub name: String,
+    pub value: u16,
+    pub worst: u16,
+    pub thresh: u16,
+    /// Empty, `"past"` or `"now"`.
+    pub when_failed: String,
+    pub flags: AttributeFlags,
+    pub raw: RawValue,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AttributeFlags {
+    pub prefailure: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RawValue {
+    pub value: u64,
+    pub string: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AtaSelfTestLog {
+    pub standard: Option<AtaSelfTestTable>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AtaSelfTestTable {
+    pub table: Vec<AtaSelfTestEntry>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AtaSelfTestEntry {
+    #[serde(rename = "type")]
+    pub test_type: Described,
+    pub status: SelfTestStatus,
+    pub lifetime_hours: u64,
+    pub lba: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Described {
+    pub value: u64,
+    pub string: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SelfTestStatus {
+    /// High nibble 0xF means a test is in progress.
+    pub value: u64,
+    pub string: String,
+    pub remaining_percent: Option<u8>,
+    pub passed: Option<bool>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NvmeHealthLog {
+    pub critical_warning: u64,
+    pub temperature: i64,
+    pub available_spare: u8,
+    pub available_spare_threshold: u8,
+    pub percentage_used: u8,
+    pub data_units_read: u64,
+    pub data_units_written: u64,
+    pub power_cycles: u64,
+    pub power_on_hours: u64,
+    pub unsafe_shutdowns: u64,
+    pub media_errors: u64,
+    pub num_err_log_entries: u64,
+    /// Minutes spent above the warning and critical composite temperatures.
+    pub warning_temp_time: u64,
+    pub critical_comp_time: u64,
+    pub temperature_sensors: Vec<i64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NvmeSelfTestLog {
+    /// Value 0 when no self-test is running.
+    pub current_self_test_operation: Described,
+    pub current_self_test_completion_percent: Option<u8>,
+    pub table: Vec<NvmeSelfTestEntry>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NvmeSelfTestEntry {
+    pub self_test_code: Described,
+    pub self_test_result: Described,
+    pub power_on_hours: u64,
+    pub lba: Option<u64>,
+}
+
+/// One self-test log row, the same for ATA and NVMe.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelfTestEntry {
+    pub test: String,
+    pub status: String,
+    /// `None` when the test did not complete, e.g. aborted by the host.
+    pub passed: Option<bool>,
+    pub hours: u64,
+    pub lba: Option<u64>,
+}
+
+impl SmartData {
+    pub fn parse(json: &str) -> Result<Self, String> {
+        let mut data: SmartData =
+            serde_json::from_str(json).map_err(|e| format!("Invalid smartctl JSON: {}", e))?;
+        data.raw = json.to_string();
+        Ok(data)
+    }
+
+    pub fn is_nvme(&self) -> bool {
+        self.device.protocol == "NVMe" || self.nvme_smart_health_information_log.is_some()
+    }
+
+    pub fn model(&self) -> Option<&str> {
+        self.model_name.as_deref().or(self.scsi_model_name.as_deref())
+    }
+
+    pub fn capacity_bytes(&self) -> Option<u64> {
+        self.user_capacity
+            .as_ref()
+            .map(|c| c.bytes)
+            .or(self.nvme_total_capacity)
+    }
+
+    pub fn temperature_c(&self) -> Option<i64> {
+        self.temperature
+            .as_ref()
+            .map(|t| t.current)
+            .or(self.nvme_smart_health_information_log.as_ref().map(|log| log.temperature))
+    }
+
+    pub fn power_on_hours(&self) -> Option<u64> {
+        self.power_on_time
+            .as_ref()
+            .map(|t| t.hours)
+            .or(self.nvme_smart_health_information_log.as_ref().map(|log| log.power_on_hours))
+    }
+
+    pub fn attributes(&self) -> &[AtaAttribute] {
+        self.ata_smart_attributes
+            .as_ref()
+            .map(|a| a.table.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Newest first, as smartctl lists them.
+    pub fn self_tests(&self) -> Vec<SelfTestEntry> {
+        if let Some(log) = &self.nvme_self_test_log {
+            return log
+                .table
+                .iter()
+                .map(|e| SelfTestEntry {
+                    test: e.self_test_code.string.clone(),
+                    status: e.self_test_result.string.clone(),
+                    // Result codes 5-7 are failures; the rest besides 0 are aborts.
+                    passed: match e.self_test_result.value {
+                        0 => Some(true),
+                        5..=7 => Some(false),
+                        _ => None,
+                    },
+                    hours: e.power_on_hours,
+                    lba: e.lba,
+                })
+                .collect();
+        }
+        self.ata_smart_self_test_log
+            .as_ref()
+            .and_then(|log| log.standard.as_ref())
+            .map(|standard| {
+                standard
+                    .table
+                    .iter()
+                    .map(|e| SelfTestEntry {
+                        test: e.test_type.string.clone(),
+                        status: e.status.string.clone(),
+                        passed: e.status.passed,
+                        hours: e.lifetime_hours,
+                        lba: e.lba,
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    /// smartctl's own error messages, e.g. when the device cannot be opened.
+    pub fn errors(&self) -> Vec<&str> {
+        self.smartctl
+            .messages
+            .iter()
+            .filter(|m| m.severity == "error")
+            .map(|m| m.string.as_str())
+            .collect()
+    }
+}
@@ -0,0 +1,113 @@
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_json::Value;
+
+use super::usage::Usage;
+
+/// Columns asked of `lsblk`; every one of them maps onto a `Drive` field.
+pub const LSBLK_COLUMNS: &str = "PATH,NAME,SIZE,MODEL,SERIAL,TRAN,ROTA,RM,VENDOR,WWN,TYPE";
+
+/// One whole disk as `lsblk --json -b -d` reports it.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Drive {
+    pub path: String,
+    pub name: String,
+    #[serde(deserialize_with = "number")]
+    pub size: u64,
+    #[serde(deserialize_with = "text")]
+    pub model: Option<String>,
+    #[serde(deserialize_with = "text")]
+    pub serial: Option<String>,
+    /// sata, nvme, usb, ...
+    #[serde(rename = "tran", deserialize_with = "text")]
+    pub transport: Option<String>,
+    #[serde(rename = "rota", deserialize_with = "flag")]
+    pub rotational: bool,
+    #[serde(rename = "rm", deserialize_with = "flag")]
+    pub removable: bool,
+    #[serde(deserialize_with = "text")]
+    pub vendor: Option<String>,
+    #[serde(deserialize_with = "text")]
+    pub wwn: Option<String>,
+    /// disk, loop, rom, ...
+    #[serde(rename = "type")]
+    pub kind: String,
+    /// Mounts and swap on this disk, filled in from /proc after listing.
+    #[serde(skip)]
+    pub system_use: Vec<Usage>,
+}
+
+impl Drive {
+    /// Loop and zram devices are RAM or files, not drives on the bench.
+    pub fn is_virtual(&self) -> bool {
+        self.kind == "loop" || self.name.starts_with("zram")
+    }
+
+    /// "HDD" or "SSD", from the kernel's rotational flag.
+    pub fn media(&self) -> &'static str {
+        if self.rotational {
+            "HDD"
+        } else {
+            "SSD"
+        }
+    }
+
+    /// Something on this disk is mounted or swapped on; it must not be written to.
+    pub fn in_use(&self) -> bool {
+        !self.system_use.is_empty()
+    }
+
+    /// The disk this workstation is running from.
+    pub fn is_boot_disk(&self) -> bool {
+        self.system_use.iter().any(Usage::is_root)
+    }
+
+    pub fn model_text(&self) -> &str {
+        self.model.as_deref().unwrap_or("Unknown")
+    }
+}
+
+#[derive(Deserialize)]
+struct LsblkOutput {
+    blockdevices: Vec<Drive>,
+}
+
+/// Parses `lsblk --json -b -d -o LSBLK_COLUMNS`. Older util-linux prints
+/// numbers and flags as strings, so both forms are accepted. Devices without
+/// a PATH column get one from their name.
+pub fn parse_lsblk_json(json: &str) -> Result<Vec<Drive>, String> {
+    let output: LsblkOutput = serde_json::from_str(json).map_err(|e| format!("Invalid lsblk JSON: {}", e))?;
+    Ok(output
+        .blockdevices
+        .into_iter()
+        .map(|mut drive| {
+            if drive.path.is_empty() {
+                drive.path = format!("/dev/{}", drive.name);
+            }
+            drive
+        })
+        .collect())
+}
+
+/// Trimmed text; empty or null becomes `None`. lsblk pads vendor names.
+fn text<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<String>, D::Error> {
+    let value = Option::<String>::deserialize(deserializer)?;
+    Ok(value.map(|s| s.trim().to_string()).filter(|s| !s.is_empty()))
+}
+
+fn number<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+    Ok(match Value::deserialize(deserializer)? {
+        Value::Number(n) => n.as_u64().unwrap_or(0),
+        Value::String(s) => s.trim().parse().unwrap_or(0),
+        _ => 0,
+    })
+}
+
+fn flag<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+    Ok(match Value::deserialize(deserializer)? {
+        Value::Bool(b) => b,
+        Value::Number(n) => n.as_u64() == Some(1),
+        Value::String(s) => s.trim() == "1",
+        _ => false,
+    })
+}
@@ -0,0 +1,138 @@
+use ratatui::style::Color;
+use serde::Serialize;
+
+use super::data::SmartData;
+use super::nvme;
+use crate::config::{GradingConfig, Thresholds};
+use crate::session::TestStatus;
+
+/// The grade a drive is sold with, best first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+pub enum Grade {
+    Great,
+    Good,
+    Fair,
+    /// smartctl could not tell us the drive's health.
+    Unknown,
+    Bad,
+}
+
+impl Grade {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Grade::Great => "Great",
+            Grade::Good => "Good",
+            Grade::Fair => "Fair",
+            Grade::Unknown => "Unknown",
+            Grade::Bad => "Bad",
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            Grade::Great => Color::Green,
+            Grade::Good => Color::LightGreen,
+            Grade::Fair => Color::Yellow,
+            Grade::Unknown => Color::Gray,
+            Grade::Bad => Color::Red,
+        }
+    }
+
+    pub fn status(&self) -> TestStatus {
+        match self {
+            Grade::Great | Grade::Good => TestStatus::Pass,
+            Grade::Fair | Grade::Unknown => TestStatus::Warn,
+            Grade::Bad => TestStatus::Fail,
+        }
+    }
+}
+
+/// The worst grade `value` reaches under `thresholds`.
+pub fn threshold_grade(thresholds: &Thresholds, value: u64) -> Grade {
+    let reached = |limit: Option<u64>| limit.is_some_and(|limit| value >= limit);
+    if reached(thresholds.bad) {
+        Grade::Bad
+    } else if reached(thresholds.fair) {
+        Grade::Fair
+    } else if reached(thresholds.good) {
+        Grade::Good
+    } else {
+        Grade::Great
+    }
+}
+
+/// A grade plus every reason it is below Great.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct Grading {
+    pub grade: Grade,
+    pub reasons: Vec<String>,
+}
+
+impl Grading {
+    pub fn new() -> Self {
+        Self {
+            grade: Grade::Great,
+            reasons: Vec::new(),
+        }
+    }
+
+    pub fn lower(&mut self, to: Grade, reason: impl Into<String>) {
+        if to > Grade::Great {
+            self.grade = self.grade.max(to);
+            self.reasons.push(reason.into());
+        }
+    }
+
+    /// Applies one threshold rule; `describe` words the reason for `value`.
+    pub fn rule(&mut self, thresholds: &Thresholds, value: u64, describe: impl FnOnce(u64) -> String) {
+        self.lower(threshold_grade(thresholds, value), describe(value));
+    }
+}
+
+/// ATA attribute IDs the grading rules read.
+pub const REALLOCATED_SECTORS: u8 = 5;
+pub const PENDING_SECTORS: u8 = 197;
+pub const OFFLINE_UNCORRECTABLE: u8 = 198;
+pub const UDMA_CRC_ERRORS: u8 = 199;
+
+pub fn grade_drive(data: &SmartData, rules: &GradingConfig) -> Grading {
+    let mut grading = Grading::new();
+
+    match &data.smart_status {
+        Some(status) if !status.passed => grading.lower(Grade::Bad, "SMART overall health check failed"),
+        Some(_) => {}
+        None => grading.lower(Grade::Unknown, "smartctl reported no SMART health status"),
+    }
+
+    let raw = |id: u8| data.attributes().iter().find(|a| a.id == id).map(|a| a.raw.value);
+    if let Some(count) = raw(REALLOCATED_SECTORS) {
+        grading.rule(&rules.reallocated_sectors, count, |n| format!("{} reallocated sectors", n));
+    }
+    if let Some(count) = raw(PENDING_SECTORS) {
+        grading.rule(&rules.pending_sectors, count, |n| format!("{} sectors pending reallocation", n));
+    }
+    if let Some(count) = raw(OFFLINE_UNCORRECTABLE) {
+        grading.rule(&rules.offline_uncorrectable, count, |n| format!("{} offline uncorrectable sectors", n));
+    }
+    if let Some(count) = raw(UDMA_CRC_ERRORS) {
+        grading.rule(&rules.crc_errors, count, |n| format!("{} UDMA CRC errors (check the cable)", n));
+    }
+    for attr in data.attributes().iter().filter(|a| a.when_failed == "now") {
+        grading.lower(Grade::Bad, format!("{} is below its failure threshold", attr.name));
+    }
+
+    if let Some(hours) = data.power_on_hours() {
+        grading.rule(&rules.power_on_hours, hours, |n| format!("{} power-on hours", n));
+    }
+
+    if let Some(log) = &data.nvme_smart_health_information_log {
+        let wear = nvme::wear_grade(log, rules);
+        grading.grade = grading.grade.max(wear.grade);
+        grading.reasons.extend(wear.reasons);
+    }
+
+    let failed_tests = data.self_tests().iter().filter(|t| t.passed == Some(false)).count() as u64;
+    grading.rule(&rules.self_test_failures, failed_tests, |n| format!("{} failed self-tests in the log", n));
+
+    grading
+}
@@ -0,0 +1,91 @@
+use once_cell::sync::Lazy;
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+pub const SYS_BLOCK: &str = "/sys/block";
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Block device names seen at the last poll, and when that was. `names` is
+/// `None` until `/sys/block` has been read once.
+pub struct Snapshot {
+    names: Option<BTreeSet<String>>,
+    taken: Instant,
+}
+
+static KNOWN: Lazy<Mutex<Option<Snapshot>>> = Lazy::new(|| Mutex::new(None));
+
+/// Devices that appeared or went away between two polls.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Change {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl Change {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Names of the entries in a `/sys/block`-style directory.
+pub fn block_devices(dir: &Path) -> io::Result<BTreeSet<String>> {
+    let mut names = BTreeSet::new();
+    for entry in fs::read_dir(dir)? {
+        names.insert(entry?.file_name().to_string_lossy().into_owned());
+    }
+    Ok(names)
+}
+
+pub fn diff(before: &BTreeSet<String>, after: &BTreeSet<String>) -> Change {
+    Change {
+        added: after.difference(before).cloned().collect(),
+        removed: before.difference(after).cloned().collect(),
+    }
+}
+
+/// Takes a fresh snapshot so later polls only report changes from now on.
+pub fn reset() {
+    *KNOWN.lock().unwrap() = Some(Snapshot {
+        names: block_devices(Path::new(SYS_BLOCK)).ok(),
+        taken: Instant::now(),
+    });
+}
+
+/// Reads `/sys/block` at most once per second and reports what changed since
+/// the previous read. `None` when nothing did or it is not time yet.
+pub fn poll() -> Option<Change> {
+    let mut known = KNOWN.lock().unwrap();
+    if known
+        .as_ref()
+        .is_some_and(|snapshot| snapshot.taken.elapsed() < POLL_INTERVAL)
+    {
+        return None;
+    }
+    update(&mut known, block_devices(Path::new(SYS_BLOCK)))
+}
+
+/// Moves `known` on to a fresh read of the block devices. A failed read
+/// keeps the last good list, so the next one does not report every device
+/// as added.
+pub fn update(known: &mut Option<Snapshot>, read: io::Result<BTreeSet<String>>) -> Option<Change> {
+    let previous = known.take().and_then(|snapshot| snapshot.names);
+    let (names, change) = match read {
+        Ok(names) => {
+            let change = previous.as_ref().map(|before| diff(before, &names));
+            (Some(names), change)
+        }
+        Err(e) => {
+            log::warn!("Cannot read {}: {}", SYS_BLOCK, e);
+            (previous, None)
+        }
+    };
+    *known = Some(Snapshot {
+        names,
+        taken: Instant::now(),
+    });
+    change.filter(|c| !c.is_empty())
+}
@@ -2,148 +2,371 @@ use ratatui::{
     layout::{Constraint, Direction, Layout},
     style::{Color, Style, Modifier, Stylize},
     text::{Line, Span, Text},
-    widgets::{Block, Borders, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState},
+    widgets::{Block, Borders, Gauge, Paragraph, Scrollbar, ScrollbarOrientation, ScrollbarState},
     Frame,
 };
 use once_cell::sync::Lazy;
-use std::{process::Command, sync::Mutex};
+use serde::Serialize;
+use std::{collections::BTreeSet, io, path::Path, sync::Mutex};
+
+use crate::command::{CommandRunner, SystemRunner};
+use crate::config;
+use crate::history::{self, SmartDelta};
+use crate::session::{self, TestResult};
+
+pub mod batch;
+pub mod data;
+pub mod drive;
+pub mod grade;
+pub mod hotplug;
+pub mod nvme;
+pub mod self_test;
+pub mod usage;
+
+pub use data::SmartData;
+pub use drive::Drive;
+pub use grade::{Grade, Grading};
 
 pub static SMART_OUTPUT: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new(String::new()));
-pub static SMART_ACTIVE: Lazy<Mutex<bool>> = Lazy::new(|| Mutex::new(false));
-pub static DISK_SELECTION_ACTIVE: Lazy<Mutex<bool>> = Lazy::new(|| Mutex::new(false));
-pub static DISK_LIST: Lazy<Mutex<Vec<String>>> = Lazy::new(|| Mutex::new(vec![]));
+/// Parsed once per run; the SMART screen renders from this.
+pub static SMART_DATA: Lazy<Mutex<Option<SmartData>>> = Lazy::new(|| Mutex::new(None));
+/// Change since the shown drive's previous visit, from the history database.
+pub static SMART_DELTA: Lazy<Mutex<Option<SmartDelta>>> = Lazy::new(|| Mutex::new(None));
+/// Every block device lsblk reported, virtual ones included.
+pub static DISK_LIST: Lazy<Mutex<Vec<Drive>>> = Lazy::new(|| Mutex::new(vec![]));
+static SHOW_VIRTUAL: Lazy<Mutex<bool>> = Lazy::new(|| Mutex::new(false));
+/// Paths of drives plugged in while the selection screen was open.
+static NEW_DRIVES: Lazy<Mutex<BTreeSet<String>>> = Lazy::new(|| Mutex::new(BTreeSet::new()));
 pub static SELECTED_DISK_INDEX: Lazy<Mutex<usize>> = Lazy::new(|| Mutex::new(0));
 pub static SMART_SCROLL: Lazy<Mutex<u16>> = Lazy::new(|| Mutex::new(0));
 
-pub fn check_disk_selection_active() -> bool {
-    *DISK_SELECTION_ACTIVE.lock().unwrap()
+pub fn list_drives(runner: &dyn CommandRunner) -> io::Result<Vec<Drive>> {
+    let output = runner.run("lsblk", &["--json", "-b", "-d", "-o", drive::LSBLK_COLUMNS])?;
+    if !output.success() {
+        return Err(io::Error::other(format!("lsblk exited with {:?}: {}", output.status, output.stderr.trim())));
+    }
+    let mut drives =
+        drive::parse_lsblk_json(&output.stdout).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+    match usage::read_in_use(Path::new(usage::PROC)) {
+        Ok(in_use) => usage::annotate(&mut drives, &in_use, Path::new(usage::SYS)),
+        Err(e) => log::warn!("Cannot read mounts and swaps: {}", e),
+    }
+    Ok(drives)
 }
 
-pub fn enter_disk_selection() {
-    let output = Command::new("lsblk")
-        .arg("-d")
-        .arg("-o")
-        .arg("NAME,SIZE,MODEL")
-        .output()
-        .expect("Failed to run lsblk");
-
-    let list = String::from_utf8_lossy(&output.stdout)
-        .lines()
-        .skip(1)
-        .filter_map(|line| {
-            let parts: Vec<_> = line.split_whitespace().collect();
-            if parts.len() >= 3 {
-                Some(format!("/dev/{} - {} - {}", parts[0], parts[1], parts[2]))
-            } else {
-                None
-            }
-        })
-        .collect();
+/// The drives on the bench, without loop and zram devices.
+pub fn physical_drives(runner: &dyn CommandRunner) -> io::Result<Vec<Drive>> {
+    Ok(list_drives(runner)?.into_iter().filter(|d| !d.is_virtual()).collect())
+}
 
-    *DISK_LIST.lock().unwrap() = list;
+pub fn enter_disk_selection() {
+    *DISK_LIST.lock().unwrap() = list_drives(&SystemRunner).unwrap_or_else(|e| {
+        log::error!("Failed to list drives: {}", e);
+        Vec::new()
+    });
     *SELECTED_DISK_INDEX.lock().unwrap() = 0;
-    *DISK_SELECTION_ACTIVE.lock().unwrap() = true;
+    NEW_DRIVES.lock().unwrap().clear();
+    hotplug::reset();
+    match boot_disk() {
+        Some(disk) => log::info!("Workstation boot disk is {}", disk),
+        None => log::warn!("Could not tell which disk this workstation booted from"),
+    }
 }
 
-pub fn get_drive_list() -> Vec<String> {
-    DISK_LIST.lock().unwrap().clone()
+/// Called every UI tick while the selection screen is shown; re-reads the
+/// drive list when a block device comes or goes.
+pub fn watch_drives() {
+    let Some(change) = hotplug::poll() else {
+        return;
+    };
+    for name in &change.added {
+        log::info!("Block device added: /dev/{}", name);
+    }
+    for name in &change.removed {
+        log::info!("Block device removed: /dev/{}", name);
+    }
+    match list_drives(&SystemRunner) {
+        Ok(drives) => {
+            let added: Vec<String> = change.added.iter().map(|name| format!("/dev/{}", name)).collect();
+            refresh_drives(drives, &added);
+        }
+        Err(e) => log::error!("Failed to list drives: {}", e),
+    }
+}
+
+/// Replaces the drive list, keeping the highlighted drive selected when it is
+/// still there and flagging `added` paths as newly inserted.
+pub fn refresh_drives(drives: Vec<Drive>, added: &[String]) {
+    let selected = selected_drive().map(|d| d.path);
+    {
+        let mut new = NEW_DRIVES.lock().unwrap();
+        new.extend(added.iter().cloned());
+        new.retain(|path| drives.iter().any(|d| &d.path == path));
+    }
+    *DISK_LIST.lock().unwrap() = drives;
+
+    let list = get_drive_list();
+    let mut index = SELECTED_DISK_INDEX.lock().unwrap();
+    *index = selected
+        .and_then(|path| list.iter().position(|d| d.path == path))
+        .unwrap_or_else(|| (*index).min(list.len().saturating_sub(1)));
+}
+
+/// Plugged in while the selection screen was open.
+pub fn is_new_drive(path: &str) -> bool {
+    NEW_DRIVES.lock().unwrap().contains(path)
+}
+
+/// The listed drive holding the running root filesystem.
+pub fn boot_disk() -> Option<String> {
+    DISK_LIST.lock().unwrap().iter().find(|d| d.is_boot_disk()).map(|d| d.path.clone())
+}
+
+/// The drives shown in the list; loop and zram devices only when asked for.
+pub fn get_drive_list() -> Vec<Drive> {
+    let show_virtual = *SHOW_VIRTUAL.lock().unwrap();
+    DISK_LIST
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|d| show_virtual || !d.is_virtual())
+        .cloned()
+        .collect()
 }
 
 pub fn get_selected_drive_index() -> usize {
     *SELECTED_DISK_INDEX.lock().unwrap()
 }
 
-pub fn exit_disk_selection() {
-    *DISK_SELECTION_ACTIVE.lock().unwrap() = false;
+pub fn selected_drive() -> Option<Drive> {
+    get_drive_list().get(get_selected_drive_index()).cloned()
 }
 
-pub fn draw_smart_output(f: &mut Frame) {
-    let area = f.area();
-    let output = SMART_OUTPUT.lock().unwrap().clone();
-    let scroll = *SMART_SCROLL.lock().unwrap();
+pub fn toggle_virtual_drives() {
+    let mut show = SHOW_VIRTUAL.lock().unwrap();
+    *show = !*show;
+    *SELECTED_DISK_INDEX.lock().unwrap() = 0;
+}
 
-    let mut health = "Unknown";
-    let mut family = "Unknown";
-    let mut model = "Unknown";
-    let mut capacity = String::from("Unknown");
-    let mut temp = "N/A";
-    let mut hours = "Unknown";
+pub fn showing_virtual_drives() -> bool {
+    *SHOW_VIRTUAL.lock().unwrap()
+}
 
-    for line in output.lines() {
-        if line.contains("Device Model:") {
-            model = line.split(':').nth(1).unwrap_or("").trim();
-        }
-        if line.contains("Model Family:") {
-            family = line.split(':').nth(1).unwrap_or("").trim();
-        }
-        if line.contains("User Capacity:") {
-            let raw = line
-                .split(':')
-                .nth(1)
-                .unwrap_or("")
-                .replace(",", "")
-                .replace("bytes", "")
-                .trim()
-                .to_string();
-                
-            if let Ok(bytes) = raw.parse::<u64>() {
-                capacity = format_capacity(bytes);
-            }
-        }
-        if line.contains("Temperature_Celsius") {
-            temp = line.split_whitespace().last().unwrap_or("N/A");
+/// Key facts shown on the SMART screen and stored with the session result.
+#[derive(Debug, Clone, Serialize)]
+pub struct SmartSummary {
+    pub grade: Grade,
+    /// Why the grade is below Great.
+    pub reasons: Vec<String>,
+    pub health: String,
+    pub family: String,
+    pub model: String,
+    pub serial: String,
+    pub capacity: String,
+    pub temperature: String,
+    pub power_on_hours: String,
+}
+
+impl SmartSummary {
+    pub fn from_data(data: &SmartData) -> Self {
+        let Grading { grade, reasons } = grade::grade_drive(data, &config::get().grading);
+
+        SmartSummary {
+            grade,
+            reasons,
+            health: grade.label().to_string(),
+            family: data.model_family.clone().unwrap_or_else(|| "Unknown".to_string()),
+            model: data.model().unwrap_or("Unknown").to_string(),
+            serial: data.serial_number.clone().unwrap_or_else(|| "Unknown".to_string()),
+            capacity: data
+                .capacity_bytes()
+                .map(format_capacity)
+                .unwrap_or_else(|| "Unknown".to_string()),
+            temperature: data
+                .temperature_c()
+                .map(|t| t.to_string())
+                .unwrap_or_else(|| "N/A".to_string()),
+            power_on_hours: data
+                .power_on_hours()
+                .map(|h| h.to_string())
+                .unwrap_or_else(|| "Unknown".to_string()),
         }
-        if line.contains("Power_On_Hours") {
-            hours = line.split_whitespace().last().unwrap_or("Unknown");
+    }
+}
+
+/// The scrollable detail list: smartctl errors, then the ATA attribute table
+/// or the NVMe health log, then the self-test log.
+fn detail_lines(data: &SmartData, delta: Option<&SmartDelta>) -> Vec<Line<'static>> {
+    let heading = |text: &str| {
+        Line::from(Span::styled(text.to_string(), Style::default().fg(Color::Cyan).bold()))
+    };
+    let field = |key: &str, value: String| {
+        Line::from(vec![
+            Span::styled(format!("{}: ", key), Style::default().fg(Color::Cyan).bold()),
+            Span::styled(value, Style::default().fg(Color::White)),
+        ])
+    };
+
+    let mut lines = Vec::new();
+    let grading = grade::grade_drive(data, &config::get().grading);
+    for reason in &grading.reasons {
+        lines.push(Line::from(Span::styled(format!("• {}", reason), Style::default().fg(grading.grade.color()))));
+    }
+    for error in data.errors() {
+        lines.push(Line::from(Span::styled(error.to_string(), Style::default().fg(Color::Red))));
+    }
+    if let Some(delta) = delta {
+        let color = if delta.degrading() { Color::Red } else { Color::White };
+        lines.push(heading(&format!("Since last test ({})", delta.since.format("%Y-%m-%d"))));
+        for line in delta.lines() {
+            lines.push(Line::from(Span::styled(format!(" {}", line), Style::default().fg(color))));
         }
-        if line.contains("SMART overall-health self-assessment test result:") {
-            let result = line.split(':').nth(1).unwrap_or("").trim();
-            health = match result {
-                "PASSED" => "Great",
-                "OK" => "Good",
-                _ => "Bad",
+        lines.push(Line::raw(""));
+    }
+
+    lines.push(field("Device", format!("{} ({})", data.device.name, data.device.protocol)));
+    if let Some(firmware) = &data.firmware_version {
+        lines.push(field("Firmware", firmware.clone()));
+    }
+    if let Some(rpm) = data.rotation_rate.filter(|_| !data.is_nvme()) {
+        lines.push(field("Rotation", if rpm == 0 { "Solid state".to_string() } else { format!("{} rpm", rpm) }));
+    }
+    if let Some(cycles) = data.power_cycle_count {
+        lines.push(field("Power cycles", cycles.to_string()));
+    }
+
+    if !data.attributes().is_empty() {
+        lines.push(Line::raw(""));
+        lines.push(heading(" ID Attribute                 Value Worst Thresh Raw"));
+        for attr in data.attributes() {
+            let style = if attr.when_failed.is_empty() {
+                Style::default().fg(Color::White)
+            } else {
+                Style::default().fg(Color::Red)
             };
+            lines.push(Line::from(Span::styled(
+                format!(
+                    "{:>3} {:<24} {:>5} {:>5} {:>6} {}",
+                    attr.id, attr.name, attr.value, attr.worst, attr.thresh, attr.raw.string
+                ),
+                style,
+            )));
         }
     }
 
-    let health_color = match health {
-        "Great" => Color::Green,
-        "Good" => Color::Yellow,
-        "Bad" => Color::Red,
-        _ => Color::Gray,
-    };
+    if let Some(log) = &data.nvme_smart_health_information_log {
+        lines.push(Line::raw(""));
+        lines.extend(nvme::nvme_lines(log));
+    }
+
+    let self_tests = data.self_tests();
+    lines.push(Line::raw(""));
+    lines.push(heading("Self-test Log"));
+    if self_tests.is_empty() {
+        lines.push(Line::raw(" No self-tests logged"));
+    }
+    for test in self_tests {
+        let color = match test.passed {
+            Some(true) => Color::White,
+            Some(false) => Color::Red,
+            None => Color::Yellow,
+        };
+        let lba = test.lba.map(|lba| format!(", first error at LBA {}", lba)).unwrap_or_default();
+        lines.push(Line::from(Span::styled(
+            format!(" {} at {}h: {}{}", test.test, test.hours, test.status, lba),
+            Style::default().fg(color),
+        )));
+    }
+
+    lines
+}
+
+pub fn draw_smart_output(f: &mut Frame) {
+    let area = f.area();
+    let data = SMART_DATA.lock().unwrap();
+    let delta = SMART_DELTA.lock().unwrap().clone();
+    let message = SMART_OUTPUT.lock().unwrap().clone();
+    let scroll = *SMART_SCROLL.lock().unwrap();
+
+    let SmartSummary {
+        grade,
+        reasons,
+        health,
+        family,
+        model,
+        serial,
+        capacity,
+        temperature: temp,
+        power_on_hours: hours,
+    } = data
+        .as_ref()
+        .map(SmartSummary::from_data)
+        .unwrap_or_else(|| SmartSummary::from_data(&SmartData::default()));
 
     let main_chunks = Layout::default()
         .direction(Direction::Vertical)
         .margin(1)
-        .constraints([Constraint::Length(5), Constraint::Length(7), Constraint::Min(5)])
+        .constraints([
+            Constraint::Length(5),
+            Constraint::Length(8),
+            Constraint::Min(5),
+            Constraint::Length(3),
+        ])
         .split(area);
 
     // HEALTH INDICATOR BLOCK (top)
-    let health_block = Paragraph::new(Text::from(Line::from(Span::styled(
-        format!("🩺 {health}"),
+    let degrading = if delta.as_ref().is_some_and(SmartDelta::degrading) {
+        " (degrading since last test)"
+    } else {
+        ""
+    };
+    let mut health_lines = vec![Line::from(Span::styled(
+        format!("🩺 {health}{degrading}"),
         Style::default()
             .fg(Color::White)
-            .bg(health_color)
+            .bg(grade.color())
             .add_modifier(Modifier::BOLD),
-    ))))
-    .block(Block::default().borders(Borders::ALL).title("Health Status"))
-    .alignment(ratatui::layout::Alignment::Center);
+    ))];
+    // Room for two reasons; all of them are listed at the top of the details.
+    for (i, reason) in reasons.iter().take(2).enumerate() {
+        let text = match reasons.len() {
+            n if i == 1 && n > 2 => format!("{} (+{} more below)", reason, n - 2),
+            _ => reason.clone(),
+        };
+        health_lines.push(Line::from(Span::styled(text, Style::default().fg(grade.color()))));
+    }
+    let health_block = Paragraph::new(Text::from(health_lines))
+        .block(Block::default().borders(Borders::ALL).title("Drive Grade"))
+        .alignment(ratatui::layout::Alignment::Center);
 
     f.render_widget(health_block, main_chunks[0]);
 
     // GRID OF KEY ATTRIBUTES
-    let attribute_chunks = Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(33); 3])
+    let rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(4); 2])
         .split(main_chunks[1]);
 
-    let info_lines = vec![
-        ("Model Family", family, "🏠"),
-        ("Device Model", model, "💾"),
+    // NVMe drives have no model family; their wear matters more.
+    let (first_label, first_value) = match data.as_ref().and_then(|d| d.nvme_smart_health_information_log.as_ref()) {
+        Some(log) => (
+            "NVMe Wear",
+            format!(
+                "{} ({}% used)",
+                nvme::wear_grade(log, &config::get().grading).grade.label(),
+                log.percentage_used
+            ),
+        ),
+        None => ("Model Family", family),
+    };
+
+    let info_lines = [
+        (first_label, &first_value, "🏠"),
+        ("Device Model", &model, "💾"),
+        ("Serial Number", &serial, "🔖"),
         ("Capacity", &capacity, "💽"),
-        ("Temperature (°C)", temp, "🌡"),
-        ("Runtime Hours", hours, "⏱"),
+        ("Temperature (°C)", &temp, "🌡"),
+        ("Runtime Hours", &hours, "⏱"),
     ];
 
     for (i, (label, value, icon)) in info_lines.iter().enumerate() {
@@ -153,33 +376,25 @@ pub fn draw_smart_output(f: &mut Frame) {
                 Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD),
             )),
             Line::from(Span::styled(
-                format!("{label}: {value}"),
+                value.to_string(),
                 Style::default().fg(Color::White),
             )),
         ]))
         .block(Block::default().borders(Borders::ALL).title(*label))
         .alignment(ratatui::layout::Alignment::Center);
 
-        let col = i % 3;
-        let row = i / 3;
-
-        if row == 0 {
-            f.render_widget(paragraph, attribute_chunks[col]);
-        }
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(33); 3])
+            .split(rows[i / 3]);
+        f.render_widget(paragraph, columns[i % 3]);
     }
 
     // SCROLLABLE SMART ATTRIBUTE LIST (bottom)
-    let mut lines = Vec::new();
-    for line in output.lines() {
-        if let Some((key, value)) = line.split_once(':') {
-            lines.push(Line::from(vec![
-                Span::styled(format!("{}: ", key.trim()), Style::default().fg(Color::Cyan).bold()),
-                Span::styled(value.trim(), Style::default().fg(Color::White)),
-            ]));
-        } else {
-            lines.push(Line::from(Span::raw(format!(" {}", line))));
-        }
-    }
+    let lines = match data.as_ref() {
+        Some(data) => detail_lines(data, delta.as_ref()),
+        None => vec![Line::raw(message)],
+    };
 
     let content_height = lines.len();
     let visible_height = main_chunks[2].height.saturating_sub(2);
@@ -196,8 +411,7 @@ pub fn draw_smart_output(f: &mut Frame) {
     let mut scroll_state = ScrollbarState::new(content_height).position(scroll_pos);
 
     let smart_paragraph = Paragraph::new(Text::from(visible_lines))
-        .block(Block::default().borders(Borders::ALL).title("SMART Attributes"))
-        .wrap(ratatui::widgets::Wrap { trim: true });
+        .block(Block::default().borders(Borders::ALL).title("SMART Attributes"));
 
     let scrollbar = Scrollbar::default()
         .orientation(ScrollbarOrientation::VerticalRight)
@@ -206,6 +420,24 @@ pub fn draw_smart_output(f: &mut Frame) {
 
     f.render_widget(smart_paragraph, main_chunks[2]);
     f.render_stateful_widget(scrollbar, main_chunks[2], &mut scroll_state);
+
+    // SELF-TEST PROGRESS
+    let device = data.as_ref().map(|d| d.device.name.clone()).unwrap_or_default();
+    let help = "s: short self-test  e: extended  v: conveyance  c: abort  ↑/↓: scroll";
+    match self_test::self_test_progress(&device) {
+        Some((percent, status)) => {
+            let gauge = Gauge::default()
+                .block(Block::default().borders(Borders::ALL).title(format!("Self-test ({})", help)))
+                .gauge_style(Style::default().fg(Color::Green).bg(Color::Black))
+                .percent(percent)
+                .label(status);
+            f.render_widget(gauge, main_chunks[3]);
+        }
+        None => {
+            let info = Paragraph::new(help).block(Block::default().borders(Borders::ALL).title("Self-test"));
+            f.render_widget(info, main_chunks[3]);
+        }
+    }
 }
 
 pub fn scroll_up() {
@@ -228,22 +460,16 @@ pub fn previous_drive() {
 }
 
 pub fn next_drive() {
+    let count = get_drive_list().len();
     let mut index = SELECTED_DISK_INDEX.lock().unwrap();
-    let disks = DISK_LIST.lock().unwrap();
-    if *index < disks.len().saturating_sub(1) {
+    if *index < count.saturating_sub(1) {
         *index += 1;
     }
 }
 
-fn detect_device_type(device: &str) -> String {
-    let output = Command::new("smartctl")
-        .arg("-i")
-        .arg("--json=c")
-        .arg(device)
-        .output();
-
-    if let Ok(out) = output {
-        if let Ok(json) = serde_json::from_slice::<serde_json::Value>(&out.stdout) {
+fn detect_device_type(runner: &dyn CommandRunner, device: &str) -> String {
+    if let Ok(out) = runner.run("smartctl", &["-i", "--json=c", device]) {
+        if let Ok(json) = serde_json::from_str::<serde_json::Value>(&out.stdout) {
             if let Some(device_type) = json.get("device").and_then(|d| d.get("type")).and_then(|t| t.as_str()) {
                 return device_type.to_string();
             }
@@ -254,46 +480,115 @@ fn detect_device_type(device: &str) -> String {
     "auto".to_string()
 }
 
+/// Runs `smartctl -a --json` against a device and parses the result.
+pub fn read_smart(runner: &dyn CommandRunner, device: &str) -> io::Result<SmartData> {
+    let dev_type = detect_device_type(runner, device);
+    log::info!("Reading SMART data from {} as {}", device, dev_type);
+    read_smart_as(runner, device, &dev_type)
+}
+
+/// `read_smart` for a device whose smartctl type is already known.
+pub fn read_smart_as(runner: &dyn CommandRunner, device: &str, dev_type: &str) -> io::Result<SmartData> {
+    let output = runner.run("smartctl", &["-a", "--json", "-d", dev_type, device])?;
+    if !output.success() {
+        // smartctl sets status bits for failing drives too, so this is not an error on its own.
+        log::warn!("smartctl {} exited with {:?}", device, output.status);
+    }
+    SmartData::parse(&output.stdout).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Turns parsed `smartctl` data into a session result graded by the configured rules.
+pub fn smart_result(device: &str, data: &SmartData) -> TestResult {
+    let summary = SmartSummary::from_data(data);
+
+    let mut result = TestResult::new("SMART", device);
+    if let Some(log) = &data.nvme_smart_health_information_log {
+        result = result
+            .metric("percentage_used", log.percentage_used)
+            .metric("available_spare", log.available_spare)
+            .metric("media_errors", log.media_errors)
+            .metric("unsafe_shutdowns", log.unsafe_shutdowns)
+            .metric("data_written_tb", format!("{:.2}", nvme::data_units_to_tb(log.data_units_written)));
+    }
+
+    result
+        .status(summary.grade.status())
+        .metric("health", &summary.health)
+        .metric("grade_reasons", summary.reasons.join("; "))
+        .metric("model", &summary.model)
+        .metric("family", &summary.family)
+        .metric("serial", &summary.serial)
+        .metric("capacity", &summary.capacity)
+        .metric("temperature_c", &summary.temperature)
+        .metric("power_on_hours", &summary.power_on_hours)
+        .raw_output(data.raw.clone())
+        .finish()
+}
+
+/// Stores a reading in the drive history and the session, returning the
+/// change since the drive was last tested.
+pub fn record_reading(device: &str, data: &SmartData) -> Option<SmartDelta> {
+    let delta = history::record_smart(device, data);
+    let mut result = smart_result(device, data);
+    if let Some(delta) = &delta {
+        result = result
+            .metric("degrading", delta.degrading())
+            .metric("since_last_test", delta.lines().join("; "));
+    }
+    session::record(result);
+    delta
+}
+
 pub fn run_smart_test_on_selected_drive() {
-    let disks = DISK_LIST.lock().unwrap();
-    let index = *SELECTED_DISK_INDEX.lock().unwrap();
-
-    let fallback = String::from("/dev/sda");
-    let disk_line = disks.get(index).unwrap_or(&fallback);
-    let device = disk_line.split(" - ").next().unwrap_or("/dev/sda");
-
-    let dev_type = detect_device_type(device);
-
-    let output = Command::new("smartctl")
-        .arg("-a")
-        .arg("-d")
-        .arg(&dev_type)
-        .arg(device)
-        .output();
-
-    match output {
-        Ok(out) => {
-            *SMART_OUTPUT.lock().unwrap() = String::from_utf8_lossy(&out.stdout).to_string();
-            *SMART_SCROLL.lock().unwrap() = 0;
+    let Some(drive) = selected_drive() else {
+        *SMART_OUTPUT.lock().unwrap() = "No drive selected.".to_string();
+        return;
+    };
+    let device = drive.path.as_str();
+
+    *SMART_SCROLL.lock().unwrap() = 0;
+    match read_smart(&SystemRunner, device) {
+        Ok(data) => {
+            *SMART_DELTA.lock().unwrap() = record_reading(device, &data);
+            *SMART_DATA.lock().unwrap() = Some(data);
         }
         Err(e) => {
-            *SMART_OUTPUT.lock().unwrap() = format!("Failed to run smartctl: {}", e);
+            let message = format!("Failed to run smartctl: {}", e);
+            log::error!("{} on {}", message, device);
+            session::record(TestResult::new("SMART", device).raw_output(message.clone()).finish());
+            *SMART_DATA.lock().unwrap() = None;
+            *SMART_DELTA.lock().unwrap() = None;
+            *SMART_OUTPUT.lock().unwrap() = message;
         }
     }
-
-    *SMART_ACTIVE.lock().unwrap() = true;
-    *DISK_SELECTION_ACTIVE.lock().unwrap() = false;
 }
 
-pub fn check_smart_active() -> bool {
-    *SMART_ACTIVE.lock().unwrap()
+/// Reads SMART from every physical drive `lsblk` lists as a batch, recording one
+/// result per drive. Returns how many drives will be read.
+pub fn start_smart_all_drives() -> Result<usize, String> {
+    let devices: Vec<String> = physical_drives(&SystemRunner)
+        .map_err(|e| format!("Failed to list drives: {}", e))?
+        .into_iter()
+        .map(|drive| drive.path)
+        .collect();
+    if devices.is_empty() {
+        return Err("No drives found".to_string());
+    }
+    let count = devices.len();
+    if !batch::start_batch(devices) {
+        return Err("A SMART batch is already running".to_string());
+    }
+    Ok(count)
 }
 
 pub fn exit_smart_output() {
-    *SMART_ACTIVE.lock().unwrap() = false;
+    SMART_OUTPUT.lock().unwrap().clear();
+    *SMART_DATA.lock().unwrap() = None;
+    *SMART_DELTA.lock().unwrap() = None;
+    *SMART_SCROLL.lock().unwrap() = 0;
 }
 
-fn format_capacity(bytes: u64) -> String {
+pub fn format_capacity(bytes: u64) -> String {
     const GB: u64 = 1 << 30;
     const TB: u64 = 1 << 40;
     const MB: u64 = 1 << 20;
@@ -306,4 +601,7 @@ fn format_capacity(bytes: u64) -> String {
     } else {
         format!("{} bytes", bytes)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,128 @@
+use ratatui::{
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+};
+
+use super::data::NvmeHealthLog;
+use super::grade::{Grade, Grading};
+use crate::config::{self, GradingConfig};
+
+/// Critical warning bits from the NVMe SMART / Health log (NVMe base spec, log page 02h).
+const CRITICAL_WARNINGS: [(u64, &str); 6] = [
+    (0x01, "available spare below threshold"),
+    (0x02, "temperature out of range"),
+    (0x04, "reliability degraded by media errors"),
+    (0x08, "media is read-only"),
+    (0x10, "volatile memory backup failed"),
+    (0x20, "persistent memory region read-only"),
+];
+
+/// Bits that mean the drive is failing rather than just running hot.
+const FAILING_WARNINGS: u64 = 0x01 | 0x04 | 0x08 | 0x10 | 0x20;
+
+pub fn critical_warning_flags(bits: u64) -> Vec<&'static str> {
+    CRITICAL_WARNINGS
+        .iter()
+        .filter(|(bit, _)| bits & bit != 0)
+        .map(|(_, name)| *name)
+        .collect()
+}
+
+/// NVMe data units are 1000 sectors of 512 bytes.
+pub fn data_units_to_tb(units: u64) -> f64 {
+    units as f64 * 512_000.0 / 1e12
+}
+
+/// Grades NVMe wear. These drives mostly pass the overall SMART check until
+/// they are completely worn out, so spare, media errors and endurance used
+/// matter more than the health flag.
+pub fn wear_grade(log: &NvmeHealthLog, rules: &GradingConfig) -> Grading {
+    let mut grading = Grading::new();
+
+    if log.critical_warning & FAILING_WARNINGS != 0 {
+        let flags = critical_warning_flags(log.critical_warning & FAILING_WARNINGS);
+        grading.lower(Grade::Bad, format!("critical warning: {}", flags.join(", ")));
+    } else if log.critical_warning != 0 {
+        grading.lower(Grade::Fair, "critical warning: temperature out of range");
+    }
+    if log.available_spare < log.available_spare_threshold {
+        grading.lower(
+            Grade::Bad,
+            format!("spare {}% is below the {}% threshold", log.available_spare, log.available_spare_threshold),
+        );
+    } else if log.available_spare < log.available_spare_threshold.saturating_add(10) {
+        grading.lower(Grade::Fair, format!("spare {}% is close to the threshold", log.available_spare));
+    }
+    grading.rule(&rules.nvme_media_errors, log.media_errors, |n| {
+        format!("{} media and data-integrity errors", n)
+    });
+    grading.rule(&rules.nvme_percentage_used, log.percentage_used as u64, |n| {
+        format!("{}% of rated endurance used", n)
+    });
+    if log.critical_comp_time > 0 {
+        grading.lower(Grade::Fair, format!("{} minutes above critical temperature", log.critical_comp_time));
+    }
+
+    grading
+}
+
+/// The NVMe counterpart of the ATA attribute table.
+pub fn nvme_lines(log: &NvmeHealthLog) -> Vec<Line<'static>> {
+    let field = |key: &str, value: String, color: Color| {
+        Line::from(vec![
+            Span::styled(format!("{}: ", key), Style::default().fg(Color::Cyan).bold()),
+            Span::styled(value, Style::default().fg(color)),
+        ])
+    };
+    let warn_if = |bad: bool| if bad { Color::Red } else { Color::White };
+
+    let wear = wear_grade(log, &config::get().grading);
+    let mut lines = vec![
+        Line::from(Span::styled("NVMe Health".to_string(), Style::default().fg(Color::Cyan).bold())),
+        field("Wear grade", wear.grade.label().to_string(), wear.grade.color()),
+    ];
+    for reason in wear.reasons {
+        lines.push(Line::from(Span::styled(format!("  • {}", reason), Style::default().fg(wear.grade.color()))));
+    }
+
+    let flags = critical_warning_flags(log.critical_warning);
+    lines.push(field(
+        "Critical warning",
+        if flags.is_empty() {
+            "none".to_string()
+        } else {
+            format!("0x{:02x} ({})", log.critical_warning, flags.join(", "))
+        },
+        warn_if(!flags.is_empty()),
+    ));
+    lines.push(field("Percentage used", format!("{}%", log.percentage_used), warn_if(log.percentage_used >= 80)));
+    lines.push(field(
+        "Available spare",
+        format!("{}% (threshold {}%)", log.available_spare, log.available_spare_threshold),
+        warn_if(log.available_spare < log.available_spare_threshold),
+    ));
+    lines.push(field("Media/integrity errors", log.media_errors.to_string(), warn_if(log.media_errors > 0)));
+    lines.push(field("Error log entries", log.num_err_log_entries.to_string(), Color::White));
+    lines.push(field("Unsafe shutdowns", log.unsafe_shutdowns.to_string(), Color::White));
+    lines.push(field("Data read", format!("{:.2} TB", data_units_to_tb(log.data_units_read)), Color::White));
+    lines.push(field("Data written", format!("{:.2} TB", data_units_to_tb(log.data_units_written)), Color::White));
+
+    let sensors = if log.temperature_sensors.is_empty() {
+        format!("{} °C", log.temperature)
+    } else {
+        log.temperature_sensors
+            .iter()
+            .map(|t| format!("{} °C", t))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    lines.push(field("Temperature sensors", sensors, Color::White));
+    if log.warning_temp_time > 0 || log.critical_comp_time > 0 {
+        lines.push(field(
+            "Time over temperature",
+            format!("{} min warning, {} min critical", log.warning_temp_time, log.critical_comp_time),
+            warn_if(log.critical_comp_time > 0),
+        ));
+    }
+    lines
+}
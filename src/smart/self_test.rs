@@ -0,0 +1,350 @@
+use chrono::{DateTime, Local};
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::data::{SelfTestEntry, SmartData};
+use super::{read_smart_as, SMART_DATA};
+use crate::command::{CommandRunner, SystemRunner};
+use crate::jobs::{self, JobId};
+use crate::session::{self, TestResult, TestStatus};
+
+const POLL_INTERVAL: Duration = Duration::from_secs(5);
+/// A drive may take a moment to report a test it has just accepted.
+const START_GRACE: Duration = Duration::from_secs(30);
+/// Consecutive failed reads before the job gives up on the drive.
+const MAX_POLL_FAILURES: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SelfTestKind {
+    Short,
+    Extended,
+    Conveyance,
+}
+
+impl SelfTestKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            SelfTestKind::Short => "Short",
+            SelfTestKind::Extended => "Extended",
+            SelfTestKind::Conveyance => "Conveyance",
+        }
+    }
+
+    /// The `smartctl -t` argument.
+    pub fn arg(&self) -> &'static str {
+        match self {
+            SelfTestKind::Short => "short",
+            SelfTestKind::Extended => "long",
+            SelfTestKind::Conveyance => "conveyance",
+        }
+    }
+}
+
+/// The self-test job and the device it runs on.
+static SELF_TEST_JOB: Lazy<Mutex<Option<(JobId, String)>>> = Lazy::new(|| Mutex::new(None));
+/// Progress line or final verdict for the SMART screen.
+static SELF_TEST_STATUS: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new(String::new()));
+
+/// smartctl's error messages, or its exit status when it printed none.
+fn smartctl_error(stdout: &str, status: Option<i32>) -> String {
+    let errors = SmartData::parse(stdout)
+        .map(|data| data.errors().join("; "))
+        .unwrap_or_default();
+    if errors.is_empty() {
+        format!("smartctl exited with {:?}", status)
+    } else {
+        errors
+    }
+}
+
+pub fn start_self_test(
+    runner: &dyn CommandRunner,
+    device: &str,
+    dev_type: &str,
+    kind: SelfTestKind,
+) -> Result<(), String> {
+    let output = runner
+        .run("smartctl", &["-t", kind.arg(), "--json", "-d", dev_type, device])
+        .map_err(|e| format!("Failed to run smartctl: {}", e))?;
+    if output.success() {
+        Ok(())
+    } else {
+        Err(smartctl_error(&output.stdout, output.status))
+    }
+}
+
+pub fn abort_self_test(runner: &dyn CommandRunner, device: &str, dev_type: &str) -> Result<(), String> {
+    let output = runner
+        .run("smartctl", &["-X", "--json", "-d", dev_type, device])
+        .map_err(|e| format!("Failed to run smartctl: {}", e))?;
+    if output.success() {
+        Ok(())
+    } else {
+        Err(smartctl_error(&output.stdout, output.status))
+    }
+}
+
+/// Percent of the running self-test still to go, or `None` when no test is running.
+pub fn remaining_percent(data: &SmartData) -> Option<u8> {
+    if let Some(log) = &data.nvme_self_test_log {
+        if log.current_self_test_operation.value == 0 {
+            return None;
+        }
+        return Some(100 - log.current_self_test_completion_percent.unwrap_or(0).min(100));
+    }
+    let status = &data.ata_smart_data.as_ref()?.self_test.status;
+    if status.value >> 4 == 0xF {
+        Some(status.remaining_percent.unwrap_or(100))
+    } else {
+        None
+    }
+}
+
+/// How long the drive says a test takes. NVMe short tests are capped at two
+/// minutes by the spec; smartctl does not report the extended time.
+pub fn recommended_duration(data: &SmartData, kind: SelfTestKind) -> Option<Duration> {
+    let minutes = match data.ata_smart_data.as_ref() {
+        Some(ata) => {
+            let polling = &ata.self_test.polling_minutes;
+            match kind {
+                SelfTestKind::Short => polling.short,
+                SelfTestKind::Extended => polling.extended,
+                SelfTestKind::Conveyance => polling.conveyance,
+            }
+        }
+        None if data.is_nvme() && kind == SelfTestKind::Short => Some(2),
+        None => None,
+    }?;
+    Some(Duration::from_secs(minutes * 60))
+}
+
+/// Percent done and time left. ATA drives report progress in 10% steps, so
+/// between steps the gauge moves on elapsed time, never past the next step.
+pub fn estimate(elapsed: Duration, remaining: u8, recommended: Option<Duration>) -> (u16, Option<Duration>) {
+    let reported = 100 - remaining.min(100) as u16;
+    let by_time = recommended
+        .filter(|r| !r.is_zero())
+        .map(|r| (elapsed.as_secs_f64() / r.as_secs_f64() * 100.0) as u16);
+    let progress = match by_time {
+        Some(t) => t.clamp(reported, (reported + 9).min(99)),
+        None => reported,
+    };
+
+    let eta = if reported >= 10 {
+        Some(elapsed.mul_f64(remaining as f64 / reported as f64))
+    } else {
+        recommended.map(|r| r.saturating_sub(elapsed))
+    };
+    (progress, eta)
+}
+
+pub fn format_eta(eta: Duration) -> String {
+    let secs = eta.as_secs();
+    if secs >= 3600 {
+        format!("{}h{:02}m", secs / 3600, secs % 3600 / 60)
+    } else {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    }
+}
+
+/// The newest self-test log entry, if it was written after `before` was
+/// read. Log entries only carry the power-on hour, so an entry from the same
+/// hour also has to differ from the newest one in `before`.
+pub fn new_entry(after: &SmartData, before: &SmartData) -> Option<SelfTestEntry> {
+    let newest = after.self_tests().into_iter().next()?;
+    let previous = before.self_tests().into_iter().next();
+    let since = before.power_on_hours().map(|hours| {
+        // The ATA log keeps only the low 16 bits of the hour count.
+        if after.is_nvme() {
+            hours
+        } else {
+            hours % 65536
+        }
+    });
+    let fresh = since.is_none_or(|hours| newest.hours >= hours) && previous.as_ref() != Some(&newest);
+    fresh.then_some(newest)
+}
+
+/// Grades a finished self-test from the log entry it wrote, if any.
+pub fn self_test_result(
+    device: &str,
+    kind: SelfTestKind,
+    started: DateTime<Local>,
+    entry: Option<&SelfTestEntry>,
+) -> TestResult {
+    let result = TestResult::new("SMART self-test", device)
+        .started(started)
+        .metric("kind", kind.label());
+    let Some(entry) = entry else {
+        return result
+            .status(TestStatus::Warn)
+            .metric("result", "No new self-test log entry")
+            .finish();
+    };
+
+    let result = result
+        .status(match entry.passed {
+            Some(true) => TestStatus::Pass,
+            Some(false) => TestStatus::Fail,
+            None => TestStatus::Warn,
+        })
+        .metric("result", &entry.status)
+        .metric("lifetime_hours", entry.hours);
+    match entry.lba {
+        Some(lba) => result.metric("first_error_lba", lba).finish(),
+        None => result.finish(),
+    }
+}
+
+fn summary(result: &TestResult) -> String {
+    let mut text = format!(
+        "{} self-test {}: {}",
+        result.metrics["kind"],
+        result.status.label(),
+        result.metrics["result"]
+    );
+    if let Some(lba) = result.metrics.get("first_error_lba") {
+        text.push_str(&format!(", first error at LBA {}", lba));
+    }
+    text
+}
+
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> T + Send + 'static) -> Result<T, String> {
+    tokio::task::spawn_blocking(f).await.map_err(|e| e.to_string())
+}
+
+/// Aborts the test on the drive and records why it did not finish.
+async fn stop(
+    device: &str,
+    dev_type: &str,
+    kind: SelfTestKind,
+    started: DateTime<Local>,
+    reason: &str,
+    status: TestStatus,
+) -> Result<String, String> {
+    let (dev, ty) = (device.to_string(), dev_type.to_string());
+    let aborted = blocking(move || abort_self_test(&SystemRunner, &dev, &ty)).await?;
+    let message = match aborted {
+        Ok(()) => format!("{}Self-test aborted.", reason),
+        Err(e) => format!("{}Abort failed: {}", reason, e),
+    };
+    log::warn!("{} self-test on {}: {}", kind.label(), device, message);
+    session::record(
+        TestResult::new("SMART self-test", device)
+            .started(started)
+            .status(status)
+            .metric("kind", kind.label())
+            .raw_output(message.clone())
+            .finish(),
+    );
+    *SELF_TEST_STATUS.lock().unwrap() = message.clone();
+    Ok(message)
+}
+
+/// Starts a self-test on the drive shown on the SMART screen and polls it
+/// in a background job. Cancelling the job aborts the test on the drive.
+pub fn run_self_test(kind: SelfTestKind) {
+    let mut job = SELF_TEST_JOB.lock().unwrap();
+    if job.as_ref().is_some_and(|(id, _)| jobs::is_running(*id)) {
+        return;
+    }
+    let Some(data) = SMART_DATA.lock().unwrap().clone() else {
+        return;
+    };
+    let device = data.device.name.clone();
+    let dev_type = data.device.device_type.clone();
+    *SELF_TEST_STATUS.lock().unwrap() = format!("Starting {} self-test...", kind.label().to_lowercase());
+
+    let name = format!("SMART {} self-test {}", kind.label().to_lowercase(), device);
+    let job_device = device.clone();
+    let id = jobs::spawn(&name, move |ctx| async move {
+        let started = Local::now();
+        let clock = Instant::now();
+        let recommended = recommended_duration(&data, kind);
+
+        // Read right before starting so an older log entry is not taken for this test's.
+        let (dev, ty) = (device.clone(), dev_type.clone());
+        let before = blocking(move || read_smart_as(&SystemRunner, &dev, &ty))
+            .await?
+            .unwrap_or(data);
+
+        let (dev, ty) = (device.clone(), dev_type.clone());
+        if let Err(e) = blocking(move || start_self_test(&SystemRunner, &dev, &ty, kind)).await? {
+            log::error!("Could not start self-test on {}: {}", device, e);
+            *SELF_TEST_STATUS.lock().unwrap() = e.clone();
+            return Err(e);
+        }
+        log::info!("Started {} self-test on {}", kind.label(), device);
+
+        let mut seen_running = false;
+        let mut failures = 0;
+        loop {
+            if ctx.sleep(POLL_INTERVAL).await.is_err() {
+                let message = stop(&device, &dev_type, kind, started, "", TestStatus::Skipped).await?;
+                return Err(message);
+            }
+
+            let (dev, ty) = (device.clone(), dev_type.clone());
+            let data = match blocking(move || read_smart_as(&SystemRunner, &dev, &ty)).await? {
+                Ok(data) => {
+                    failures = 0;
+                    data
+                }
+                Err(e) => {
+                    failures += 1;
+                    log::warn!("Cannot read self-test progress on {}: {}", device, e);
+                    if failures < MAX_POLL_FAILURES {
+                        continue;
+                    }
+                    let reason = format!("Lost contact with the drive ({}). ", e);
+                    let message = stop(&device, &dev_type, kind, started, &reason, TestStatus::Warn).await?;
+                    return Err(message);
+                }
+            };
+
+            match remaining_percent(&data) {
+                Some(remaining) => {
+                    seen_running = true;
+                    let (progress, eta) = estimate(clock.elapsed(), remaining, recommended);
+                    ctx.progress(progress);
+                    *SELF_TEST_STATUS.lock().unwrap() = match eta {
+                        Some(eta) => format!("{}% done, about {} left", progress, format_eta(eta)),
+                        None => format!("{}% done", progress),
+                    };
+                }
+                None if !seen_running && clock.elapsed() < START_GRACE => {}
+                None => {
+                    let result = self_test_result(&device, kind, started, new_entry(&data, &before).as_ref());
+                    let text = summary(&result);
+                    log::info!("{} on {}", text, device);
+                    *SELF_TEST_STATUS.lock().unwrap() = text.clone();
+                    session::record(result);
+                    let mut shown = SMART_DATA.lock().unwrap();
+                    if shown.as_ref().is_some_and(|d| d.device.name == device) {
+                        *shown = Some(data);
+                    }
+                    return Ok(text);
+                }
+            }
+        }
+    });
+    *job = Some((id, job_device));
+}
+
+pub fn cancel_self_test() {
+    if let Some((id, _)) = &*SELF_TEST_JOB.lock().unwrap() {
+        jobs::cancel(*id);
+    }
+}
+
+/// Gauge percentage and status text for `device`, if a self-test was started on it.
+pub fn self_test_progress(device: &str) -> Option<(u16, String)> {
+    let job = SELF_TEST_JOB
+        .lock()
+        .unwrap()
+        .as_ref()
+        .filter(|(_, dev)| dev == device)
+        .and_then(|(id, _)| jobs::get(*id))?;
+    Some((job.progress.unwrap_or(0), SELF_TEST_STATUS.lock().unwrap().clone()))
+}
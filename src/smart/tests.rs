@@ -0,0 +1,641 @@
+use super::*;
+use crate::command::FixtureRunner;
+use crate::config::{GradingConfig, Thresholds};
+use crate::session::TestStatus;
+use chrono::Local;
+use std::fs;
+use std::time::Duration;
+
+fn data(name: &str) -> SmartData {
+    SmartData::parse(&FixtureRunner::fixture(name)).unwrap()
+}
+
+fn attribute(data: &SmartData, id: u8) -> &data::AtaAttribute {
+    data.attributes().iter().find(|a| a.id == id).unwrap()
+}
+
+#[test]
+fn parses_healthy_sata_drive() {
+    let summary = SmartSummary::from_data(&data("smartctl_sata_hdd.stdout"));
+    assert_eq!(summary.health, "Great");
+    assert_eq!(summary.family, "Seagate BarraCuda 3.5 (SMR)");
+    assert_eq!(summary.model, "ST2000DM008-2FR102");
+    assert_eq!(summary.serial, "ZFL1ABCD");
+    assert_eq!(summary.capacity, "1.82 TB");
+    assert_eq!(summary.temperature, "34");
+    assert_eq!(summary.power_on_hours, "10842");
+}
+
+#[test]
+fn parses_ata_attribute_table() {
+    let data = data("smartctl_sata_hdd.stdout");
+    assert_eq!(data.attributes().len(), 15);
+    let airflow = attribute(&data, 190);
+    assert_eq!(airflow.name, "Airflow_Temperature_Cel");
+    assert_eq!(airflow.raw.string, "34 (Min/Max 22/41)");
+    assert!(attribute(&data, 5).flags.prefailure);
+    assert!(!data.is_nvme());
+}
+
+#[test]
+fn failing_drive_is_bad_and_fails() {
+    let data = data("smartctl_failing_hdd.stdout");
+    assert_eq!(SmartSummary::from_data(&data).health, "Bad");
+    assert_eq!(attribute(&data, 5).when_failed, "now");
+
+    let result = smart_result("/dev/sdb", &data);
+    assert_eq!(result.status, TestStatus::Fail);
+    assert_eq!(result.metrics["model"], "WDC WD10EZEX-08WN4A0");
+    assert!(result.raw_output.contains("\"smart_status\""));
+}
+
+#[test]
+fn reads_self_test_log_with_first_error() {
+    let tests = data("smartctl_failing_hdd.stdout").self_tests();
+    assert_eq!(
+        tests,
+        vec![data::SelfTestEntry {
+            test: "Short offline".to_string(),
+            status: "Completed: read failure".to_string(),
+            passed: Some(false),
+            hours: 44900,
+            lba: Some(1953525160),
+        }]
+    );
+    assert_eq!(data("smartctl_sata_hdd.stdout").self_tests()[0].passed, Some(true));
+}
+
+#[test]
+fn healthy_drive_passes() {
+    let result = smart_result("/dev/sda", &data("smartctl_sata_hdd.stdout"));
+    assert_eq!(result.test, "SMART");
+    assert_eq!(result.device, "/dev/sda");
+    assert_eq!(result.status, TestStatus::Pass);
+    assert_eq!(result.metrics["health"], "Great");
+}
+
+#[test]
+fn missing_health_status_warns() {
+    let output = r#"{"smartctl":{"exit_status":2,"messages":[{"string":"Smartctl open device: /dev/sdz failed: No such device","severity":"error"}]},"device":{"name":"/dev/sdz"}}"#;
+    let data = SmartData::parse(output).unwrap();
+    assert_eq!(data.errors(), vec!["Smartctl open device: /dev/sdz failed: No such device"]);
+
+    let result = smart_result("/dev/sdz", &data);
+    assert_eq!(result.status, TestStatus::Warn);
+    assert_eq!(result.metrics["health"], "Unknown");
+    assert_eq!(result.metrics["model"], "Unknown");
+}
+
+#[test]
+fn parses_nvme_drive() {
+    let data = data("smartctl_nvme.stdout");
+    assert!(data.is_nvme());
+    assert!(data.attributes().is_empty());
+
+    let log = data.nvme_smart_health_information_log.as_ref().unwrap();
+    assert_eq!(log.percentage_used, 3);
+    assert_eq!(log.available_spare, 100);
+    assert_eq!(log.num_err_log_entries, 2114);
+
+    let summary = SmartSummary::from_data(&data);
+    assert_eq!(summary.health, "Great");
+    assert_eq!(summary.model, "Samsung SSD 970 EVO Plus 1TB");
+    assert_eq!(summary.capacity, "931.51 GB");
+    assert_eq!(summary.temperature, "41");
+    assert_eq!(summary.power_on_hours, "6204");
+    assert_eq!(data.self_tests()[0].test, "Short");
+}
+
+#[test]
+fn text_output_is_rejected() {
+    assert!(SmartData::parse("smartctl 7.4 2023-08-01 r5530").is_err());
+}
+
+#[test]
+fn parses_lsblk_json() {
+    let drives = drive::parse_lsblk_json(&FixtureRunner::fixture("lsblk_json.stdout")).unwrap();
+    assert_eq!(drives.len(), 7);
+    assert_eq!(
+        drives[2],
+        Drive {
+            path: "/dev/sdb".to_string(),
+            name: "sdb".to_string(),
+            size: 1000204886016,
+            model: Some("WDC WD10EZEX-08WN4A0".to_string()),
+            serial: Some("WD-WCC6Y1234567".to_string()),
+            transport: Some("sata".to_string()),
+            rotational: true,
+            removable: false,
+            vendor: Some("ATA".to_string()),
+            wwn: Some("0x50014ee2b1234567".to_string()),
+            kind: "disk".to_string(),
+            system_use: Vec::new(),
+        }
+    );
+    // Drives without a model are kept.
+    assert_eq!(drives[4].path, "/dev/sdd");
+    assert_eq!(drives[4].model_text(), "Unknown");
+    assert!(drives[3].removable);
+    assert_eq!(drives[6].media(), "SSD");
+
+    let virtual_devices: Vec<&str> = drives.iter().filter(|d| d.is_virtual()).map(|d| d.name.as_str()).collect();
+    assert_eq!(virtual_devices, vec!["loop0", "zram0"]);
+}
+
+#[test]
+fn parses_legacy_lsblk_json() {
+    let drives = drive::parse_lsblk_json(&FixtureRunner::fixture("lsblk_json_legacy.stdout")).unwrap();
+    assert_eq!(drives[0].path, "/dev/sda");
+    assert_eq!(drives[0].size, 2000398934016);
+    assert!(drives[0].rotational);
+    assert_eq!(drives[1].model.as_deref(), Some("Cruzer Blade"));
+    assert!(drives[1].removable && !drives[1].rotational);
+    assert!(drive::parse_lsblk_json("NAME SIZE MODEL").is_err());
+}
+
+#[test]
+fn lists_drives_through_runner() {
+    let command = format!("lsblk --json -b -d -o {}", drive::LSBLK_COLUMNS);
+    let runner = FixtureRunner::new().with(&command, "lsblk_json");
+    assert_eq!(list_drives(&runner).unwrap().len(), 7);
+    let physical: Vec<String> = physical_drives(&runner).unwrap().into_iter().map(|d| d.path).collect();
+    assert_eq!(physical, vec!["/dev/sda", "/dev/sdb", "/dev/sdc", "/dev/sdd", "/dev/nvme0n1"]);
+    assert_eq!(runner.calls(), vec![command.clone(), command]);
+}
+
+#[test]
+fn hotplug_diff_reports_added_and_removed_devices() {
+    let dir = tempfile::tempdir().unwrap();
+    for name in ["sda", "sdb", "nvme0n1"] {
+        fs::create_dir(dir.path().join(name)).unwrap();
+    }
+    let before = hotplug::block_devices(dir.path()).unwrap();
+    fs::remove_dir(dir.path().join("sdb")).unwrap();
+    fs::create_dir(dir.path().join("sdc")).unwrap();
+    let after = hotplug::block_devices(dir.path()).unwrap();
+
+    let change = hotplug::diff(&before, &after);
+    assert_eq!(change.added, vec!["sdc"]);
+    assert_eq!(change.removed, vec!["sdb"]);
+    assert!(hotplug::diff(&after, &after).is_empty());
+}
+
+#[test]
+fn hotplug_keeps_the_last_list_when_a_read_fails() {
+    let names = |list: &[&str]| -> BTreeSet<String> { list.iter().map(|s| s.to_string()).collect() };
+    let mut known = None;
+    assert_eq!(hotplug::update(&mut known, Ok(names(&["sda", "sdb"]))), None);
+    assert_eq!(hotplug::update(&mut known, Err(io::Error::from_raw_os_error(libc::EIO))), None);
+    let change = hotplug::update(&mut known, Ok(names(&["sda", "sdb", "sdc"]))).unwrap();
+    assert_eq!(change.added, vec!["sdc"]);
+    assert!(change.removed.is_empty());
+}
+
+#[test]
+fn parses_mountinfo_and_swaps() {
+    let mounts = usage::parse_mountinfo(&FixtureRunner::fixture("proc_mountinfo.stdout"));
+    let devices: Vec<&str> = mounts.iter().map(|m| m.device.as_str()).collect();
+    assert_eq!(
+        devices,
+        vec!["/dev/nvme0n1p2", "/dev/nvme0n1p1", "/dev/loop0", "/dev/mapper/vg-data", "/dev/sdc1"]
+    );
+    assert!(mounts[0].usage.is_root());
+    assert_eq!(mounts[0].dev.as_deref(), Some("259:2"));
+    assert_eq!(mounts[4].usage, usage::Usage::Mounted("/media/tech/USB STICK".to_string()));
+
+    let swaps = usage::parse_swaps(&FixtureRunner::fixture("proc_swaps.stdout"));
+    assert_eq!(
+        swaps,
+        vec![usage::InUse {
+            device: "/dev/zram0".to_string(),
+            dev: None,
+            usage: usage::Usage::Swap("/dev/zram0".to_string()),
+        }]
+    );
+}
+
+#[test]
+fn partitions_resolve_to_parent_disk() {
+    let disks: Vec<String> = ["sda", "sdaa", "nvme0n1", "mmcblk0", "loop1"].iter().map(|s| s.to_string()).collect();
+    assert_eq!(usage::partition_parent("sda2", &disks), Some("sda"));
+    assert_eq!(usage::partition_parent("sdaa1", &disks), Some("sdaa"));
+    assert_eq!(usage::partition_parent("nvme0n1p3", &disks), Some("nvme0n1"));
+    assert_eq!(usage::partition_parent("mmcblk0p1", &disks), Some("mmcblk0"));
+    assert_eq!(usage::partition_parent("loop12", &disks), None);
+    assert_eq!(usage::partition_parent("nvme0n12", &disks), None);
+    assert_eq!(usage::partition_parent("sdb1", &disks), None);
+}
+
+#[test]
+fn marks_mounted_and_swap_disks() {
+    // device-mapper volume vg-data (dm-0) sits on the first partition of sdd.
+    let sys = tempfile::tempdir().unwrap();
+    let dm = sys.path().join("block/dm-0");
+    fs::create_dir_all(dm.join("dm")).unwrap();
+    fs::write(dm.join("dm/name"), "vg-data\n").unwrap();
+    fs::create_dir_all(dm.join("slaves/sdd1")).unwrap();
+
+    let mut drives = drive::parse_lsblk_json(&FixtureRunner::fixture("lsblk_json.stdout")).unwrap();
+    let mut in_use = usage::parse_mountinfo(&FixtureRunner::fixture("proc_mountinfo.stdout"));
+    in_use.extend(usage::parse_swaps(&FixtureRunner::fixture("proc_swaps.stdout")));
+    usage::annotate(&mut drives, &in_use, sys.path());
+
+    let uses = |name: &str| drives.iter().find(|d| d.name == name).unwrap().system_use.clone();
+    assert_eq!(
+        uses("nvme0n1"),
+        vec![usage::Usage::Mounted("/".to_string()), usage::Usage::Mounted("/boot/efi".to_string())]
+    );
+    assert_eq!(uses("sdd"), vec![usage::Usage::Mounted("/srv/data".to_string())]);
+    assert_eq!(uses("sdc"), vec![usage::Usage::Mounted("/media/tech/USB STICK".to_string())]);
+    assert_eq!(uses("zram0"), vec![usage::Usage::Swap("/dev/zram0".to_string())]);
+    assert!(uses("sda").is_empty() && uses("sdb").is_empty());
+
+    let boot: Vec<&str> = drives.iter().filter(|d| d.is_boot_disk()).map(|d| d.path.as_str()).collect();
+    assert_eq!(boot, vec!["/dev/nvme0n1"]);
+    let refusal = usage::refusal("/dev/nvme0n1", &uses("nvme0n1"));
+    assert!(refusal.contains("holds the running root filesystem"));
+    assert!(refusal.contains("system disk"));
+}
+
+#[test]
+fn guard_refuses_disk_under_lvm_root() {
+    // Root is on LVM volume vg-root (dm-0), which sits on sda2.
+    let root = tempfile::tempdir().unwrap();
+    let (proc, sys) = (root.path().join("proc"), root.path().join("sys"));
+    fs::create_dir_all(proc.join("self")).unwrap();
+    fs::write(
+        proc.join("self/mountinfo"),
+        "22 1 253:0 / / rw,relatime shared:1 - ext4 /dev/mapper/vg-root rw\n",
+    )
+    .unwrap();
+    fs::write(proc.join("swaps"), "Filename\tType\tSize\tUsed\tPriority\n").unwrap();
+    fs::create_dir_all(sys.join("block/sda/sda2")).unwrap();
+    fs::create_dir_all(sys.join("block/sdb")).unwrap();
+    fs::create_dir_all(sys.join("block/dm-0/dm")).unwrap();
+    fs::write(sys.join("block/dm-0/dm/name"), "vg-root\n").unwrap();
+    fs::create_dir_all(sys.join("block/dm-0/slaves/sda2")).unwrap();
+
+    let refusal = usage::guard_at("/dev/sda", &proc, &sys).unwrap_err();
+    assert!(refusal.contains("holds the running root filesystem"));
+    assert!(usage::guard_at("/dev/sdb", &proc, &sys).is_ok());
+    assert!(usage::guard_at("/dev/sdc", &root.path().join("missing"), &sys).is_err());
+}
+
+#[test]
+fn guard_resolves_dev_root_by_device_number() {
+    // Booted without an initramfs: root shows up as /dev/root, which is 8:2 (sda2).
+    let root = tempfile::tempdir().unwrap();
+    let (proc, sys) = (root.path().join("proc"), root.path().join("sys"));
+    fs::create_dir_all(proc.join("self")).unwrap();
+    fs::write(
+        proc.join("self/mountinfo"),
+        "15 1 8:2 / / rw,relatime shared:1 - ext4 /dev/root rw\n",
+    )
+    .unwrap();
+    fs::create_dir_all(sys.join("block/sda/sda2")).unwrap();
+    fs::create_dir_all(sys.join("block/sdb")).unwrap();
+    fs::create_dir_all(sys.join("dev/block")).unwrap();
+    std::os::unix::fs::symlink("../../block/sda/sda2", sys.join("dev/block/8:2")).unwrap();
+
+    let refusal = usage::guard_at("/dev/sda", &proc, &sys).unwrap_err();
+    assert!(refusal.contains("holds the running root filesystem"));
+    assert!(usage::guard_at("/dev/sdb", &proc, &sys).is_ok());
+
+    // Without the device number link the root disk is unknown, so every disk is refused.
+    fs::remove_file(sys.join("dev/block/8:2")).unwrap();
+    let refusal = usage::guard_at("/dev/sdb", &proc, &sys).unwrap_err();
+    assert!(refusal.contains("Cannot tell which disk holds the root filesystem"));
+}
+
+#[test]
+fn refresh_keeps_selection_and_flags_new_drives() {
+    let drives = drive::parse_lsblk_json(&FixtureRunner::fixture("lsblk_json.stdout")).unwrap();
+    let without_sdb: Vec<Drive> = drives.iter().filter(|d| d.name != "sdb").cloned().collect();
+    refresh_drives(without_sdb, &[]);
+    *SELECTED_DISK_INDEX.lock().unwrap() = 2;
+    assert_eq!(selected_drive().unwrap().path, "/dev/sdd");
+
+    refresh_drives(drives.clone(), &["/dev/sdb".to_string()]);
+    assert_eq!(selected_drive().unwrap().path, "/dev/sdd");
+    assert!(is_new_drive("/dev/sdb"));
+    assert!(!is_new_drive("/dev/sda"));
+
+    let without_sdd: Vec<Drive> = drives.into_iter().filter(|d| d.name != "sdd").collect();
+    refresh_drives(without_sdd, &[]);
+    assert_eq!(selected_drive().unwrap().path, "/dev/nvme0n1");
+}
+
+#[test]
+fn missing_lsblk_is_an_error() {
+    assert!(list_drives(&FixtureRunner::new()).is_err());
+}
+
+#[test]
+fn read_smart_uses_detected_device_type() {
+    let runner = FixtureRunner::new()
+        .with("smartctl -i --json=c /dev/sda", "smartctl_info_sat")
+        .with("smartctl -a --json -d sat /dev/sda", "smartctl_sata_hdd");
+
+    let data = read_smart(&runner, "/dev/sda").unwrap();
+    assert_eq!(data.model(), Some("ST2000DM008-2FR102"));
+    assert_eq!(
+        runner.calls(),
+        vec!["smartctl -i --json=c /dev/sda", "smartctl -a --json -d sat /dev/sda"]
+    );
+}
+
+#[test]
+fn read_smart_falls_back_to_auto() {
+    let runner = FixtureRunner::new().with("smartctl -a --json -d auto /dev/sdb", "smartctl_failing_hdd");
+    let data = read_smart(&runner, "/dev/sdb").unwrap();
+    assert_eq!(data.smartctl.exit_status, 8);
+}
+
+#[test]
+fn read_smart_detects_nvme() {
+    let runner = FixtureRunner::new()
+        .with("smartctl -i --json=c /dev/nvme0", "smartctl_info_nvme")
+        .with("smartctl -a --json -d nvme /dev/nvme0", "smartctl_nvme");
+    assert!(read_smart(&runner, "/dev/nvme0").unwrap().is_nvme());
+}
+
+#[test]
+fn formats_capacity() {
+    assert_eq!(format_capacity(512), "512 bytes");
+    assert_eq!(format_capacity(16 << 20), "16.00 MB");
+    assert_eq!(format_capacity(1_000_204_886_016), "931.51 GB");
+}
+
+fn nvme_log(percentage_used: u8, available_spare: u8, media_errors: u64, critical_warning: u64) -> data::NvmeHealthLog {
+    data::NvmeHealthLog {
+        percentage_used,
+        available_spare,
+        available_spare_threshold: 10,
+        media_errors,
+        critical_warning,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn decodes_nvme_critical_warning_bits() {
+    assert!(nvme::critical_warning_flags(0).is_empty());
+    assert_eq!(
+        nvme::critical_warning_flags(0x05),
+        vec!["available spare below threshold", "reliability degraded by media errors"]
+    );
+}
+
+#[test]
+fn converts_nvme_data_units_to_tb() {
+    let log = data("smartctl_nvme.stdout").nvme_smart_health_information_log.unwrap();
+    assert_eq!(format!("{:.2}", nvme::data_units_to_tb(log.data_units_written)), "15.88");
+    assert_eq!(log.temperature_sensors, vec![41, 47]);
+}
+
+#[test]
+fn grades_nvme_wear() {
+    let rules = GradingConfig::default();
+    let grade = |log| nvme::wear_grade(&log, &rules).grade;
+    assert_eq!(nvme::wear_grade(&nvme_log(3, 100, 0, 0), &rules), Grading::new());
+    assert_eq!(grade(nvme_log(45, 100, 0, 0)), Grade::Good);
+    assert_eq!(grade(nvme_log(85, 100, 0, 0)), Grade::Fair);
+    assert_eq!(grade(nvme_log(10, 15, 0, 0)), Grade::Fair);
+    assert_eq!(grade(nvme_log(100, 100, 0, 0)), Grade::Bad);
+    assert_eq!(grade(nvme_log(3, 5, 0, 0x01)), Grade::Bad);
+    assert_eq!(grade(nvme_log(3, 100, 0, 0x02)), Grade::Fair);
+
+    let grading = nvme::wear_grade(&nvme_log(90, 100, 4, 0), &rules);
+    assert_eq!(grading.grade, Grade::Bad);
+    assert_eq!(grading.reasons, vec!["4 media and data-integrity errors", "90% of rated endurance used"]);
+}
+
+#[test]
+fn worn_nvme_fails_despite_passing_smart() {
+    let mut data = data("smartctl_nvme.stdout");
+    let result = smart_result("/dev/nvme0", &data);
+    assert_eq!(result.status, TestStatus::Pass);
+    assert_eq!(result.metrics["health"], "Great");
+    assert_eq!(result.metrics["data_written_tb"], "15.88");
+
+    data.nvme_smart_health_information_log.as_mut().unwrap().media_errors = 12;
+    let result = smart_result("/dev/nvme0", &data);
+    assert_eq!(result.status, TestStatus::Fail);
+    assert_eq!(result.metrics["grade_reasons"], "12 media and data-integrity errors");
+}
+
+#[test]
+fn applies_thresholds() {
+    let rules = Thresholds::new(Some(1), Some(10), Some(100));
+    assert_eq!(grade::threshold_grade(&rules, 0), Grade::Great);
+    assert_eq!(grade::threshold_grade(&rules, 1), Grade::Good);
+    assert_eq!(grade::threshold_grade(&rules, 99), Grade::Fair);
+    assert_eq!(grade::threshold_grade(&rules, 100), Grade::Bad);
+    assert_eq!(grade::threshold_grade(&Thresholds::default(), u64::MAX), Grade::Great);
+}
+
+#[test]
+fn passing_drive_with_remapped_sectors_is_not_great() {
+    let mut data = data("smartctl_sata_hdd.stdout");
+    assert_eq!(grade::grade_drive(&data, &GradingConfig::default()), Grading::new());
+
+    let table = &mut data.ata_smart_attributes.as_mut().unwrap().table;
+    table.iter_mut().find(|a| a.id == 5).unwrap().raw.value = 312;
+    table.iter_mut().find(|a| a.id == 199).unwrap().raw.value = 3;
+
+    let grading = grade::grade_drive(&data, &GradingConfig::default());
+    assert_eq!(grading.grade, Grade::Bad);
+    assert_eq!(grading.reasons, vec!["312 reallocated sectors", "3 UDMA CRC errors (check the cable)"]);
+    assert_eq!(smart_result("/dev/sda", &data).status, TestStatus::Fail);
+}
+
+#[test]
+fn failing_drive_lists_every_reason() {
+    let grading = grade::grade_drive(&data("smartctl_failing_hdd.stdout"), &GradingConfig::default());
+    assert_eq!(grading.grade, Grade::Bad);
+    assert_eq!(
+        grading.reasons,
+        vec![
+            "SMART overall health check failed",
+            "1848 reallocated sectors",
+            "212 sectors pending reallocation",
+            "198 offline uncorrectable sectors",
+            "Raw_Read_Error_Rate is below its failure threshold",
+            "Reallocated_Sector_Ct is below its failure threshold",
+            "44912 power-on hours",
+            "1 failed self-tests in the log",
+        ]
+    );
+}
+
+#[test]
+fn grading_rules_come_from_config() {
+    let data = data("smartctl_sata_hdd.stdout");
+    let strict = GradingConfig {
+        power_on_hours: Thresholds::new(None, Some(10_000), None),
+        ..GradingConfig::default()
+    };
+    let grading = grade::grade_drive(&data, &strict);
+    assert_eq!(grading.grade, Grade::Fair);
+    assert_eq!(grading.reasons, vec!["10842 power-on hours"]);
+
+    let config: crate::config::Config = toml::from_str("[grading]\npending_sectors = { bad = 1 }\n").unwrap();
+    assert_eq!(config.grading.pending_sectors, Thresholds::new(None, None, Some(1)));
+    assert_eq!(config.grading.reallocated_sectors, GradingConfig::default().reallocated_sectors);
+}
+
+#[test]
+fn starts_and_aborts_self_tests() {
+    use self_test::{abort_self_test, start_self_test, SelfTestKind};
+    let runner = FixtureRunner::new()
+        .with("smartctl -t long --json -d sat /dev/sda", "smartctl_selftest_start")
+        .with("smartctl -X --json -d sat /dev/sda", "smartctl_selftest_abort");
+    assert_eq!(start_self_test(&runner, "/dev/sda", "sat", SelfTestKind::Extended), Ok(()));
+    assert_eq!(abort_self_test(&runner, "/dev/sda", "sat"), Ok(()));
+}
+
+#[test]
+fn busy_drive_reports_smartctl_message() {
+    use self_test::{start_self_test, SelfTestKind};
+    let runner = FixtureRunner::new().with("smartctl -t short --json -d sat /dev/sda", "smartctl_selftest_busy");
+    assert_eq!(
+        start_self_test(&runner, "/dev/sda", "sat", SelfTestKind::Short),
+        Err("Can't start self-test without aborting current test (70% remaining),".to_string())
+    );
+}
+
+#[test]
+fn reads_self_test_progress() {
+    use self_test::{recommended_duration, remaining_percent, SelfTestKind};
+    let ata = data("smartctl_selftest_running_ata.stdout");
+    assert_eq!(remaining_percent(&ata), Some(70));
+    assert_eq!(recommended_duration(&ata, SelfTestKind::Extended), Some(Duration::from_secs(205 * 60)));
+    assert_eq!(recommended_duration(&ata, SelfTestKind::Conveyance), None);
+
+    let nvme = data("smartctl_selftest_running_nvme.stdout");
+    assert_eq!(remaining_percent(&nvme), Some(60));
+    assert_eq!(recommended_duration(&nvme, SelfTestKind::Short), Some(Duration::from_secs(120)));
+
+    assert_eq!(remaining_percent(&data("smartctl_sata_hdd.stdout")), None);
+    assert_eq!(remaining_percent(&data("smartctl_nvme.stdout")), None);
+}
+
+#[test]
+fn estimates_progress_between_steps() {
+    use self_test::{estimate, format_eta};
+    let minutes = |m: u64| Duration::from_secs(m * 60);
+
+    // Reported 30% done but the clock says 35%: the gauge follows the clock.
+    let (progress, eta) = estimate(minutes(35), 70, Some(minutes(100)));
+    assert_eq!(progress, 35);
+    assert_eq!(eta, Some(minutes(35).mul_f64(70.0 / 30.0)));
+
+    // The clock never pushes the gauge past the next reported step.
+    assert_eq!(estimate(minutes(80), 70, Some(minutes(100))).0, 39);
+    // Before the first step the drive's recommended time gives the ETA.
+    assert_eq!(estimate(minutes(1), 100, Some(minutes(2))), (9, Some(minutes(1))));
+    assert_eq!(estimate(minutes(1), 100, None), (0, None));
+
+    assert_eq!(format_eta(Duration::from_secs(95)), "1m35s");
+    assert_eq!(format_eta(minutes(205)), "3h25m");
+}
+
+#[test]
+fn self_test_result_reports_first_error_lba() {
+    use self_test::{self_test_result, SelfTestKind};
+    let failing = data("smartctl_failing_hdd.stdout");
+    let result = self_test_result("/dev/sdb", SelfTestKind::Short, Local::now(), failing.self_tests().first());
+    assert_eq!(result.test, "SMART self-test");
+    assert_eq!(result.status, TestStatus::Fail);
+    assert_eq!(result.metrics["result"], "Completed: read failure");
+    assert_eq!(result.metrics["first_error_lba"], "1953525160");
+
+    let healthy = data("smartctl_sata_hdd.stdout");
+    let result = self_test_result("/dev/sda", SelfTestKind::Short, Local::now(), healthy.self_tests().first());
+    assert_eq!(result.status, TestStatus::Pass);
+    assert!(!result.metrics.contains_key("first_error_lba"));
+
+    let result = self_test_result("/dev/sda", SelfTestKind::Short, Local::now(), None);
+    assert_eq!(result.status, TestStatus::Warn);
+}
+
+#[test]
+fn self_test_result_only_uses_a_new_log_entry() {
+    use self_test::new_entry;
+    let after = data("smartctl_sata_hdd.stdout");
+    // Same log before and after: the drive never ran the test.
+    assert_eq!(new_entry(&after, &after), None);
+
+    let mut before = after.clone();
+    before.ata_smart_self_test_log = None;
+    before.power_on_time = Some(data::PowerOnTime { hours: 10830 });
+    assert_eq!(new_entry(&after, &before).map(|e| e.hours), Some(10830));
+    // An entry logged before the test started is not this test's.
+    before.power_on_time = Some(data::PowerOnTime { hours: 10842 });
+    assert_eq!(new_entry(&after, &before), None);
+    // The ATA log hour count wraps at 16 bits.
+    before.power_on_time = Some(data::PowerOnTime { hours: 65536 + 10830 });
+    assert!(new_entry(&after, &before).is_some());
+}
+
+#[test]
+fn batch_records_keep_unreadable_drives() {
+    use batch::{BatchRecord, BatchRow, RowState};
+    let done = BatchRecord::from_row(&BatchRow {
+        device: "/dev/sdb".to_string(),
+        state: RowState::Done(Box::new(data("smartctl_failing_hdd.stdout"))),
+        delta: None,
+    });
+    assert_eq!(done.grade, "Bad");
+    assert!(!done.reasons.is_empty());
+    assert_eq!(done.error, None);
+
+    let failed = BatchRecord::from_row(&BatchRow {
+        device: "/dev/sdc".to_string(),
+        state: RowState::Error("Failed to run smartctl: not found".to_string()),
+        delta: None,
+    });
+    assert_eq!(failed.device, "/dev/sdc");
+    assert_eq!(failed.error.as_deref(), Some("Failed to run smartctl: not found"));
+}
+
+#[test]
+fn batch_csv_quotes_fields() {
+    use batch::{to_csv, BatchRecord, BatchRow, RowState};
+    let mut record = BatchRecord::from_row(&BatchRow {
+        device: "/dev/sda".to_string(),
+        state: RowState::Error("Bad \"drive\", no reply".to_string()),
+        delta: None,
+    });
+    record.reasons = vec!["a".to_string(), "b".to_string()];
+    let csv = to_csv(&[record]);
+    let mut lines = csv.lines();
+    assert!(lines.next().unwrap().starts_with("device,model,serial"));
+    let row = lines.next().unwrap();
+    assert!(row.starts_with("/dev/sda,"));
+    assert!(row.contains(",a; b,"));
+    assert!(row.ends_with(",\"Bad \"\"drive\"\", no reply\""));
+}
+
+#[test]
+fn batch_export_writes_csv_and_json() {
+    use batch::{export, BatchRecord, BatchRow, RowState};
+    let records: Vec<BatchRecord> = ["smartctl_sata_hdd.stdout", "smartctl_nvme.stdout"]
+        .iter()
+        .map(|fixture| {
+            let data = data(fixture);
+            BatchRecord::from_row(&BatchRow {
+                device: data.device.name.clone(),
+                state: RowState::Done(Box::new(data)),
+                delta: None,
+            })
+        })
+        .collect();
+    let dir = tempfile::tempdir().unwrap();
+    let (csv, json) = export(&records, &dir.path().join("batches")).unwrap();
+
+    assert_eq!(fs::read_to_string(csv).unwrap().lines().count(), 3);
+    let parsed: serde_json::Value = serde_json::from_str(&fs::read_to_string(json).unwrap()).unwrap();
+    assert_eq!(parsed.as_array().unwrap().len(), 2);
+    assert_eq!(parsed[0]["serial"], records[0].serial.as_str());
+}
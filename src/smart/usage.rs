@@ -0,0 +1,267 @@
+use serde::Serialize;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use super::{hotplug, Drive};
+
+pub const PROC: &str = "/proc";
+pub const SYS: &str = "/sys";
+/// Device-mapper stacks deeper than this are not followed.
+const MAX_DEPTH: usize = 8;
+
+/// Why this workstation needs a disk right now.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum Usage {
+    Mounted(String),
+    Swap(String),
+}
+
+impl Usage {
+    pub fn is_root(&self) -> bool {
+        matches!(self, Usage::Mounted(point) if point == "/")
+    }
+}
+
+impl fmt::Display for Usage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Usage::Mounted(point) if point == "/" => write!(f, "holds the running root filesystem"),
+            Usage::Mounted(point) => write!(f, "has a filesystem mounted at {}", point),
+            Usage::Swap(device) => write!(f, "has active swap on {}", device),
+        }
+    }
+}
+
+/// A block device the system is using, before it is tied to a whole disk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InUse {
+    pub device: String,
+    /// `major:minor` of the device, when the kernel reported it.
+    pub dev: Option<String>,
+    pub usage: Usage,
+}
+
+/// Mounts backed by a `/dev` node. Field 3 is the device number and field 5
+/// the mount point; the source comes after the ` - ` separator, following
+/// the filesystem type.
+pub fn parse_mountinfo(text: &str) -> Vec<InUse> {
+    text.lines()
+        .filter_map(|line| {
+            let (left, right) = line.split_once(" - ")?;
+            let mut fields = left.split_whitespace();
+            let dev = fields.nth(2)?;
+            let point = fields.nth(1)?;
+            let source = right.split_whitespace().nth(1)?;
+            source.starts_with("/dev/").then(|| InUse {
+                device: source.to_string(),
+                dev: Some(dev.to_string()),
+                usage: Usage::Mounted(unescape(point)),
+            })
+        })
+        .collect()
+}
+
+/// Swap partitions (zram included). Swap files sit on a mounted filesystem,
+/// which already protects their disk.
+pub fn parse_swaps(text: &str) -> Vec<InUse> {
+    text.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let device = unescape(fields.next()?);
+            (fields.next()? == "partition").then(|| InUse {
+                usage: Usage::Swap(device.clone()),
+                device,
+                dev: None,
+            })
+        })
+        .collect()
+}
+
+/// The kernel writes spaces, tabs, newlines and backslashes in paths as
+/// three-digit octal escapes.
+fn unescape(field: &str) -> String {
+    let bytes = field.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let octal = bytes.get(i + 1..i + 4).and_then(|digits| {
+            let digits = std::str::from_utf8(digits).ok()?;
+            u8::from_str_radix(digits, 8).ok()
+        });
+        match octal {
+            Some(byte) if bytes[i] == b'\\' => {
+                out.push(byte);
+                i += 4;
+            }
+            _ => {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// Everything mounted or swapped on right now, from `self/mountinfo` and
+/// `swaps` under `proc`.
+pub fn read_in_use(proc: &Path) -> io::Result<Vec<InUse>> {
+    let mut in_use = parse_mountinfo(&fs::read_to_string(proc.join("self/mountinfo"))?);
+    match fs::read_to_string(proc.join("swaps")) {
+        Ok(text) => in_use.extend(parse_swaps(&text)),
+        // Kernels built without swap support have no /proc/swaps.
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+    Ok(in_use)
+}
+
+/// The disk `name` is a partition of: `sda2` → `sda`, `nvme0n1p3` → `nvme0n1`.
+pub fn partition_parent<'a>(name: &str, disks: &'a [String]) -> Option<&'a str> {
+    let is_number = |s: &str| !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit());
+    disks
+        .iter()
+        .filter(|disk| match name.strip_prefix(disk.as_str()) {
+            Some(rest) if disk.ends_with(|c: char| c.is_ascii_digit()) => rest.strip_prefix('p').is_some_and(is_number),
+            Some(rest) => is_number(rest),
+            None => false,
+        })
+        .max_by_key(|disk| disk.len())
+        .map(String::as_str)
+}
+
+/// Kernel name of a `/dev` path, e.g. `/dev/mapper/vg-data` → `dm-0`.
+fn kernel_name(device: &str, sys: &Path) -> String {
+    let name = device.strip_prefix("/dev/").unwrap_or(device);
+    if let Some(mapped) = name.strip_prefix("mapper/") {
+        if let Some(dm) = dm_by_name(sys, mapped) {
+            return dm;
+        }
+    }
+    fs::canonicalize(device)
+        .ok()
+        .and_then(|path| path.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| name.to_string())
+}
+
+/// Kernel name of the device behind a mount or swap. The device number is
+/// trusted over the source path, which can be a name like `/dev/root` that
+/// has no node.
+fn in_use_name(item: &InUse, sys: &Path) -> String {
+    item.dev
+        .as_ref()
+        .and_then(|dev| fs::read_link(sys.join("dev/block").join(dev)).ok())
+        .and_then(|target| target.file_name().map(|n| n.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| kernel_name(&item.device, sys))
+}
+
+fn dm_by_name(sys: &Path, mapped: &str) -> Option<String> {
+    fs::read_dir(sys.join("block")).ok()?.flatten().find_map(|entry| {
+        let name = fs::read_to_string(entry.path().join("dm/name")).ok()?;
+        (name.trim() == mapped).then(|| entry.file_name().to_string_lossy().into_owned())
+    })
+}
+
+/// Whole disks under a kernel block device: the disks beneath a
+/// device-mapper or md device, the disk a partition belongs to, or itself.
+/// Stacked devices show up in `/sys/block` too, so their slaves are followed
+/// before a name is taken as a disk.
+pub fn parent_disks(name: &str, disks: &[String], sys: &Path) -> Vec<String> {
+    fn walk(name: &str, disks: &[String], sys: &Path, depth: usize, found: &mut Vec<String>) {
+        let slaves: Vec<String> = fs::read_dir(sys.join("block").join(name).join("slaves"))
+            .map(|dir| dir.flatten().map(|e| e.file_name().to_string_lossy().into_owned()).collect())
+            .unwrap_or_default();
+        if !slaves.is_empty() {
+            if depth < MAX_DEPTH {
+                for slave in &slaves {
+                    walk(slave, disks, sys, depth + 1, found);
+                }
+            }
+        } else if disks.iter().any(|d| d == name) {
+            found.push(name.to_string());
+        } else if let Some(parent) = partition_parent(name, disks) {
+            found.push(parent.to_string());
+        }
+    }
+    let mut found = Vec::new();
+    walk(name, disks, sys, 0, &mut found);
+    found.sort();
+    found.dedup();
+    found
+}
+
+/// Fills in `Drive::system_use` for every drive something in `in_use` lives on.
+pub fn annotate(drives: &mut [Drive], in_use: &[InUse], sys: &Path) {
+    let disks: Vec<String> = drives.iter().map(|d| d.name.clone()).collect();
+    for item in in_use {
+        for disk in parent_disks(&in_use_name(item, sys), &disks, sys) {
+            if let Some(drive) = drives.iter_mut().find(|d| d.name == disk) {
+                if !drive.system_use.contains(&item.usage) {
+                    drive.system_use.push(item.usage.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Why `device` may not be written to, for the operator.
+pub fn refusal(device: &str, uses: &[Usage]) -> String {
+    let reasons: Vec<String> = uses.iter().map(Usage::to_string).collect();
+    let advice = if uses.iter().any(Usage::is_root) {
+        "This is the workstation's system disk"
+    } else {
+        "Unmount it and turn off its swap first"
+    };
+    format!(
+        "Refusing to write to {}: it {}. {}; nothing was written.",
+        device,
+        reasons.join(", "),
+        advice
+    )
+}
+
+/// Checks, right before a destructive operation, that nothing on `device` is
+/// mounted or swapped on. Fails closed when that cannot be determined,
+/// including when the root filesystem cannot be tied to a disk.
+pub fn guard(device: &str) -> Result<(), String> {
+    guard_at(device, Path::new(PROC), Path::new(SYS))
+}
+
+/// `guard` against the given procfs and sysfs roots.
+pub fn guard_at(device: &str, proc: &Path, sys: &Path) -> Result<(), String> {
+    let unknown = |e: io::Error| {
+        format!(
+            "Cannot tell whether {} is in use ({}); refusing to write to it.",
+            device, e
+        )
+    };
+    let in_use = read_in_use(proc).map_err(unknown)?;
+    let disks: Vec<String> = hotplug::block_devices(&sys.join("block"))
+        .map_err(unknown)?
+        .into_iter()
+        .collect();
+    let name = kernel_name(device, sys);
+
+    let mut uses: Vec<Usage> = Vec::new();
+    for item in in_use {
+        let item_name = in_use_name(&item, sys);
+        let parents = parent_disks(&item_name, &disks, sys);
+        if item.usage.is_root() && parents.is_empty() {
+            return Err(format!(
+                "Cannot tell which disk holds the root filesystem ({}); refusing to write to {}.",
+                item.device, device
+            ));
+        }
+        let on_device = item_name == name || parents.contains(&name);
+        if on_device && !uses.contains(&item.usage) {
+            uses.push(item.usage);
+        }
+    }
+    if uses.is_empty() {
+        Ok(())
+    } else {
+        Err(refusal(device, &uses))
+    }
+}
@@ -1,38 +1,67 @@
-use std::process::Command;
-use std::thread;
-use std::time::{Duration, Instant};
+use once_cell::sync::Lazy;
+use std::sync::Mutex;
+use std::time::Instant;
 
-use crate::gpu_detect::{detect_gpu_type, get_selected_gpu, GpuType};
+use chrono::Local;
 
+use crate::command::SystemRunner;
+use crate::gpu_detect::{detect_gpu_type, get_selected_gpu};
+use crate::jobs::{self, JobId};
+use crate::session::{self, TestResult, TestStatus};
+
+static STABILITY_JOB: Lazy<Mutex<Option<JobId>>> = Lazy::new(|| Mutex::new(None));
+
+/// Starts a stability run as a background job unless one is already running.
 pub fn start_stability_test() {
+    let mut job = STABILITY_JOB.lock().unwrap();
+    if job.is_some_and(jobs::is_running) {
+        return;
+    }
+
     let selected_gpu = get_selected_gpu();
-    let gpu_type = detect_gpu_type();
+    let gpu_type = detect_gpu_type(&SystemRunner);
 
-    thread::spawn(move || {
+    *job = Some(jobs::spawn(&format!("GPU stability {}", selected_gpu), move |ctx| async move {
         let start_time = Instant::now();
+        let started = Local::now();
 
-        println!(
-            "[Stability Test] Starting stability test for {:?} GPU: {}",
-            gpu_type, selected_gpu
-        );
+        log::info!("Starting stability test for {:?} GPU: {}", gpu_type, selected_gpu);
 
         // Placeholder command: Replace with actual stability test tool in the future.
-        let result = Command::new("bash")
-            .arg("-c")
-            .arg("sleep 10") // Simulate test duration
-            .output();
+        let result = ctx.run_command("bash", &["-c", "sleep 10"]).await; // Simulate test duration
+
+        let status = match &result {
+            Ok(out) if out.success() => TestStatus::Pass,
+            Ok(_) => TestStatus::Fail,
+            Err(_) => TestStatus::Skipped,
+        };
+        session::record(
+            TestResult::new("GPU stability", &selected_gpu)
+                .started(started)
+                .status(status)
+                .metric("duration_secs", format!("{:.1}", start_time.elapsed().as_secs_f32()))
+                .finish(),
+        );
 
         match result {
-            Ok(_) => {
-                let elapsed = start_time.elapsed();
-                println!(
-                    "[Stability Test] Completed in {:.2?} for GPU: {}",
-                    elapsed, selected_gpu
-                );
+            Ok(out) if out.success() => {
+                log::info!("Completed in {:.2?} for GPU: {}", start_time.elapsed(), selected_gpu);
+                Ok("Stability test passed.".to_string())
+            }
+            Ok(out) => {
+                log::error!("Stability test exited with {:?}: {}", out.status, out.stderr.trim());
+                Err(format!("Stability test exited with code {}", out.status.unwrap_or(-1)))
             }
             Err(e) => {
-                eprintln!("[Stability Test] Failed to run test: {}", e);
+                log::error!("Failed to run stability test: {}", e);
+                Err(e.to_string())
             }
         }
-    });
-}
\ No newline at end of file
+    }));
+}
+
+pub fn cancel_stability_test() {
+    if let Some(id) = *STABILITY_JOB.lock().unwrap() {
+        jobs::cancel(id);
+    }
+}
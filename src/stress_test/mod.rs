@@ -1,45 +1,75 @@
 use std::sync::Mutex;
+use std::time::Duration;
 use once_cell::sync::Lazy;
+use chrono::{DateTime, Local};
 use ratatui::{
     layout::{Constraint, Layout},
     style::{Color, Style},
-    text::{Span},
+    text::Span,
     widgets::{Block, Borders, Gauge, Paragraph},
     Frame,
 };
 
-pub static STRESS_TEST_ACTIVE: Lazy<Mutex<bool>> = Lazy::new(|| Mutex::new(false));
-pub static STRESS_TEST_PROGRESS: Lazy<Mutex<u16>> = Lazy::new(|| Mutex::new(0));
-pub static STRESS_TEST_MESSAGE: Lazy<Mutex<String>> = Lazy::new(|| Mutex::new(String::new()));
+use crate::gpu_detect::get_selected_gpu;
+use crate::jobs::{self, JobId, JobState};
+use crate::session::{self, TestResult, TestStatus};
 
-pub fn start_stress_test() {
-    *STRESS_TEST_ACTIVE.lock().unwrap() = true;
-    *STRESS_TEST_PROGRESS.lock().unwrap() = 0;
-    *STRESS_TEST_MESSAGE.lock().unwrap() = "Running stress test...".to_string();
+const STEP: Duration = Duration::from_millis(50);
 
-    std::thread::spawn(|| {
-        for i in 0..=100 {
-            *STRESS_TEST_PROGRESS.lock().unwrap() = i;
-            std::thread::sleep(std::time::Duration::from_millis(50));
-        }
+static STRESS_JOB: Lazy<Mutex<Option<JobId>>> = Lazy::new(|| Mutex::new(None));
 
-        *STRESS_TEST_MESSAGE.lock().unwrap() = "Stress test completed.".to_string();
-    });
+pub fn stress_result(gpu: &str, started: DateTime<Local>) -> TestResult {
+    let elapsed = (Local::now() - started).num_milliseconds() as f64 / 1000.0;
+    TestResult::new("GPU stress", gpu)
+        .started(started)
+        .status(TestStatus::Pass)
+        .metric("duration_secs", format!("{:.1}", elapsed))
+        .finish()
 }
 
-pub fn check_stress_active() -> bool {
-    *STRESS_TEST_ACTIVE.lock().unwrap()
+/// Runs one stress cycle, reporting progress from 0 to 100.
+pub fn run_stress_cycle(mut on_progress: impl FnMut(u16)) {
+    for i in 0..=100 {
+        on_progress(i);
+        std::thread::sleep(STEP);
+    }
 }
 
-pub fn stop_stress_test() {
-    *STRESS_TEST_ACTIVE.lock().unwrap() = false;
-    *STRESS_TEST_PROGRESS.lock().unwrap() = 0;
-    *STRESS_TEST_MESSAGE.lock().unwrap() = String::new();
+/// Starts a stress run as a background job unless one is already running.
+pub fn start_stress_test() {
+    let mut job = STRESS_JOB.lock().unwrap();
+    if job.is_some_and(jobs::is_running) {
+        return;
+    }
+
+    let gpu = get_selected_gpu();
+    *job = Some(jobs::spawn(&format!("GPU stress {}", gpu), |ctx| async move {
+        let started = Local::now();
+        for i in 0..=100 {
+            ctx.progress(i);
+            if let Err(e) = ctx.sleep(STEP).await {
+                session::record(
+                    TestResult::new("GPU stress", &gpu)
+                        .started(started)
+                        .raw_output(e.to_string())
+                        .finish(),
+                );
+                return Err(e.to_string());
+            }
+        }
+        session::record(stress_result(&gpu, started));
+        Ok("Stress test completed.".to_string())
+    }));
+}
+
+pub fn cancel_stress_test() {
+    if let Some(id) = *STRESS_JOB.lock().unwrap() {
+        jobs::cancel(id);
+    }
 }
 
 pub fn draw_stress_test_popup(f: &mut Frame) {
-    let progress = *STRESS_TEST_PROGRESS.lock().unwrap();
-    let message = STRESS_TEST_MESSAGE.lock().unwrap();
+    let job = STRESS_JOB.lock().unwrap().and_then(jobs::get);
     let area = f.area();
 
     let chunks = Layout::default()
@@ -47,14 +77,23 @@ pub fn draw_stress_test_popup(f: &mut Frame) {
         .margin(2)
         .split(area);
 
+    let (progress, message) = match &job {
+        Some(j) if j.state == JobState::Running => (
+            j.progress.unwrap_or(0),
+            "Running stress test... Press 'c' to cancel, Esc to leave it running.".to_string(),
+        ),
+        Some(j) => (j.progress.unwrap_or(0), j.summary.clone()),
+        None => (0, String::new()),
+    };
+
     let gauge = Gauge::default()
         .block(Block::default().title("Stress Test Progress").borders(Borders::ALL))
         .gauge_style(Style::default().fg(Color::Green).bg(Color::Black))
         .percent(progress);
 
-    let paragraph = Paragraph::new(Span::raw(message.as_str()))
+    let paragraph = Paragraph::new(Span::raw(message))
         .block(Block::default().title("Status").borders(Borders::ALL));
 
     f.render_widget(gauge, chunks[0]);
     f.render_widget(paragraph, chunks[1]);
-}
\ No newline at end of file
+}
@@ -0,0 +1,525 @@
+use chrono::{DateTime, Local};
+use once_cell::sync::Lazy;
+use ratatui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Style},
+    text::{Line, Span},
+    widgets::{Gauge, Paragraph, Wrap},
+    Frame,
+};
+use std::fs::File;
+use std::io::{self, Seek, SeekFrom};
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::FileExt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::config::{self, SurfaceConfig};
+use crate::jobs::{self, cancelled_error, JobId};
+use crate::session::{self, TestResult, TestStatus};
+use crate::smart::format_capacity;
+use crate::theme::bordered_block;
+
+/// Bad sectors are reported as 512-byte LBAs, as smartctl does.
+pub const LBA_SIZE: u64 = 512;
+/// Stop listing bad LBAs past this; a drive this far gone has failed anyway.
+pub const MAX_BAD_LBAS: usize = 10_000;
+
+/// Something the scan can read by offset: a block device, an image file,
+/// or in tests an image with injected faults.
+pub trait Surface {
+    fn size(&self) -> u64;
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()>;
+}
+
+/// A drive or image opened read-only. Each block is dropped from the page
+/// cache after it is read, so a rescan measures the media again.
+pub struct DeviceSurface {
+    file: File,
+    size: u64,
+}
+
+impl DeviceSurface {
+    pub fn open(path: &str) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let size = file.seek(SeekFrom::End(0))?;
+        // Turn readahead off so each timed read covers only its own block;
+        // prefetching the next one would hide a slow area. Advisory only,
+        // like the wipe's cache drop.
+        unsafe {
+            libc::posix_fadvise(file.as_raw_fd(), 0, 0, libc::POSIX_FADV_RANDOM);
+        }
+        Ok(Self { file, size })
+    }
+}
+
+impl Surface for DeviceSurface {
+    fn size(&self) -> u64 {
+        self.size
+    }
+
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        let read = self.file.read_exact_at(buf, offset);
+        unsafe {
+            libc::posix_fadvise(
+                self.file.as_raw_fd(),
+                offset as libc::off_t,
+                buf.len() as libc::off_t,
+                libc::POSIX_FADV_DONTNEED,
+            );
+        }
+        read
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BlockState {
+    /// Not read yet. Ordered so the worst state in a map cell wins.
+    #[default]
+    Pending,
+    Good,
+    Slow,
+    Bad,
+}
+
+impl BlockState {
+    pub fn color(&self) -> Color {
+        match self {
+            BlockState::Pending => Color::DarkGray,
+            BlockState::Good => Color::Green,
+            BlockState::Slow => Color::Yellow,
+            BlockState::Bad => Color::Red,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Block {
+    pub state: BlockState,
+    /// Time to read the whole block, or to retry it sector by sector when it failed.
+    pub latency_us: u32,
+}
+
+impl Block {
+    pub fn latency(&self) -> Duration {
+        Duration::from_micros(self.latency_us as u64)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ScanReport {
+    pub device: String,
+    pub size_bytes: u64,
+    pub block_size: u64,
+    pub slow_ms: u64,
+    pub started_at: DateTime<Local>,
+    pub finished_at: DateTime<Local>,
+    pub blocks: Vec<Block>,
+    pub bad_lbas: Vec<u64>,
+}
+
+impl ScanReport {
+    pub fn count(&self, state: BlockState) -> usize {
+        self.blocks.iter().filter(|b| b.state == state).count()
+    }
+
+    pub fn max_latency(&self) -> Duration {
+        self.blocks.iter().map(Block::latency).max().unwrap_or_default()
+    }
+
+    /// Byte ranges of slow blocks, merged where they touch.
+    pub fn slow_regions(&self) -> Vec<(u64, u64)> {
+        let mut regions: Vec<(u64, u64)> = Vec::new();
+        for (i, block) in self.blocks.iter().enumerate() {
+            if block.state != BlockState::Slow {
+                continue;
+            }
+            let start = i as u64 * self.block_size;
+            let end = (start + self.block_size).min(self.size_bytes);
+            match regions.last_mut() {
+                Some(last) if last.1 == start => last.1 = end,
+                _ => regions.push((start, end)),
+            }
+        }
+        regions
+    }
+
+    pub fn summary(&self) -> String {
+        format!(
+            "{} blocks read: {} slow, {} unreadable, {}, slowest {} ms",
+            self.blocks.len(),
+            self.count(BlockState::Slow),
+            self.count(BlockState::Bad),
+            bad_lba_text(self.bad_lbas.len()),
+            self.max_latency().as_millis()
+        )
+    }
+}
+
+/// `n bad LBAs`, saying so when the list reached `MAX_BAD_LBAS` and stopped.
+pub fn bad_lba_text(count: usize) -> String {
+    if count >= MAX_BAD_LBAS {
+        format!("{}+ bad LBAs (list truncated)", count)
+    } else {
+        format!("{} bad LBAs", count)
+    }
+}
+
+fn block_count(size: u64, block_size: u64) -> usize {
+    size.div_ceil(block_size) as usize
+}
+
+/// Reads a failed block one LBA at a time and returns the LBAs that fail.
+fn find_bad_lbas(surface: &dyn Surface, offset: u64, len: u64, cancelled: &dyn Fn() -> bool) -> io::Result<Vec<u64>> {
+    let mut bad = Vec::new();
+    let mut sector = [0u8; LBA_SIZE as usize];
+    let mut at = offset;
+    while at < offset + len {
+        let n = (offset + len - at).min(LBA_SIZE) as usize;
+        if surface.read_at(&mut sector[..n], at).is_err() {
+            bad.push(at / LBA_SIZE);
+        }
+        at += n as u64;
+        if cancelled() {
+            return Err(cancelled_error());
+        }
+    }
+    Ok(bad)
+}
+
+/// Reads the whole surface in `block_size` blocks and never writes.
+/// `on_block` gets each block with its bad LBAs as it is read; `cancelled`
+/// is checked between blocks and between retried sectors.
+pub fn scan(
+    surface: &dyn Surface,
+    device: &str,
+    settings: &SurfaceConfig,
+    on_block: &mut dyn FnMut(usize, &Block, &[u64]),
+    cancelled: &dyn Fn() -> bool,
+) -> io::Result<ScanReport> {
+    let started_at = Local::now();
+    let size = surface.size();
+    let block_size = settings.block_size.max(LBA_SIZE) / LBA_SIZE * LBA_SIZE;
+    let slow = Duration::from_millis(settings.slow_ms);
+    let mut buf = vec![0u8; block_size as usize];
+    let mut blocks = Vec::with_capacity(block_count(size, block_size));
+    let mut bad_lbas = Vec::new();
+
+    for index in 0..block_count(size, block_size) {
+        let offset = index as u64 * block_size;
+        let len = (size - offset).min(block_size);
+        let started = Instant::now();
+        let (state, bad) = match surface.read_at(&mut buf[..len as usize], offset) {
+            Ok(()) if started.elapsed() >= slow => (BlockState::Slow, Vec::new()),
+            Ok(()) => (BlockState::Good, Vec::new()),
+            Err(e) => {
+                log::warn!("{}: read error at byte {}: {}", device, offset, e);
+                let bad = find_bad_lbas(surface, offset, len, cancelled)?;
+                (BlockState::Bad, bad)
+            }
+        };
+        let block = Block {
+            state,
+            latency_us: started.elapsed().as_micros().min(u32::MAX as u128) as u32,
+        };
+        let room = MAX_BAD_LBAS.saturating_sub(bad_lbas.len());
+        bad_lbas.extend(bad.iter().take(room));
+        blocks.push(block);
+        on_block(index, &block, &bad);
+        if cancelled() {
+            return Err(cancelled_error());
+        }
+    }
+
+    Ok(ScanReport {
+        device: device.to_string(),
+        size_bytes: size,
+        block_size,
+        slow_ms: settings.slow_ms,
+        started_at,
+        finished_at: Local::now(),
+        blocks,
+        bad_lbas,
+    })
+}
+
+/// Folds the blocks into `cells` map cells; each cell shows its worst block.
+pub fn map_cells(blocks: &[BlockState], total: usize, cells: usize) -> Vec<BlockState> {
+    if total == 0 || cells == 0 {
+        return Vec::new();
+    }
+    let cells = cells.min(total);
+    (0..cells)
+        .map(|cell| {
+            let start = cell * total / cells;
+            let end = ((cell + 1) * total / cells).max(start + 1);
+            blocks
+                .get(start..end.min(blocks.len()))
+                .and_then(|range| range.iter().max().copied())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+/// Unreadable blocks fail the drive; slow ones are a warning.
+pub fn scan_result(report: &ScanReport) -> TestResult {
+    let status = if report.count(BlockState::Bad) > 0 {
+        TestStatus::Fail
+    } else if report.count(BlockState::Slow) > 0 {
+        TestStatus::Warn
+    } else {
+        TestStatus::Pass
+    };
+    let seconds = (report.finished_at - report.started_at).num_milliseconds().max(1) as f64 / 1000.0;
+    let mut raw = format!("{}\n", report.summary());
+    for (start, end) in report.slow_regions() {
+        raw.push_str(&format!("slow: bytes {}-{}\n", start, end));
+    }
+    for lba in &report.bad_lbas {
+        raw.push_str(&format!("bad LBA: {}\n", lba));
+    }
+    let result = TestResult::new("Surface scan", &report.device)
+        .started(report.started_at)
+        .status(status)
+        .metric("blocks", report.blocks.len())
+        .metric("block_size", report.block_size)
+        .metric("slow_blocks", report.count(BlockState::Slow))
+        .metric("bad_blocks", report.count(BlockState::Bad))
+        .metric("bad_lbas", report.bad_lbas.len())
+        .metric("slow_ms", report.slow_ms)
+        .metric("max_latency_ms", report.max_latency().as_millis())
+        .metric("mb_per_sec", format!("{:.1}", report.size_bytes as f64 / 1e6 / seconds))
+        .raw_output(raw);
+    match report.bad_lbas.first() {
+        Some(lba) => result.metric("first_bad_lba", lba).finish(),
+        None => result.finish(),
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct ScanState {
+    device: String,
+    size_bytes: u64,
+    total: usize,
+    blocks: Vec<BlockState>,
+    bad_lbas: Vec<u64>,
+    slowest: Duration,
+    summary: Option<String>,
+    error: Option<String>,
+}
+
+static SCAN_JOB: Lazy<Mutex<Option<JobId>>> = Lazy::new(|| Mutex::new(None));
+static SCAN_STATE: Lazy<Mutex<ScanState>> = Lazy::new(|| Mutex::new(ScanState::default()));
+
+pub fn is_running() -> bool {
+    SCAN_JOB.lock().unwrap().is_some_and(jobs::is_running)
+}
+
+/// Starts a read-only scan of `device` unless one is already running.
+pub fn start_scan(device: &str) {
+    if is_running() {
+        log::warn!("A surface scan is already running");
+        return;
+    }
+    let settings = config::get().surface.clone();
+    let surface = match DeviceSurface::open(device) {
+        Ok(surface) => surface,
+        Err(e) => {
+            *SCAN_STATE.lock().unwrap() = ScanState {
+                device: device.to_string(),
+                error: Some(format!("Cannot open {}: {}", device, e)),
+                ..ScanState::default()
+            };
+            return;
+        }
+    };
+    *SCAN_STATE.lock().unwrap() = ScanState {
+        device: device.to_string(),
+        size_bytes: surface.size(),
+        total: block_count(surface.size(), settings.block_size.max(LBA_SIZE) / LBA_SIZE * LBA_SIZE),
+        ..ScanState::default()
+    };
+    log::info!("Surface scan of {} started", device);
+
+    let device = device.to_string();
+    let id = jobs::spawn(&format!("Surface scan {}", device), move |ctx| async move {
+        let token = ctx.token();
+        let job = ctx.clone();
+        let name = device.clone();
+        let outcome = tokio::task::spawn_blocking(move || {
+            let mut on_block = |index: usize, block: &Block, bad: &[u64]| {
+                let mut state = SCAN_STATE.lock().unwrap();
+                state.blocks.push(block.state);
+                let room = MAX_BAD_LBAS.saturating_sub(state.bad_lbas.len());
+                state.bad_lbas.extend(bad.iter().take(room));
+                state.slowest = state.slowest.max(block.latency());
+                if let Some(percent) = ((index + 1) * 100).checked_div(state.total) {
+                    job.progress(percent as u16);
+                }
+            };
+            scan(&surface, &name, &settings, &mut on_block, &|| token.is_cancelled())
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+
+        match outcome {
+            Ok(report) => {
+                let summary = report.summary();
+                log::info!("Surface scan of {}: {}", device, summary);
+                let result = scan_result(&report);
+                let passed = result.status != TestStatus::Fail;
+                session::record(result);
+                SCAN_STATE.lock().unwrap().summary = Some(summary.clone());
+                if passed {
+                    Ok(summary)
+                } else {
+                    Err(summary)
+                }
+            }
+            Err(e) => {
+                let message = format!("Surface scan of {} stopped: {}", device, e);
+                log::warn!("{}", message);
+                SCAN_STATE.lock().unwrap().error = Some(message.clone());
+                Err(message)
+            }
+        }
+    });
+    *SCAN_JOB.lock().unwrap() = Some(id);
+}
+
+pub fn cancel_scan() {
+    if let Some(id) = *SCAN_JOB.lock().unwrap() {
+        jobs::cancel(id);
+    }
+}
+
+/// What one frame of the scan screen needs, read from `SCAN_STATE` without
+/// copying its per-block list, which has millions of entries on large drives.
+struct ScanView {
+    device: String,
+    size_bytes: u64,
+    total: usize,
+    done: usize,
+    good: usize,
+    slow: usize,
+    bad: usize,
+    /// The first few bad LBAs, and how many there are in all.
+    bad_lbas: Vec<u64>,
+    bad_lba_count: usize,
+    slowest: Duration,
+    summary: Option<String>,
+    error: Option<String>,
+    cells: Vec<BlockState>,
+}
+
+impl ScanView {
+    fn take(state: &ScanState, cells: usize) -> Self {
+        let count = |wanted: BlockState| state.blocks.iter().filter(|b| **b == wanted).count();
+        Self {
+            device: state.device.clone(),
+            size_bytes: state.size_bytes,
+            total: state.total,
+            done: state.blocks.len(),
+            good: count(BlockState::Good),
+            slow: count(BlockState::Slow),
+            bad: count(BlockState::Bad),
+            bad_lbas: state.bad_lbas.iter().take(8).copied().collect(),
+            bad_lba_count: state.bad_lbas.len(),
+            slowest: state.slowest,
+            summary: state.summary.clone(),
+            error: state.error.clone(),
+            cells: map_cells(&state.blocks, state.total, cells),
+        }
+    }
+}
+
+fn draw_map(f: &mut Frame, block: ratatui::widgets::Block, area: Rect, cells: &[BlockState]) {
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    let width = inner.width as usize;
+    let lines: Vec<Line> = cells
+        .chunks(width.max(1))
+        .map(|row| {
+            Line::from(
+                row.iter()
+                    .map(|cell| Span::styled("█", Style::default().fg(cell.color())))
+                    .collect::<Vec<_>>(),
+            )
+        })
+        .collect();
+    f.render_widget(Paragraph::new(lines), inner);
+}
+
+pub fn draw_surface_scan(f: &mut Frame) {
+    let slow_ms = config::get().surface.slow_ms;
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([
+            Constraint::Length(3),
+            Constraint::Min(6),
+            Constraint::Length(3),
+            Constraint::Length(6),
+        ])
+        .split(f.area());
+    let map = bordered_block("Surface map");
+    let inner = map.inner(chunks[1]);
+    let cells = inner.width as usize * inner.height as usize;
+    let state = ScanView::take(&SCAN_STATE.lock().unwrap(), cells);
+
+    let header = Paragraph::new(format!(
+        "{} ({}), read only",
+        state.device,
+        format_capacity(state.size_bytes)
+    ))
+    .block(bordered_block("Surface Scan"));
+    f.render_widget(header, chunks[0]);
+
+    draw_map(f, map, chunks[1], &state.cells);
+
+    let done = state.done;
+    let percent = (done * 100).checked_div(state.total).unwrap_or(0) as u16;
+    let gauge = Gauge::default()
+        .block(bordered_block("Progress"))
+        .gauge_style(Style::default().fg(Color::Green).bg(Color::Black))
+        .percent(percent.min(100))
+        .label(format!("{} of {} blocks ({}%)", done, state.total, percent));
+    f.render_widget(gauge, chunks[2]);
+
+    let legend = Line::from(vec![
+        Span::styled("█", Style::default().fg(BlockState::Good.color())),
+        Span::raw(format!(" ok {}  ", state.good)),
+        Span::styled("█", Style::default().fg(BlockState::Slow.color())),
+        Span::raw(format!(" slow (≥{} ms) {}  ", slow_ms, state.slow)),
+        Span::styled("█", Style::default().fg(BlockState::Bad.color())),
+        Span::raw(format!(" unreadable {}  ", state.bad)),
+        Span::raw(format!("slowest {} ms", state.slowest.as_millis())),
+    ]);
+    let (status, color) = match (&state.error, &state.summary) {
+        (Some(e), _) => (e.clone(), Color::Red),
+        (None, Some(summary)) if state.bad > 0 => (summary.clone(), Color::Red),
+        (None, Some(summary)) => (summary.clone(), Color::Green),
+        (None, None) => ("Scanning...".to_string(), Color::Cyan),
+    };
+    let mut bad = state.bad_lbas.iter().map(u64::to_string).collect::<Vec<_>>().join(", ");
+    if state.bad_lba_count > state.bad_lbas.len() {
+        bad.push_str(&format!(" and {} more", state.bad_lba_count - state.bad_lbas.len()));
+    }
+    if state.bad_lba_count >= MAX_BAD_LBAS {
+        bad.push_str(&format!(" (list stopped at {})", MAX_BAD_LBAS));
+    }
+    let result = Paragraph::new(vec![
+        legend,
+        Line::from(Span::styled(status, Style::default().fg(color))),
+        Line::from(format!("Bad LBAs: {}", if bad.is_empty() { "none" } else { &bad })),
+        Line::from("c: cancel, Esc: back (the scan keeps running), j: jobs"),
+    ])
+    .block(bordered_block("Result"))
+    .wrap(Wrap { trim: true });
+    f.render_widget(result, chunks[3]);
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,163 @@
+use super::*;
+use std::ops::Range;
+use std::thread;
+
+/// A sparse image file with unreadable and slow byte ranges.
+struct FaultyImage {
+    inner: DeviceSurface,
+    bad: Vec<Range<u64>>,
+    slow: Vec<Range<u64>>,
+    delay: Duration,
+}
+
+impl FaultyImage {
+    fn new(dir: &tempfile::TempDir, size: u64) -> Self {
+        let path = dir.path().join("sparse.img");
+        File::create(&path).unwrap().set_len(size).unwrap();
+        Self {
+            inner: DeviceSurface::open(&path.to_string_lossy()).unwrap(),
+            bad: Vec::new(),
+            slow: Vec::new(),
+            delay: Duration::from_millis(40),
+        }
+    }
+}
+
+fn overlaps(ranges: &[Range<u64>], offset: u64, len: u64) -> bool {
+    ranges.iter().any(|r| r.start < offset + len && offset < r.end)
+}
+
+impl Surface for FaultyImage {
+    fn size(&self) -> u64 {
+        self.inner.size()
+    }
+
+    fn read_at(&self, buf: &mut [u8], offset: u64) -> io::Result<()> {
+        if overlaps(&self.bad, offset, buf.len() as u64) {
+            return Err(io::Error::from_raw_os_error(libc::EIO));
+        }
+        if overlaps(&self.slow, offset, buf.len() as u64) {
+            thread::sleep(self.delay);
+        }
+        self.inner.read_at(buf, offset)
+    }
+}
+
+const MIB: u64 = 1 << 20;
+
+fn settings() -> SurfaceConfig {
+    SurfaceConfig {
+        block_size: 64 * 1024,
+        slow_ms: 20,
+    }
+}
+
+fn run(surface: &dyn Surface) -> io::Result<ScanReport> {
+    scan(surface, "sparse.img", &settings(), &mut |_, _, _| {}, &|| false)
+}
+
+#[test]
+fn clean_image_scans_good() {
+    let dir = tempfile::tempdir().unwrap();
+    // Not a whole number of blocks, so the last one is short.
+    let image = FaultyImage::new(&dir, 4 * MIB + 1000);
+    let report = run(&image).unwrap();
+    assert_eq!(report.blocks.len(), 65);
+    assert_eq!(report.count(BlockState::Good), 65);
+    assert!(report.bad_lbas.is_empty());
+    assert_eq!(scan_result(&report).status, TestStatus::Pass);
+}
+
+#[test]
+fn injected_errors_are_reported_as_lbas() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut image = FaultyImage::new(&dir, 4 * MIB);
+    // Three bad sectors in the second block and one at the very end.
+    image.bad = vec![70_000..71_000, 4 * MIB - 512..4 * MIB];
+    let mut seen = Vec::new();
+    let report = scan(
+        &image,
+        "sparse.img",
+        &settings(),
+        &mut |index, block, bad| {
+            if block.state == BlockState::Bad {
+                seen.push((index, bad.to_vec()));
+            }
+        },
+        &|| false,
+    )
+    .unwrap();
+
+    assert_eq!(report.bad_lbas, vec![136, 137, 138, 8191]);
+    assert_eq!(seen, vec![(1, vec![136, 137, 138]), (63, vec![8191])]);
+    assert_eq!(report.count(BlockState::Bad), 2);
+    assert_eq!(report.count(BlockState::Good), 62);
+
+    let result = scan_result(&report);
+    assert_eq!(result.status, TestStatus::Fail);
+    assert_eq!(result.metrics["bad_lbas"], "4");
+    assert_eq!(result.metrics["first_bad_lba"], "136");
+    assert!(result.raw_output.contains("bad LBA: 8191"));
+}
+
+#[test]
+fn summary_says_when_the_bad_lba_list_was_cut_short() {
+    assert_eq!(bad_lba_text(4), "4 bad LBAs");
+    let report = ScanReport {
+        device: "/dev/sdz".to_string(),
+        size_bytes: 0,
+        block_size: MIB,
+        slow_ms: 500,
+        started_at: Local::now(),
+        finished_at: Local::now(),
+        blocks: Vec::new(),
+        bad_lbas: (0..MAX_BAD_LBAS as u64).collect(),
+    };
+    assert!(report.summary().contains("10000+ bad LBAs (list truncated)"));
+}
+
+#[test]
+fn slow_regions_are_flagged_and_merged() {
+    let dir = tempfile::tempdir().unwrap();
+    let mut image = FaultyImage::new(&dir, MIB);
+    image.slow.push(128 * 1024..256 * 1024);
+    let report = run(&image).unwrap();
+
+    assert_eq!(report.count(BlockState::Slow), 2);
+    assert_eq!(report.slow_regions(), vec![(128 * 1024, 256 * 1024)]);
+    assert!(report.max_latency() >= Duration::from_millis(40));
+    let result = scan_result(&report);
+    assert_eq!(result.status, TestStatus::Warn);
+    assert!(result.raw_output.contains("slow: bytes 131072-262144"));
+}
+
+#[test]
+fn cancelling_stops_the_scan() {
+    let dir = tempfile::tempdir().unwrap();
+    let image = FaultyImage::new(&dir, MIB);
+    let mut read = 0;
+    let stop = std::cell::Cell::new(false);
+    let outcome = scan(
+        &image,
+        "sparse.img",
+        &settings(),
+        &mut |index, _, _| {
+            read = index + 1;
+            stop.set(index == 2);
+        },
+        &|| stop.get(),
+    );
+    assert_eq!(outcome.unwrap_err().kind(), io::ErrorKind::Interrupted);
+    assert_eq!(read, 3);
+}
+
+#[test]
+fn map_cells_show_the_worst_block() {
+    use BlockState::*;
+    let blocks = [Good, Good, Slow, Good, Bad, Good, Good];
+    assert_eq!(map_cells(&blocks, 7, 3), vec![Good, Slow, Bad]);
+    // Blocks not read yet stay pending; cells never outnumber blocks.
+    assert_eq!(map_cells(&blocks[..2], 7, 3), vec![Good, Pending, Pending]);
+    assert_eq!(map_cells(&blocks, 7, 100).len(), 7);
+    assert!(map_cells(&[], 0, 10).is_empty());
+}
@@ -5,7 +5,7 @@ use ratatui::{
 };
 
 /// Returns a reusable bordered block with the given title.
-pub fn bordered_block(title: &str) -> Block {
+pub fn bordered_block(title: &str) -> Block<'_> {
     Block::default()
         .title(Span::styled(
             title,
@@ -0,0 +1,218 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::{Pattern, WipeReport};
+use crate::config;
+use crate::report::{escape, STYLE};
+use crate::smart::{format_capacity, SmartData, SmartSummary};
+
+pub const TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%:z";
+
+/// Proof that one drive was wiped. Times are stored as text so the content
+/// hash does not depend on how a reader parses them.
+///
+/// The content hash is unkeyed: it catches accidental corruption, not
+/// forgery, since anyone who edits a certificate can recompute it. A
+/// certificate is authentic only when it matches the copy in the issuing
+/// station's drive history.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Certificate {
+    pub id: String,
+    pub issued_at: String,
+    pub operator: String,
+    pub device: String,
+    pub model: String,
+    pub serial: String,
+    pub capacity_bytes: u64,
+    pub method: String,
+    pub nist_level: String,
+    pub passes: usize,
+    pub patterns: Vec<Pattern>,
+    pub started_at: String,
+    pub finished_at: String,
+    pub verified: bool,
+    pub verification: String,
+    pub note: Option<String>,
+    /// The drive's grade and hours from SMART at the time of the wipe.
+    pub smart_grade: Option<String>,
+    pub smart_power_on_hours: Option<String>,
+    pub tool_version: String,
+    /// SHA-256 of this certificate's JSON with `content_hash` left empty.
+    /// Detects corruption only; see the type docs.
+    pub content_hash: String,
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// The same wipe always gets the same id: its start time plus a digest of
+/// the drive it was run on.
+pub fn certificate_id(report: &WipeReport, serial: &str) -> String {
+    let started = report.started_at.format(TIME_FORMAT).to_string();
+    let digest = sha256_hex(format!("{}|{}|{}", serial, report.device, started).as_bytes());
+    format!(
+        "WC-{}-{}",
+        report.started_at.format("%Y%m%d-%H%M%S"),
+        digest[..8].to_uppercase()
+    )
+}
+
+impl Certificate {
+    /// Fills identity gaps in the wipe report from the drive's SMART data.
+    pub fn issue(report: &WipeReport, smart: Option<&SmartData>, operator: &str, issued_at: &str) -> Self {
+        let summary = smart.map(SmartSummary::from_data);
+        let known = |value: &Option<String>, from_smart: Option<&String>| {
+            value
+                .clone()
+                .or_else(|| from_smart.filter(|v| v.as_str() != "Unknown").cloned())
+                .unwrap_or_else(|| "Unknown".to_string())
+        };
+        let model = known(&report.model, summary.as_ref().map(|s| &s.model));
+        let serial = known(&report.serial, summary.as_ref().map(|s| &s.serial));
+
+        let mut certificate = Certificate {
+            id: certificate_id(report, &serial),
+            issued_at: issued_at.to_string(),
+            operator: operator.to_string(),
+            device: report.device.clone(),
+            model,
+            serial,
+            capacity_bytes: report.size_bytes,
+            method: report.method.label().to_string(),
+            nist_level: report.method.nist_level().to_string(),
+            // A firmware erase is a single pass as far as the drive is concerned.
+            passes: report.passes.len().max(1),
+            patterns: report.passes.clone(),
+            started_at: report.started_at.format(TIME_FORMAT).to_string(),
+            finished_at: report.finished_at.format(TIME_FORMAT).to_string(),
+            verified: report.passed(),
+            verification: report.verification_text(),
+            note: report.note.clone(),
+            smart_grade: summary.as_ref().map(|s| s.health.clone()),
+            smart_power_on_hours: summary.map(|s| s.power_on_hours),
+            tool_version: format!("electronics_tui_tester {}", env!("CARGO_PKG_VERSION")),
+            content_hash: String::new(),
+        };
+        certificate.content_hash = certificate.compute_hash();
+        certificate
+    }
+
+    pub fn compute_hash(&self) -> String {
+        let body = Certificate {
+            content_hash: String::new(),
+            ..self.clone()
+        };
+        sha256_hex(serde_json::to_string(&body).unwrap_or_default().as_bytes())
+    }
+
+    /// True when the hash still matches the content. This rules out accidental
+    /// corruption, not deliberate edits.
+    pub fn verify(&self) -> bool {
+        self.content_hash == self.compute_hash()
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    pub fn render_html(&self) -> String {
+        let (class, result) = if self.verified { ("pass", "VERIFIED") } else { ("fail", "NOT VERIFIED") };
+        let patterns: Vec<&str> = self.patterns.iter().map(Pattern::label).collect();
+        let optional = |value: &Option<String>| escape(value.as_deref().unwrap_or("-"));
+        format!(
+            r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+<meta charset="utf-8">
+<title>Data Sanitization Certificate {id}</title>
+<style>{style}</style>
+</head>
+<body>
+<h1>Data Sanitization Certificate</h1>
+<p class="subtitle">Certificate {id}</p>
+<p class="overall {class}">{result}</p>
+
+<h2>Drive</h2>
+<table>
+<tr><th>Model</th><td>{model}</td></tr>
+<tr><th>Serial number</th><td>{serial}</td></tr>
+<tr><th>Capacity</th><td>{capacity} ({bytes} bytes)</td></tr>
+<tr><th>Device</th><td>{device}</td></tr>
+<tr><th>SMART grade</th><td>{grade}</td></tr>
+<tr><th>Power-on hours</th><td>{hours}</td></tr>
+</table>
+
+<h2>Sanitization</h2>
+<table>
+<tr><th>Method</th><td>{method}</td></tr>
+<tr><th>NIST SP 800-88 level</th><td>{nist}</td></tr>
+<tr><th>Passes</th><td>{passes} {patterns}</td></tr>
+<tr><th>Started</th><td>{started}</td></tr>
+<tr><th>Finished</th><td>{finished}</td></tr>
+<tr><th>Verification</th><td class="{class}">{verification}</td></tr>
+<tr><th>Notes</th><td>{note}</td></tr>
+<tr><th>Operator</th><td>{operator}</td></tr>
+<tr><th>Issued</th><td>{issued}</td></tr>
+</table>
+
+<footer>Content hash (SHA-256): {hash}<br>
+The hash detects accidental corruption only; authenticity is confirmed against the issuing station's drive history.<br>
+Check with <code>electronics_tui_tester verify-certificate {id}.json</code> on that station. Issued by {tool}.</footer>
+</body>
+</html>
+"#,
+            id = escape(&self.id),
+            style = STYLE,
+            class = class,
+            result = result,
+            model = escape(&self.model),
+            serial = escape(&self.serial),
+            capacity = format_capacity(self.capacity_bytes),
+            bytes = self.capacity_bytes,
+            device = escape(&self.device),
+            grade = optional(&self.smart_grade),
+            hours = optional(&self.smart_power_on_hours),
+            method = escape(&self.method),
+            nist = escape(&self.nist_level),
+            passes = self.passes,
+            patterns = if patterns.is_empty() { String::new() } else { format!("({})", patterns.join(", ")) },
+            started = escape(&self.started_at),
+            finished = escape(&self.finished_at),
+            verification = escape(&self.verification),
+            note = optional(&self.note),
+            operator = escape(&self.operator),
+            issued = escape(&self.issued_at),
+            hash = self.content_hash,
+            tool = escape(&self.tool_version),
+        )
+    }
+}
+
+/// `$XDG_DATA_HOME/electronics_tui_tester/certificates`, unless the config says otherwise.
+pub fn certificate_dir() -> PathBuf {
+    if let Some(dir) = &config::get().wipe.certificate_dir {
+        return dir.clone();
+    }
+    dirs::data_local_dir()
+        .map(|dir| dir.join("electronics_tui_tester").join("certificates"))
+        .unwrap_or_else(|| PathBuf::from("certificates"))
+}
+
+/// Writes `<id>.html` and `<id>.json` into `dir` and returns both paths.
+pub fn save(certificate: &Certificate, dir: &Path) -> io::Result<(PathBuf, PathBuf)> {
+    fs::create_dir_all(dir)?;
+    let html = dir.join(format!("{}.html", certificate.id));
+    let json = dir.join(format!("{}.json", certificate.id));
+    fs::write(&html, certificate.render_html())?;
+    fs::write(&json, certificate.to_json())?;
+    Ok((html, json))
+}
+
+pub fn load(path: &Path) -> Result<Certificate, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&text).map_err(|e| format!("Invalid certificate {}: {}", path.display(), e))
+}
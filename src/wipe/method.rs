@@ -0,0 +1,282 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::command::CommandRunner;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WipeMethod {
+    AtaSecureErase,
+    AtaEnhancedSecureErase,
+    NvmeSanitizeCrypto,
+    NvmeFormatCrypto,
+    Overwrite,
+}
+
+impl WipeMethod {
+    pub fn label(&self) -> &'static str {
+        match self {
+            WipeMethod::AtaSecureErase => "ATA Secure Erase",
+            WipeMethod::AtaEnhancedSecureErase => "ATA Enhanced Secure Erase",
+            WipeMethod::NvmeSanitizeCrypto => "NVMe Sanitize (crypto erase)",
+            WipeMethod::NvmeFormatCrypto => "NVMe Format (crypto erase)",
+            WipeMethod::Overwrite => "Overwrite",
+        }
+    }
+
+    /// The NIST SP 800-88 sanitization level the method reaches.
+    pub fn nist_level(&self) -> &'static str {
+        match self {
+            WipeMethod::Overwrite => "Clear",
+            _ => "Purge",
+        }
+    }
+
+    /// Runs inside the drive; the host cannot stop it once started.
+    pub fn is_firmware(&self) -> bool {
+        *self != WipeMethod::Overwrite
+    }
+}
+
+/// The Security section of `hdparm -I`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AtaSecurity {
+    pub supported: bool,
+    pub enabled: bool,
+    pub locked: bool,
+    pub frozen: bool,
+    pub enhanced_supported: bool,
+    pub erase_minutes: Option<u64>,
+    pub enhanced_minutes: Option<u64>,
+}
+
+/// What `hdparm -I` or `nvme id-ctrl` says about the drive.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DriveIdentity {
+    pub model: Option<String>,
+    pub serial: Option<String>,
+}
+
+fn identify_field(output: &str, key: &str) -> Option<String> {
+    output
+        .lines()
+        .find_map(|line| line.trim().strip_prefix(key))
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+pub fn parse_hdparm_identity(output: &str) -> DriveIdentity {
+    DriveIdentity {
+        model: identify_field(output, "Model Number:"),
+        serial: identify_field(output, "Serial Number:"),
+    }
+}
+
+/// `None` when the drive has no Security section, e.g. behind most USB bridges.
+pub fn parse_hdparm_security(output: &str) -> Option<AtaSecurity> {
+    let mut lines = output.lines().skip_while(|line| !line.starts_with("Security:"));
+    lines.next()?;
+
+    let mut security = AtaSecurity::default();
+    for line in lines.take_while(|line| line.starts_with('\t')) {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let (negated, rest) = match words.split_first() {
+            Some((&"not", rest)) => (true, rest.join(" ")),
+            _ => (false, words.join(" ")),
+        };
+        match rest.as_str() {
+            "supported" => security.supported = !negated,
+            "enabled" => security.enabled = !negated,
+            "locked" => security.locked = !negated,
+            "frozen" => security.frozen = !negated,
+            "supported: enhanced erase" => security.enhanced_supported = !negated,
+            _ if rest.contains("SECURITY ERASE UNIT") => {
+                for part in rest.split(". ") {
+                    let minutes = part.split("min").next().and_then(|m| m.trim().parse().ok());
+                    if part.contains("ENHANCED") {
+                        security.enhanced_minutes = minutes;
+                    } else {
+                        security.erase_minutes = minutes;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Some(security)
+}
+
+/// Sanitize and format capabilities from `nvme id-ctrl -o json`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NvmeCaps {
+    pub identity: DriveIdentity,
+    /// SANICAP bit 0.
+    pub sanitize_crypto: bool,
+    /// FNA bit 2: Format NVM supports a cryptographic secure erase.
+    pub format_crypto: bool,
+}
+
+pub fn parse_nvme_id_ctrl(json: &str) -> Result<NvmeCaps, String> {
+    let value: Value = serde_json::from_str(json).map_err(|e| format!("Invalid nvme id-ctrl JSON: {}", e))?;
+    let number = |key: &str| value.get(key).and_then(Value::as_u64).unwrap_or(0);
+    let text = |key: &str| {
+        value
+            .get(key)
+            .and_then(Value::as_str)
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+    };
+    Ok(NvmeCaps {
+        identity: DriveIdentity {
+            model: text("mn"),
+            serial: text("sn"),
+        },
+        sanitize_crypto: number("sanicap") & 0x1 != 0,
+        format_crypto: number("fna") & 0x4 != 0,
+    })
+}
+
+/// Picks the strongest method the drive supports, with a note when it had
+/// to fall back to overwriting.
+pub fn choose_method(ata: Option<&AtaSecurity>, nvme: Option<&NvmeCaps>) -> (WipeMethod, Option<String>) {
+    if let Some(caps) = nvme {
+        return if caps.sanitize_crypto {
+            (WipeMethod::NvmeSanitizeCrypto, None)
+        } else if caps.format_crypto {
+            (WipeMethod::NvmeFormatCrypto, None)
+        } else {
+            (WipeMethod::Overwrite, Some("Drive supports neither crypto sanitize nor crypto format".to_string()))
+        };
+    }
+    let Some(security) = ata else {
+        return (WipeMethod::Overwrite, None);
+    };
+    let unavailable = if !security.supported {
+        "Drive does not support the ATA security feature set"
+    } else if security.frozen {
+        "Drive security is frozen; suspend and resume the machine or re-plug the drive to unfreeze it"
+    } else if security.locked || security.enabled {
+        "Drive already has a security password set"
+    } else if security.enhanced_supported {
+        return (WipeMethod::AtaEnhancedSecureErase, None);
+    } else {
+        return (WipeMethod::AtaSecureErase, None);
+    };
+    (WipeMethod::Overwrite, Some(format!("Secure erase unavailable: {}", unavailable)))
+}
+
+/// The device as the wipe screen and report describe it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Detected {
+    pub identity: DriveIdentity,
+    pub method: WipeMethod,
+    pub note: Option<String>,
+    /// How long the drive says its firmware erase takes.
+    pub estimate: Option<Duration>,
+}
+
+/// Asks the drive what it supports. Image files, loop devices and anything
+/// the tools cannot identify are overwritten.
+pub fn detect(runner: &dyn CommandRunner, device: &str) -> Detected {
+    let name = Path::new(device).file_name().and_then(|n| n.to_str()).unwrap_or_default();
+
+    if device.starts_with("/dev/nvme") {
+        return match runner.run("nvme", &["id-ctrl", device, "-o", "json"]) {
+            Ok(output) if output.success() => match parse_nvme_id_ctrl(&output.stdout) {
+                Ok(caps) => {
+                    let (method, note) = choose_method(None, Some(&caps));
+                    Detected {
+                        identity: caps.identity,
+                        method,
+                        note,
+                        estimate: None,
+                    }
+                }
+                Err(e) => overwrite_with(format!("Could not identify the drive: {}", e)),
+            },
+            Ok(output) => overwrite_with(format!("nvme id-ctrl exited with {:?}", output.status)),
+            Err(e) => overwrite_with(format!("Failed to run nvme: {}", e)),
+        };
+    }
+
+    if device.starts_with("/dev/") && !name.starts_with("loop") {
+        return match runner.run("hdparm", &["-I", device]) {
+            Ok(output) => {
+                let security = parse_hdparm_security(&output.stdout);
+                let (method, note) = choose_method(security.as_ref(), None);
+                let minutes = security.and_then(|s| match method {
+                    WipeMethod::AtaEnhancedSecureErase => s.enhanced_minutes,
+                    WipeMethod::AtaSecureErase => s.erase_minutes,
+                    _ => None,
+                });
+                Detected {
+                    identity: parse_hdparm_identity(&output.stdout),
+                    method,
+                    note,
+                    estimate: minutes.map(|m| Duration::from_secs(m * 60)),
+                }
+            }
+            Err(e) => overwrite_with(format!("Failed to run hdparm: {}", e)),
+        };
+    }
+
+    Detected {
+        identity: DriveIdentity::default(),
+        method: WipeMethod::Overwrite,
+        note: None,
+        estimate: None,
+    }
+}
+
+fn overwrite_with(note: String) -> Detected {
+    Detected {
+        identity: DriveIdentity::default(),
+        method: WipeMethod::Overwrite,
+        note: Some(note),
+        estimate: None,
+    }
+}
+
+/// `sstat` bits 2:0 from the sanitize status log.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SanitizeState {
+    Idle,
+    Done,
+    Running { percent: u16 },
+    Failed,
+}
+
+/// The log itself, which `nvme sanitize-log -o json` nests under the controller name.
+fn sanitize_log(value: &Value) -> &Value {
+    value
+        .as_object()
+        .and_then(|controllers| controllers.values().next())
+        .filter(|log| log.get("sstat").is_some())
+        .unwrap_or(value)
+}
+
+/// Reads `nvme sanitize-log -o json`.
+pub fn parse_sanitize_log(json: &str) -> Result<SanitizeState, String> {
+    let value: Value = serde_json::from_str(json).map_err(|e| format!("Invalid sanitize log JSON: {}", e))?;
+    let log = sanitize_log(&value);
+    let sstat = log.get("sstat").and_then(Value::as_u64).ok_or("Sanitize log has no sstat")?;
+    let sprog = log.get("sprog").and_then(Value::as_u64).unwrap_or(0);
+    Ok(match sstat & 0x7 {
+        0 => SanitizeState::Idle,
+        1 | 4 => SanitizeState::Done,
+        2 => SanitizeState::Running {
+            percent: (sprog * 100 / 65536) as u16,
+        },
+        _ => SanitizeState::Failed,
+    })
+}
+
+/// How long the drive says a crypto erase sanitize takes; all ones means it
+/// gave no estimate.
+pub fn parse_sanitize_estimate(json: &str) -> Option<Duration> {
+    let value: Value = serde_json::from_str(json).ok()?;
+    let seconds = sanitize_log(&value).get("time_crypto_erase")?.as_u64()?;
+    (seconds != 0 && seconds != u32::MAX as u64).then(|| Duration::from_secs(seconds))
+}
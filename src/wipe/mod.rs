@@ -0,0 +1,669 @@
+use chrono::{DateTime, Local};
+use crossterm::event::KeyCode;
+use once_cell::sync::Lazy;
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span, Text},
+    widgets::{Gauge, Paragraph, Wrap},
+    Frame,
+};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::app::{Screen, Transition};
+use crate::confirm::{Confirmation, Outcome};
+use crate::command::{CommandOutput, CommandRunner, SystemRunner};
+use crate::config::{self, WipeConfig};
+use crate::history;
+use crate::jobs::{self, JobId};
+use crate::session::{self, TestResult, TestStatus};
+use crate::smart::{self, format_capacity};
+use crate::theme::{bordered_block, info_box};
+
+pub mod certificate;
+pub mod method;
+pub mod overwrite;
+
+pub use method::WipeMethod;
+pub use overwrite::Pattern;
+
+use certificate::Certificate;
+use method::{DriveIdentity, SanitizeState};
+use overwrite::Expected;
+
+/// Temporary password for ATA Secure Erase; the erase clears it again.
+const ATA_PASSWORD: &str = "wipe";
+const SANITIZE_POLL: Duration = Duration::from_secs(2);
+/// Longest wait for a sanitize, whatever the drive estimates.
+const SANITIZE_MAX_WAIT: Duration = Duration::from_secs(12 * 3600);
+/// Shortest wait, for drives that estimate a few seconds.
+const SANITIZE_MIN_WAIT: Duration = Duration::from_secs(10 * 60);
+/// A sanitize still idle after this many polls was never started by the drive.
+const SANITIZE_IDLE_POLLS: u32 = 5;
+/// Share of the gauge for the erase itself; verification gets the rest.
+const ERASE_SHARE: f64 = 90.0;
+
+/// What will be done to a device, shown before the operator confirms.
+#[derive(Debug, Clone)]
+pub struct WipePlan {
+    pub device: String,
+    pub identity: DriveIdentity,
+    pub size_bytes: u64,
+    pub method: WipeMethod,
+    /// Overwrite passes; empty for firmware erases.
+    pub passes: Vec<Pattern>,
+    /// Why a weaker method was chosen, if it was.
+    pub note: Option<String>,
+    pub estimate: Option<Duration>,
+}
+
+impl WipePlan {
+    pub fn new(runner: &dyn CommandRunner, device: &str, config: &WipeConfig) -> Result<Self, String> {
+        smart::usage::guard(device)?;
+        let mut file = File::open(device).map_err(|e| format!("Cannot open {}: {}", device, e))?;
+        let size_bytes = overwrite::target_len(&mut file).map_err(|e| format!("Cannot size {}: {}", device, e))?;
+        let detected = method::detect(runner, device);
+        Ok(Self {
+            device: device.to_string(),
+            identity: detected.identity,
+            size_bytes,
+            method: detected.method,
+            passes: match detected.method {
+                WipeMethod::Overwrite => config.overwrite_passes.clone(),
+                _ => Vec::new(),
+            },
+            note: detected.note,
+            estimate: detected.estimate,
+        })
+    }
+
+    /// What the operator must type: the serial number, or the device name
+    /// when the drive did not report one.
+    pub fn confirmation(&self) -> String {
+        self.identity.serial.clone().unwrap_or_else(|| {
+            Path::new(&self.device)
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_else(|| self.device.clone())
+        })
+    }
+
+    pub fn method_text(&self) -> String {
+        match self.method {
+            WipeMethod::Overwrite => {
+                let passes: Vec<&str> = self.passes.iter().map(Pattern::label).collect();
+                format!("{} ({}), NIST {}", self.method.label(), passes.join(", "), self.method.nist_level())
+            }
+            method => format!("{}, NIST {}", method.label(), method.nist_level()),
+        }
+    }
+}
+
+/// The outcome of one wipe, kept for the session and the certificate.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WipeReport {
+    pub device: String,
+    pub model: Option<String>,
+    pub serial: Option<String>,
+    pub size_bytes: u64,
+    pub method: WipeMethod,
+    pub passes: Vec<Pattern>,
+    pub note: Option<String>,
+    pub started_at: DateTime<Local>,
+    pub finished_at: DateTime<Local>,
+    pub sampled: usize,
+    /// Byte offsets of sampled sectors that were not wiped.
+    pub mismatches: Vec<u64>,
+}
+
+impl WipeReport {
+    pub fn passed(&self) -> bool {
+        self.sampled > 0 && self.mismatches.is_empty()
+    }
+
+    pub fn verification_text(&self) -> String {
+        match self.mismatches.first() {
+            None => format!("All {} sampled sectors verified wiped", self.sampled),
+            Some(first) => format!(
+                "{} of {} sampled sectors still hold data (first at byte {})",
+                self.mismatches.len(),
+                self.sampled,
+                first
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Progress {
+    pub phase: String,
+    pub percent: u16,
+}
+
+fn report_progress(progress: &mut dyn FnMut(&Progress) -> bool, phase: &str, percent: f64) -> bool {
+    progress(&Progress {
+        phase: phase.to_string(),
+        percent: percent.clamp(0.0, 100.0) as u16,
+    })
+}
+
+fn command_error(what: &str, output: &CommandOutput) -> String {
+    let detail = [output.stderr.trim(), output.stdout.trim()]
+        .into_iter()
+        .find(|s| !s.is_empty())
+        .unwrap_or("no output");
+    format!("{} failed with {:?}: {}", what, output.status, detail)
+}
+
+fn run_checked(runner: &dyn CommandRunner, program: &str, args: &[&str]) -> Result<CommandOutput, String> {
+    let output = runner
+        .run(program, args)
+        .map_err(|e| format!("Failed to run {}: {}", program, e))?;
+    if output.success() {
+        Ok(output)
+    } else {
+        Err(command_error(&format!("{} {}", program, args.first().unwrap_or(&"")), &output))
+    }
+}
+
+/// Runs a firmware erase command, moving the gauge on the drive's own
+/// estimate while it works. Firmware erases cannot be cancelled.
+fn run_timed(
+    runner: &dyn CommandRunner,
+    program: &str,
+    args: &[&str],
+    phase: &str,
+    estimate: Option<Duration>,
+    progress: &mut dyn FnMut(&Progress) -> bool,
+) -> Result<CommandOutput, String> {
+    thread::scope(|scope| {
+        let command = scope.spawn(|| run_checked(runner, program, args));
+        let started = Instant::now();
+        while !command.is_finished() {
+            let percent = estimate
+                .filter(|e| !e.is_zero())
+                .map(|e| started.elapsed().as_secs_f64() / e.as_secs_f64() * ERASE_SHARE)
+                .unwrap_or(0.0);
+            report_progress(progress, phase, percent.min(ERASE_SHARE - 1.0));
+            thread::sleep(Duration::from_millis(500));
+        }
+        command
+            .join()
+            .unwrap_or_else(|_| Err(format!("{} crashed", program)))
+    })
+}
+
+fn ata_erase(
+    runner: &dyn CommandRunner,
+    plan: &WipePlan,
+    progress: &mut dyn FnMut(&Progress) -> bool,
+) -> Result<(), String> {
+    let erase = match plan.method {
+        WipeMethod::AtaEnhancedSecureErase => "--security-erase-enhanced",
+        _ => "--security-erase",
+    };
+    let device = plan.device.as_str();
+    run_checked(runner, "hdparm", &["--user-master", "u", "--security-set-pass", ATA_PASSWORD, device])?;
+    let erased = run_timed(
+        runner,
+        "hdparm",
+        &["--user-master", "u", erase, ATA_PASSWORD, device],
+        plan.method.label(),
+        plan.estimate,
+        progress,
+    );
+    if let Err(e) = erased {
+        // Leave the drive usable rather than locked with our password.
+        if let Err(disable) =
+            run_checked(runner, "hdparm", &["--user-master", "u", "--security-disable", ATA_PASSWORD, device])
+        {
+            log::error!("Could not clear the security password on {}: {}", device, disable);
+        }
+        return Err(e);
+    }
+    Ok(())
+}
+
+fn nvme_sanitize(
+    runner: &dyn CommandRunner,
+    plan: &WipePlan,
+    progress: &mut dyn FnMut(&Progress) -> bool,
+) -> Result<(), String> {
+    // SANACT 4: cryptographic erase.
+    run_checked(runner, "nvme", &["sanitize", &plan.device, "--sanact=4"])?;
+    wait_for_sanitize(runner, plan, SANITIZE_POLL, SANITIZE_MAX_WAIT, progress)
+}
+
+/// Polls the sanitize log until the drive reports the sanitize finished.
+/// Gives up at four times the drive's estimate (within `SANITIZE_MIN_WAIT`
+/// and `max_wait`), when the drive never leaves Idle, or when `progress`
+/// asks to stop. The drive keeps sanitizing in the background regardless.
+fn wait_for_sanitize(
+    runner: &dyn CommandRunner,
+    plan: &WipePlan,
+    poll: Duration,
+    max_wait: Duration,
+    progress: &mut dyn FnMut(&Progress) -> bool,
+) -> Result<(), String> {
+    let device = plan.device.as_str();
+    let started = Instant::now();
+    let mut deadline = max_wait;
+    let mut idle_polls = 0;
+    loop {
+        let log = run_checked(runner, "nvme", &["sanitize-log", device, "-o", "json"])?;
+        if let Some(estimate) = method::parse_sanitize_estimate(&log.stdout) {
+            deadline = (estimate * 4).max(SANITIZE_MIN_WAIT).min(max_wait);
+        }
+        let percent = match method::parse_sanitize_log(&log.stdout)? {
+            SanitizeState::Done => return Ok(()),
+            SanitizeState::Failed => return Err("The drive reported the sanitize as failed".to_string()),
+            SanitizeState::Running { percent } => {
+                idle_polls = 0;
+                percent as f64 * ERASE_SHARE / 100.0
+            }
+            SanitizeState::Idle => {
+                idle_polls += 1;
+                if idle_polls >= SANITIZE_IDLE_POLLS {
+                    return Err(format!(
+                        "{} never started the sanitize; its log stayed idle for {} polls",
+                        device, idle_polls
+                    ));
+                }
+                0.0
+            }
+        };
+        if !report_progress(progress, plan.method.label(), percent) {
+            return Err(format!(
+                "Stopped waiting for the sanitize of {}; the drive finishes it on its own",
+                device
+            ));
+        }
+        if started.elapsed() >= deadline {
+            return Err(format!(
+                "Sanitize of {} did not finish within {} min",
+                device,
+                deadline.as_secs() / 60
+            ));
+        }
+        thread::sleep(poll);
+    }
+}
+
+fn overwrite_passes(
+    file: &mut File,
+    plan: &WipePlan,
+    seed: u64,
+    progress: &mut dyn FnMut(&Progress) -> bool,
+) -> io::Result<()> {
+    let total = plan.passes.len() as f64 * plan.size_bytes.max(1) as f64;
+    for (i, pattern) in plan.passes.iter().enumerate() {
+        let phase = format!("Pass {}/{}: {}", i + 1, plan.passes.len(), pattern.label());
+        let before = i as f64 * plan.size_bytes as f64;
+        overwrite::write_pass(file, plan.size_bytes, *pattern, seed, &mut |written| {
+            report_progress(progress, &phase, (before + written as f64) / total * ERASE_SHARE)
+        })?;
+        file.sync_all()?;
+    }
+    Ok(())
+}
+
+/// Wipes the planned device and verifies it by reading back `samples`
+/// sectors. `progress` returns false to cancel an overwrite. Firmware erases
+/// ignore it once started: the drive finishes regardless, and stopping early
+/// would only lose the report and certificate for an erase that happened.
+pub fn execute(
+    runner: &dyn CommandRunner,
+    plan: &WipePlan,
+    samples: usize,
+    progress: &mut dyn FnMut(&Progress) -> bool,
+) -> Result<WipeReport, String> {
+    let started_at = Local::now();
+    let seed = started_at.timestamp_nanos_opt().unwrap_or_default() as u64;
+    let io_error = |e: io::Error| format!("{}: {}", plan.device, e);
+
+    let mut file = OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(&plan.device)
+        .map_err(|e| format!("Cannot open {} for writing: {}", plan.device, e))?;
+    let offsets = overwrite::sample_offsets(plan.size_bytes, samples, seed);
+
+    let expected = match plan.method {
+        WipeMethod::Overwrite => {
+            let last = *plan.passes.last().ok_or("No overwrite passes configured")?;
+            overwrite_passes(&mut file, plan, seed, progress).map_err(io_error)?;
+            Expected::Pattern(last, seed)
+        }
+        method => {
+            report_progress(progress, "Sampling sectors before the erase", 0.0);
+            let before = offsets
+                .iter()
+                .map(|&offset| overwrite::read_sample(&mut file, offset).map(|data| (offset, data)))
+                .collect::<io::Result<Vec<_>>>()
+                .map_err(io_error)?;
+            report_progress(progress, method.label(), 0.0);
+            let progress = &mut |p: &Progress| {
+                progress(p);
+                true
+            };
+            match method {
+                WipeMethod::NvmeSanitizeCrypto => nvme_sanitize(runner, plan, progress)?,
+                WipeMethod::NvmeFormatCrypto => {
+                    run_timed(
+                        runner,
+                        "nvme",
+                        &["format", &plan.device, "--ses=2", "--force"],
+                        method.label(),
+                        plan.estimate,
+                        progress,
+                    )?;
+                }
+                _ => ata_erase(runner, plan, progress)?,
+            }
+            Expected::Changed(before)
+        }
+    };
+
+    overwrite::sync_and_drop_cache(&file).map_err(io_error)?;
+    let count = offsets.len().max(1) as f64;
+    let firmware = plan.method.is_firmware();
+    let mismatches = overwrite::verify(&mut file, &offsets, &expected, &mut |done| {
+        report_progress(progress, "Verifying", ERASE_SHARE + done as f64 / count * (100.0 - ERASE_SHARE)) || firmware
+    })
+    .map_err(io_error)?;
+
+    Ok(WipeReport {
+        device: plan.device.clone(),
+        model: plan.identity.model.clone(),
+        serial: plan.identity.serial.clone(),
+        size_bytes: plan.size_bytes,
+        method: plan.method,
+        passes: plan.passes.clone(),
+        note: plan.note.clone(),
+        started_at,
+        finished_at: Local::now(),
+        sampled: offsets.len(),
+        mismatches,
+    })
+}
+
+/// A wipe passes only when every sampled sector verified.
+pub fn wipe_result(report: &WipeReport) -> TestResult {
+    let passes: Vec<&str> = report.passes.iter().map(Pattern::label).collect();
+    let result = TestResult::new("Wipe", &report.device)
+        .started(report.started_at)
+        .status(if report.passed() { TestStatus::Pass } else { TestStatus::Fail })
+        .metric("method", report.method.label())
+        .metric("nist_level", report.method.nist_level())
+        .metric("passes", passes.join(", "))
+        .metric("size_bytes", report.size_bytes)
+        .metric("samples", report.sampled)
+        .metric("mismatches", report.mismatches.len())
+        .metric("serial", report.serial.as_deref().unwrap_or("Unknown"))
+        .metric("model", report.model.as_deref().unwrap_or("Unknown"))
+        .raw_output(serde_json::to_string_pretty(report).unwrap_or_default());
+    match report.mismatches.first() {
+        Some(offset) => result.metric("first_mismatch", offset).finish(),
+        None => result.finish(),
+    }
+}
+
+/// Issues the certificate for a finished wipe, saves it as HTML and JSON
+/// and stores it in the drive history next to a fresh SMART snapshot.
+/// Returns the certificate and the HTML path.
+pub fn certify(report: &WipeReport, operator: &str) -> Result<(Certificate, PathBuf), String> {
+    let smart = match report.device.starts_with("/dev/") {
+        true => smart::read_smart(&SystemRunner, &report.device).ok(),
+        false => None,
+    };
+    if let Some(data) = &smart {
+        smart::record_reading(&report.device, data);
+    }
+
+    let issued_at = Local::now().format(certificate::TIME_FORMAT).to_string();
+    let certificate = Certificate::issue(report, smart.as_ref(), operator, &issued_at);
+    let (html, _) = certificate::save(&certificate, &certificate::certificate_dir())
+        .map_err(|e| format!("Failed to save certificate {}: {}", certificate.id, e))?;
+    match history::get() {
+        Some(history) if certificate.serial != "Unknown" => history.add_certificate(&certificate)?,
+        _ => log::warn!("Certificate {} was not stored in the drive history", certificate.id),
+    }
+    log::info!("Wipe certificate {} saved to {}", certificate.id, html.display());
+    Ok((certificate, html))
+}
+
+#[derive(Debug, Clone, Default)]
+struct WipeState {
+    plan: Option<WipePlan>,
+    progress: Option<Progress>,
+    report: Option<WipeReport>,
+    /// Where the certificate went, or why it could not be issued.
+    certificate: Option<String>,
+    error: Option<String>,
+}
+
+/// The plan waiting for typed confirmation, or why one could not be made.
+static CONFIRM: Confirmation<WipePlan> = Confirmation::new();
+static WIPE_JOB: Lazy<Mutex<Option<JobId>>> = Lazy::new(|| Mutex::new(None));
+static WIPE_STATE: Lazy<Mutex<WipeState>> = Lazy::new(|| Mutex::new(WipeState::default()));
+
+pub fn is_running() -> bool {
+    WIPE_JOB.lock().unwrap().is_some_and(jobs::is_running)
+}
+
+/// Works out the plan for `device` and waits for the operator to confirm it.
+pub fn enter_wipe_confirm(device: &str) {
+    CONFIRM.open(WipePlan::new(&SystemRunner, device, &config::get().wipe));
+}
+
+pub fn exit_wipe_confirm() {
+    CONFIRM.close();
+}
+
+pub fn confirm_key(key: KeyCode) -> Transition {
+    let plan = match CONFIRM.handle_key(key, WipePlan::confirmation, "That does not match. Nothing was erased.") {
+        Outcome::Confirmed(plan) => plan,
+        Outcome::Done(transition) => return transition,
+    };
+    if is_running() {
+        CONFIRM.set_message("Another wipe is still running.");
+        return Transition::Stay;
+    }
+    // Something may have been mounted while the operator was typing.
+    if let Err(e) = smart::usage::guard(&plan.device) {
+        CONFIRM.set_message(&e);
+        return Transition::Stay;
+    }
+    start_wipe(plan);
+    Transition::Replace(Screen::Wipe)
+}
+
+fn start_wipe(plan: WipePlan) {
+    log::warn!("Wiping {} with {}", plan.device, plan.method_text());
+    *WIPE_STATE.lock().unwrap() = WipeState {
+        plan: Some(plan.clone()),
+        ..WipeState::default()
+    };
+    let samples = config::get().wipe.verify_samples;
+    let name = format!("Wipe {} ({})", plan.device, plan.method.label());
+
+    let id = jobs::spawn(&name, move |ctx| async move {
+        let token = ctx.token();
+        let job = ctx.clone();
+        let device = plan.device.clone();
+        let outcome = tokio::task::spawn_blocking(move || {
+            execute(&SystemRunner, &plan, samples, &mut |progress| {
+                job.progress(progress.percent);
+                WIPE_STATE.lock().unwrap().progress = Some(progress.clone());
+                !token.is_cancelled()
+            })
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+
+        match outcome {
+            Ok(report) => {
+                let text = report.verification_text();
+                log::info!("Wipe of {}: {}", device, text);
+                ctx.output(text.clone());
+
+                let operator = session::current().operator;
+                let certified = {
+                    let report = report.clone();
+                    tokio::task::spawn_blocking(move || certify(&report, &operator))
+                        .await
+                        .map_err(|e| e.to_string())?
+                };
+                let (result, certificate) = match certified {
+                    Ok((certificate, html)) => (
+                        wipe_result(&report).metric("certificate", &certificate.id),
+                        format!("Certificate {} saved to {}", certificate.id, html.display()),
+                    ),
+                    Err(e) => {
+                        log::error!("{}", e);
+                        (wipe_result(&report), e)
+                    }
+                };
+                ctx.output(certificate.clone());
+                session::record(result);
+
+                let passed = report.passed();
+                let mut state = WIPE_STATE.lock().unwrap();
+                state.report = Some(report);
+                state.certificate = Some(certificate);
+                if passed {
+                    Ok(text)
+                } else {
+                    Err(text)
+                }
+            }
+            Err(e) => {
+                log::error!("Wipe of {} failed: {}", device, e);
+                session::record(
+                    TestResult::new("Wipe", &device)
+                        .status(TestStatus::Fail)
+                        .raw_output(e.clone())
+                        .finish(),
+                );
+                WIPE_STATE.lock().unwrap().error = Some(e.clone());
+                Err(e)
+            }
+        }
+    });
+    *WIPE_JOB.lock().unwrap() = Some(id);
+}
+
+/// Stops an overwrite between chunks. Does nothing for a firmware erase,
+/// which runs to the end and still gets its report and certificate.
+pub fn cancel_wipe() {
+    let firmware = WIPE_STATE
+        .lock()
+        .unwrap()
+        .plan
+        .as_ref()
+        .is_some_and(|p| p.method.is_firmware());
+    if firmware {
+        log::warn!("A firmware erase cannot be cancelled");
+        return;
+    }
+    if let Some(id) = *WIPE_JOB.lock().unwrap() {
+        jobs::cancel(id);
+    }
+}
+
+fn plan_lines(plan: &WipePlan) -> Vec<Line<'_>> {
+    let mut lines = vec![
+        info_box("Device", &plan.device),
+        Line::from(format!("Model: {}", plan.identity.model.as_deref().unwrap_or("Unknown"))),
+        Line::from(format!("Serial: {}", plan.identity.serial.as_deref().unwrap_or("Unknown"))),
+        Line::from(format!("Size: {}", format_capacity(plan.size_bytes))),
+        Line::from(format!("Method: {}", plan.method_text())),
+    ];
+    if let Some(estimate) = plan.estimate {
+        lines.push(Line::from(format!("Drive estimate: {} min", estimate.as_secs() / 60)));
+    }
+    if let Some(note) = &plan.note {
+        lines.push(Line::from(Span::styled(note.clone(), Style::default().fg(Color::Yellow))));
+    }
+    lines
+}
+
+pub fn draw_wipe_confirm(f: &mut Frame) {
+    let Some((plan, area)) = CONFIRM.draw(f, "Wipe Drive", 8, "erase", WipePlan::confirmation) else {
+        return;
+    };
+    let mut lines = plan_lines(&plan);
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        "ALL DATA ON THIS DRIVE WILL BE DESTROYED",
+        Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+    )));
+    let details = Paragraph::new(Text::from(lines))
+        .block(bordered_block("Wipe Drive"))
+        .wrap(Wrap { trim: true });
+    f.render_widget(details, area);
+}
+
+pub fn draw_wipe(f: &mut Frame) {
+    let state = WIPE_STATE.lock().unwrap().clone();
+    let job = WIPE_JOB.lock().unwrap().and_then(jobs::get);
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([Constraint::Min(7), Constraint::Length(3), Constraint::Length(6)])
+        .split(f.area());
+
+    let details = match &state.plan {
+        Some(plan) => Paragraph::new(Text::from(plan_lines(plan))),
+        None => Paragraph::new("No wipe started."),
+    }
+    .block(bordered_block("Wipe"))
+    .wrap(Wrap { trim: true });
+    f.render_widget(details, chunks[0]);
+
+    let progress = state.progress.clone().unwrap_or(Progress {
+        phase: "Starting".to_string(),
+        percent: 0,
+    });
+    let gauge = Gauge::default()
+        .block(bordered_block("Progress"))
+        .gauge_style(Style::default().fg(Color::Green).bg(Color::Black))
+        .percent(progress.percent.min(100))
+        .label(format!("{} ({}%)", progress.phase, progress.percent));
+    f.render_widget(gauge, chunks[1]);
+
+    let firmware = state.plan.as_ref().is_some_and(|p| p.method.is_firmware());
+    let (status, color) = match (&state.report, &state.error, &job) {
+        (Some(report), _, _) if report.passed() => (report.verification_text(), Color::Green),
+        (Some(report), _, _) => (report.verification_text(), Color::Red),
+        (None, Some(e), _) => (e.clone(), Color::Red),
+        (None, None, Some(job)) if job.state == jobs::JobState::Running && firmware => (
+            "The drive is erasing itself; this cannot be stopped. Do not power it off.".to_string(),
+            Color::Yellow,
+        ),
+        _ => ("Running...".to_string(), Color::Cyan),
+    };
+    let help = if firmware {
+        "Esc: back (the wipe keeps running), j: jobs"
+    } else {
+        "c: cancel, Esc: back (the wipe keeps running), j: jobs"
+    };
+    let result = Paragraph::new(vec![
+        Line::from(Span::styled(status, Style::default().fg(color).add_modifier(Modifier::BOLD))),
+        Line::from(state.certificate.clone().unwrap_or_default()),
+        Line::from(help),
+    ])
+    .block(bordered_block("Result"))
+    .wrap(Wrap { trim: true });
+    f.render_widget(result, chunks[2]);
+}
+
+#[cfg(test)]
+mod tests;
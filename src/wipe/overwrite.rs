@@ -0,0 +1,158 @@
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::os::fd::AsRawFd;
+
+use crate::jobs::cancelled_error;
+
+/// Bytes written per `write` call.
+const CHUNK: usize = 1 << 20;
+/// Bytes read per verification sample.
+pub const SAMPLE_SIZE: u64 = 4096;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Pattern {
+    Zeros,
+    Ones,
+    /// Pseudo-random, reproducible from the seed so it can be verified.
+    Random,
+}
+
+impl Pattern {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Pattern::Zeros => "zeros",
+            Pattern::Ones => "ones",
+            Pattern::Random => "random",
+        }
+    }
+}
+
+pub fn splitmix64(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// Fills `buf` with what `pattern` puts at byte `offset`, which must be 8-byte aligned.
+pub fn fill(buf: &mut [u8], offset: u64, pattern: Pattern, seed: u64) {
+    match pattern {
+        Pattern::Zeros => buf.fill(0),
+        Pattern::Ones => buf.fill(0xff),
+        Pattern::Random => {
+            for (i, word) in buf.chunks_mut(8).enumerate() {
+                let value = splitmix64(seed ^ (offset / 8 + i as u64)).to_le_bytes();
+                word.copy_from_slice(&value[..word.len()]);
+            }
+        }
+    }
+}
+
+/// Size of a block device or image file.
+pub fn target_len(file: &mut impl Seek) -> io::Result<u64> {
+    file.seek(SeekFrom::End(0))
+}
+
+/// Writes one pass over the first `len` bytes. `progress` gets the bytes
+/// written so far and returns false to stop.
+pub fn write_pass<F: Write + Seek>(
+    file: &mut F,
+    len: u64,
+    pattern: Pattern,
+    seed: u64,
+    progress: &mut dyn FnMut(u64) -> bool,
+) -> io::Result<()> {
+    let mut buf = vec![0u8; CHUNK];
+    file.seek(SeekFrom::Start(0))?;
+    let mut written = 0u64;
+    while written < len {
+        let n = (len - written).min(CHUNK as u64) as usize;
+        fill(&mut buf[..n], written, pattern, seed);
+        file.write_all(&buf[..n])?;
+        written += n as u64;
+        if !progress(written) {
+            return Err(cancelled_error());
+        }
+    }
+    file.flush()
+}
+
+/// Flushes writes to the device and drops them from the page cache, so
+/// verification reads the media rather than memory.
+pub fn sync_and_drop_cache(file: &File) -> io::Result<()> {
+    file.sync_all()?;
+    // Advisory only; a failure just means reads may be served from cache.
+    unsafe {
+        libc::posix_fadvise(file.as_raw_fd(), 0, 0, libc::POSIX_FADV_DONTNEED);
+    }
+    Ok(())
+}
+
+/// Where to sample: the first and last sectors plus `count - 2` more spread
+/// over the device with a seeded jitter. Offsets are `SAMPLE_SIZE` aligned.
+pub fn sample_offsets(len: u64, count: usize, seed: u64) -> Vec<u64> {
+    let sectors = len / SAMPLE_SIZE;
+    if sectors == 0 || count == 0 {
+        return Vec::new();
+    }
+    let count = (count as u64).min(sectors);
+    let mut offsets = vec![0, (sectors - 1) * SAMPLE_SIZE];
+    let stride = sectors / count;
+    for i in 1..count.saturating_sub(1) {
+        let jitter = if stride > 1 { splitmix64(seed ^ i) % stride } else { 0 };
+        offsets.push((i * stride + jitter).min(sectors - 1) * SAMPLE_SIZE);
+    }
+    offsets.sort_unstable();
+    offsets.dedup();
+    offsets
+}
+
+pub fn read_sample<F: Read + Seek>(file: &mut F, offset: u64) -> io::Result<Vec<u8>> {
+    let mut buf = vec![0u8; SAMPLE_SIZE as usize];
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+/// What a sampled sector must hold after the wipe.
+#[derive(Debug, Clone)]
+pub enum Expected {
+    /// The last overwrite pass.
+    Pattern(Pattern, u64),
+    /// A firmware erase leaves zeros or ciphertext under a discarded key, so
+    /// a sector passes if it is zero or no longer matches what it held before.
+    Changed(Vec<(u64, Vec<u8>)>),
+}
+
+/// Offsets of sampled sectors that still hold the wrong data.
+pub fn verify<F: Read + Seek>(
+    file: &mut F,
+    offsets: &[u64],
+    expected: &Expected,
+    progress: &mut dyn FnMut(u64) -> bool,
+) -> io::Result<Vec<u64>> {
+    let mut mismatches = Vec::new();
+    let mut want = vec![0u8; SAMPLE_SIZE as usize];
+    for (i, &offset) in offsets.iter().enumerate() {
+        let got = read_sample(file, offset)?;
+        let ok = match expected {
+            Expected::Pattern(pattern, seed) => {
+                fill(&mut want, offset, *pattern, *seed);
+                got == want
+            }
+            Expected::Changed(before) => match before.iter().find(|(o, _)| *o == offset) {
+                Some((_, old)) => got.iter().all(|b| *b == 0) || got != *old,
+                None => true,
+            },
+        };
+        if !ok {
+            mismatches.push(offset);
+        }
+        if !progress(i as u64 + 1) {
+            return Err(cancelled_error());
+        }
+    }
+    Ok(mismatches)
+}
@@ -0,0 +1,408 @@
+use super::method::*;
+use super::overwrite::*;
+use super::*;
+use crate::command::FixtureRunner;
+use std::fs;
+use std::io::{Seek, Write};
+use std::path::PathBuf;
+
+/// An image file that looks like a used drive: non-zero everywhere, and not
+/// a whole number of write chunks.
+fn used_image(dir: &tempfile::TempDir) -> PathBuf {
+    let path = dir.path().join("disk.img");
+    let data: Vec<u8> = (0..(3 << 20) + 8192).map(|i| (i % 251 + 1) as u8).collect();
+    fs::write(&path, data).unwrap();
+    path
+}
+
+fn plan_for(path: &Path, method: WipeMethod, passes: Vec<Pattern>) -> WipePlan {
+    WipePlan {
+        device: path.to_string_lossy().into_owned(),
+        identity: DriveIdentity::default(),
+        size_bytes: fs::metadata(path).unwrap().len(),
+        method,
+        passes,
+        note: None,
+        estimate: None,
+    }
+}
+
+fn run(runner: &FixtureRunner, plan: &WipePlan, samples: usize) -> Result<WipeReport, String> {
+    execute(runner, plan, samples, &mut |_| true)
+}
+
+#[test]
+fn parses_hdparm_security_section() {
+    let output = FixtureRunner::fixture("hdparm_identify_ssd.stdout");
+    let security = parse_hdparm_security(&output).unwrap();
+    assert_eq!(
+        security,
+        AtaSecurity {
+            supported: true,
+            enabled: false,
+            locked: false,
+            frozen: false,
+            enhanced_supported: true,
+            erase_minutes: Some(2),
+            enhanced_minutes: Some(8),
+        }
+    );
+    let identity = parse_hdparm_identity(&output);
+    assert_eq!(identity.model.as_deref(), Some("Samsung SSD 860 EVO 500GB"));
+    assert_eq!(identity.serial.as_deref(), Some("S3Z2NB0K123456A"));
+
+    let frozen = parse_hdparm_security(&FixtureRunner::fixture("hdparm_identify_frozen.stdout")).unwrap();
+    assert!(frozen.frozen);
+    assert!(!frozen.enhanced_supported);
+    assert_eq!(frozen.erase_minutes, Some(118));
+
+    assert_eq!(parse_hdparm_security(&FixtureRunner::fixture("hdparm_identify_usb.stdout")), None);
+}
+
+#[test]
+fn chooses_the_strongest_supported_method() {
+    let ssd = parse_hdparm_security(&FixtureRunner::fixture("hdparm_identify_ssd.stdout"));
+    assert_eq!(choose_method(ssd.as_ref(), None), (WipeMethod::AtaEnhancedSecureErase, None));
+
+    let frozen = parse_hdparm_security(&FixtureRunner::fixture("hdparm_identify_frozen.stdout"));
+    let (method, note) = choose_method(frozen.as_ref(), None);
+    assert_eq!(method, WipeMethod::Overwrite);
+    assert!(note.unwrap().contains("frozen"));
+
+    assert_eq!(choose_method(None, None), (WipeMethod::Overwrite, None));
+
+    let plain = parse_nvme_id_ctrl(&FixtureRunner::fixture("nvme_id_ctrl.stdout")).unwrap();
+    assert_eq!(plain.identity.serial.as_deref(), Some("S4EWNX0R123456"));
+    assert_eq!(choose_method(None, Some(&plain)).0, WipeMethod::Overwrite);
+
+    let sanitize = parse_nvme_id_ctrl(&FixtureRunner::fixture("nvme_id_ctrl_sanitize.stdout")).unwrap();
+    assert!(sanitize.sanitize_crypto && sanitize.format_crypto);
+    assert_eq!(choose_method(None, Some(&sanitize)), (WipeMethod::NvmeSanitizeCrypto, None));
+
+    let format_only = NvmeCaps {
+        sanitize_crypto: false,
+        ..sanitize
+    };
+    assert_eq!(choose_method(None, Some(&format_only)).0, WipeMethod::NvmeFormatCrypto);
+}
+
+#[test]
+fn detect_asks_the_right_tool() {
+    let runner = FixtureRunner::new()
+        .with("hdparm -I /dev/sdb", "hdparm_identify_ssd")
+        .with("nvme id-ctrl /dev/nvme0n1 -o json", "nvme_id_ctrl_sanitize");
+
+    let sata = detect(&runner, "/dev/sdb");
+    assert_eq!(sata.method, WipeMethod::AtaEnhancedSecureErase);
+    assert_eq!(sata.estimate, Some(Duration::from_secs(8 * 60)));
+
+    let nvme = detect(&runner, "/dev/nvme0n1");
+    assert_eq!(nvme.method, WipeMethod::NvmeSanitizeCrypto);
+    assert_eq!(nvme.identity.model.as_deref(), Some("Samsung SSD 980 PRO 2TB"));
+
+    assert_eq!(detect(&runner, "/dev/loop3").method, WipeMethod::Overwrite);
+    assert_eq!(detect(&runner, "/tmp/disk.img").method, WipeMethod::Overwrite);
+    assert_eq!(runner.calls().len(), 2);
+
+    let missing = detect(&runner, "/dev/sdc");
+    assert_eq!(missing.method, WipeMethod::Overwrite);
+    assert!(missing.note.unwrap().contains("hdparm"));
+}
+
+#[test]
+fn parses_sanitize_log() {
+    assert_eq!(
+        parse_sanitize_log(&FixtureRunner::fixture("nvme_sanitize_log_running.stdout")),
+        Ok(SanitizeState::Running { percent: 50 })
+    );
+    assert_eq!(parse_sanitize_log(&FixtureRunner::fixture("nvme_sanitize_log_done.stdout")), Ok(SanitizeState::Done));
+    assert_eq!(parse_sanitize_log(&FixtureRunner::fixture("nvme_sanitize_log_failed.stdout")), Ok(SanitizeState::Failed));
+    assert!(parse_sanitize_log("{}").is_err());
+}
+
+#[test]
+fn sample_offsets_cover_both_ends() {
+    let len = 1 << 30;
+    let offsets = sample_offsets(len, 100, 7);
+    assert_eq!(offsets.len(), 100);
+    assert_eq!(offsets[0], 0);
+    assert_eq!(*offsets.last().unwrap(), len - SAMPLE_SIZE);
+    assert!(offsets.iter().all(|o| o % SAMPLE_SIZE == 0));
+    assert_eq!(offsets, sample_offsets(len, 100, 7));
+
+    // Never more samples than sectors.
+    assert_eq!(sample_offsets(3 * SAMPLE_SIZE, 100, 7).len(), 3);
+    assert!(sample_offsets(100, 10, 7).is_empty());
+}
+
+#[test]
+fn overwrite_wipes_an_image_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = used_image(&dir);
+    let plan = plan_for(&path, WipeMethod::Overwrite, vec![Pattern::Random, Pattern::Zeros]);
+
+    let mut phases = Vec::new();
+    let report = execute(&FixtureRunner::new(), &plan, 64, &mut |p| {
+        phases.push(p.clone());
+        true
+    })
+    .unwrap();
+
+    assert!(report.passed(), "{}", report.verification_text());
+    assert_eq!(report.sampled, 64);
+    assert!(fs::read(&path).unwrap().iter().all(|b| *b == 0));
+    assert!(phases.iter().any(|p| p.phase == "Pass 2/2: zeros"));
+    assert_eq!(phases.last().unwrap().percent, 100);
+    assert!(phases.windows(2).all(|w| w[0].percent <= w[1].percent));
+}
+
+#[test]
+fn random_pass_is_verified_against_its_seed() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = used_image(&dir);
+    let report = run(&FixtureRunner::new(), &plan_for(&path, WipeMethod::Overwrite, vec![Pattern::Random]), 32).unwrap();
+    assert!(report.passed());
+    let data = fs::read(&path).unwrap();
+    assert!(data.iter().any(|b| *b != 0));
+}
+
+#[test]
+fn verify_finds_sectors_left_behind() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = used_image(&dir);
+    let len = fs::metadata(&path).unwrap().len();
+    let mut file = OpenOptions::new().read(true).write(true).open(&path).unwrap();
+    write_pass(&mut file, len, Pattern::Ones, 0, &mut |_| true).unwrap();
+
+    // A sector the write missed, e.g. a remapped one.
+    let offsets = sample_offsets(len, 16, 1);
+    let missed = offsets[5];
+    file.seek(io::SeekFrom::Start(missed)).unwrap();
+    file.write_all(b"customer data").unwrap();
+
+    let mismatches = verify(&mut file, &offsets, &Expected::Pattern(Pattern::Ones, 0), &mut |_| true).unwrap();
+    assert_eq!(mismatches, vec![missed]);
+}
+
+#[test]
+fn cancelling_stops_an_overwrite() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = used_image(&dir);
+    let plan = plan_for(&path, WipeMethod::Overwrite, vec![Pattern::Zeros]);
+    let error = execute(&FixtureRunner::new(), &plan, 8, &mut |p| p.percent < 20).unwrap_err();
+    assert!(error.contains("cancelled"), "{}", error);
+    let data = fs::read(&path).unwrap();
+    assert_eq!(data[0], 0);
+    assert_ne!(*data.last().unwrap(), 0);
+}
+
+#[test]
+fn cancelling_a_firmware_erase_still_reports() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("erased.img");
+    fs::write(&path, vec![0u8; 1 << 20]).unwrap();
+    let device = path.to_string_lossy().into_owned();
+    let runner = FixtureRunner::new()
+        .with(&format!("hdparm --user-master u --security-set-pass wipe {}", device), "hdparm_set_pass")
+        .with(
+            &format!("hdparm --user-master u --security-erase-enhanced wipe {}", device),
+            "hdparm_security_erase",
+        );
+
+    let plan = plan_for(&path, WipeMethod::AtaEnhancedSecureErase, Vec::new());
+    let report = execute(&runner, &plan, 20, &mut |_| false).unwrap();
+    assert!(report.passed());
+    assert_eq!(report.sampled, 20);
+}
+
+#[test]
+fn firmware_erase_that_changed_nothing_fails_verification() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = used_image(&dir);
+    let device = path.to_string_lossy().into_owned();
+    let runner = FixtureRunner::new()
+        .with(&format!("hdparm --user-master u --security-set-pass wipe {}", device), "hdparm_set_pass")
+        .with(
+            &format!("hdparm --user-master u --security-erase-enhanced wipe {}", device),
+            "hdparm_security_erase",
+        );
+
+    // The fixture runner does not touch the image, like a drive that ignored the command.
+    let plan = plan_for(&path, WipeMethod::AtaEnhancedSecureErase, Vec::new());
+    let report = run(&runner, &plan, 20).unwrap();
+    assert!(!report.passed());
+    assert_eq!(report.mismatches.len(), 20);
+    assert_eq!(runner.calls().len(), 2);
+
+    // A drive that reads back zeros afterwards verifies.
+    fs::write(&path, vec![0u8; 1 << 20]).unwrap();
+    let plan = plan_for(&path, WipeMethod::AtaEnhancedSecureErase, Vec::new());
+    assert!(run(&runner, &plan, 20).unwrap().passed());
+}
+
+#[test]
+fn failed_secure_erase_clears_the_password() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = used_image(&dir);
+    let device = path.to_string_lossy().into_owned();
+    let runner = FixtureRunner::new()
+        .with(&format!("hdparm --user-master u --security-set-pass wipe {}", device), "hdparm_set_pass")
+        .with(&format!("hdparm --user-master u --security-erase wipe {}", device), "hdparm_security_erase_failed")
+        .with(&format!("hdparm --user-master u --security-disable wipe {}", device), "hdparm_security_disable");
+
+    let error = run(&runner, &plan_for(&path, WipeMethod::AtaSecureErase, Vec::new()), 4).unwrap_err();
+    assert!(error.contains("Input/output error"), "{}", error);
+    assert!(runner.calls().last().unwrap().contains("--security-disable"));
+}
+
+#[test]
+fn nvme_sanitize_polls_until_done() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("nvme.img");
+    fs::write(&path, vec![0u8; 1 << 20]).unwrap();
+    let device = path.to_string_lossy().into_owned();
+    let runner = FixtureRunner::new()
+        .with(&format!("nvme sanitize {} --sanact=4", device), "nvme_sanitize")
+        .with(&format!("nvme sanitize-log {} -o json", device), "nvme_sanitize_log_done");
+
+    let report = run(&runner, &plan_for(&path, WipeMethod::NvmeSanitizeCrypto, Vec::new()), 10).unwrap();
+    assert!(report.passed());
+    assert_eq!(runner.calls().len(), 2);
+
+    let runner = FixtureRunner::new()
+        .with(&format!("nvme sanitize {} --sanact=4", device), "nvme_sanitize")
+        .with(&format!("nvme sanitize-log {} -o json", device), "nvme_sanitize_log_failed");
+    assert!(run(&runner, &plan_for(&path, WipeMethod::NvmeSanitizeCrypto, Vec::new()), 10).is_err());
+}
+
+#[test]
+fn nvme_sanitize_wait_gives_up() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("nvme.img");
+    fs::write(&path, vec![0u8; 1 << 20]).unwrap();
+    let device = path.to_string_lossy().into_owned();
+    let plan = plan_for(&path, WipeMethod::NvmeSanitizeCrypto, Vec::new());
+    let poll = Duration::from_millis(1);
+    let log = format!("nvme sanitize-log {} -o json", device);
+
+    let idle = FixtureRunner::new().with(&log, "nvme_sanitize_log_idle");
+    let error = wait_for_sanitize(&idle, &plan, poll, SANITIZE_MAX_WAIT, &mut |_| true).unwrap_err();
+    assert!(error.contains("never started"), "{}", error);
+    assert_eq!(idle.calls().len(), SANITIZE_IDLE_POLLS as usize);
+
+    let running = FixtureRunner::new().with(&log, "nvme_sanitize_log_running");
+    let error = wait_for_sanitize(&running, &plan, poll, Duration::from_millis(20), &mut |_| true).unwrap_err();
+    assert!(error.contains("did not finish"), "{}", error);
+
+    let error = wait_for_sanitize(&running, &plan, poll, SANITIZE_MAX_WAIT, &mut |_| false).unwrap_err();
+    assert!(error.contains("Stopped waiting"), "{}", error);
+    assert_eq!(
+        parse_sanitize_estimate(&FixtureRunner::fixture("nvme_sanitize_log_running.stdout")),
+        Some(Duration::from_secs(60))
+    );
+    assert_eq!(parse_sanitize_estimate(&FixtureRunner::fixture("nvme_sanitize_log_idle.stdout")), None);
+}
+
+#[test]
+fn wipe_result_records_verification() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = used_image(&dir);
+    let mut plan = plan_for(&path, WipeMethod::Overwrite, vec![Pattern::Zeros]);
+    plan.identity.serial = Some("ZFL1ABCD".to_string());
+    assert_eq!(plan.confirmation(), "ZFL1ABCD");
+
+    let report = run(&FixtureRunner::new(), &plan, 16).unwrap();
+    let result = wipe_result(&report);
+    assert_eq!(result.test, "Wipe");
+    assert_eq!(result.status, TestStatus::Pass);
+    assert_eq!(result.metrics["method"], "Overwrite");
+    assert_eq!(result.metrics["nist_level"], "Clear");
+    assert_eq!(result.metrics["mismatches"], "0");
+    assert_eq!(result.metrics["serial"], "ZFL1ABCD");
+    let stored: WipeReport = serde_json::from_str(&result.raw_output).unwrap();
+    assert_eq!(stored.sampled, 16);
+
+    let mut bad = report.clone();
+    bad.mismatches = vec![4096];
+    let result = wipe_result(&bad);
+    assert_eq!(result.status, TestStatus::Fail);
+    assert_eq!(result.metrics["first_mismatch"], "4096");
+}
+
+#[test]
+fn confirmation_falls_back_to_the_device_name() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = used_image(&dir);
+    let plan = WipePlan::new(&FixtureRunner::new(), &path.to_string_lossy(), &WipeConfig::default()).unwrap();
+    assert_eq!(plan.method, WipeMethod::Overwrite);
+    assert_eq!(plan.passes, vec![Pattern::Zeros]);
+    assert_eq!(plan.size_bytes, (3 << 20) + 8192);
+    assert_eq!(plan.confirmation(), "disk.img");
+}
+
+fn wiped_report(dir: &tempfile::TempDir) -> WipeReport {
+    let path = used_image(dir);
+    run(&FixtureRunner::new(), &plan_for(&path, WipeMethod::Overwrite, vec![Pattern::Ones, Pattern::Zeros]), 16)
+        .unwrap()
+}
+
+fn smart_fixture(name: &str) -> crate::smart::SmartData {
+    crate::smart::SmartData::parse(&FixtureRunner::fixture(name)).unwrap()
+}
+
+#[test]
+fn certificate_takes_identity_from_smart() {
+    let dir = tempfile::tempdir().unwrap();
+    let report = wiped_report(&dir);
+    let smart = smart_fixture("smartctl_sata_hdd.stdout");
+    let certificate = Certificate::issue(&report, Some(&smart), "alice", "2026-10-18T10:00:00+00:00");
+
+    assert_eq!(certificate.model, "ST2000DM008-2FR102");
+    assert_eq!(certificate.serial, "ZFL1ABCD");
+    assert_eq!(certificate.operator, "alice");
+    assert_eq!(certificate.capacity_bytes, (3 << 20) + 8192);
+    assert_eq!(certificate.passes, 2);
+    assert_eq!(certificate.patterns, vec![Pattern::Ones, Pattern::Zeros]);
+    assert_eq!(certificate.nist_level, "Clear");
+    assert!(certificate.verified);
+    assert!(certificate.smart_power_on_hours.is_some());
+
+    let anonymous = Certificate::issue(&report, None, "alice", "2026-10-18T10:00:00+00:00");
+    assert_eq!(anonymous.serial, "Unknown");
+}
+
+#[test]
+fn certificate_id_is_stable_and_hash_detects_tampering() {
+    let dir = tempfile::tempdir().unwrap();
+    let report = wiped_report(&dir);
+    let smart = smart_fixture("smartctl_sata_hdd.stdout");
+    let first = Certificate::issue(&report, Some(&smart), "alice", "2026-10-18T10:00:00+00:00");
+    let again = Certificate::issue(&report, Some(&smart), "bob", "2026-10-19T09:00:00+00:00");
+    assert_eq!(first.id, again.id);
+    assert!(first.id.starts_with(&format!("WC-{}-", report.started_at.format("%Y%m%d-%H%M%S"))));
+    assert_ne!(first.content_hash, again.content_hash);
+
+    assert!(first.verify());
+    let mut forged = first.clone();
+    forged.verified = false;
+    forged.serial = "OTHER".to_string();
+    assert!(!forged.verify());
+}
+
+#[test]
+fn certificate_is_saved_as_html_and_json() {
+    let dir = tempfile::tempdir().unwrap();
+    let report = wiped_report(&dir);
+    let certificate = Certificate::issue(&report, None, "<alice>", "2026-10-18T10:00:00+00:00");
+
+    let (html, json) = certificate::save(&certificate, &dir.path().join("certs")).unwrap();
+    assert_eq!(html.file_name().unwrap().to_string_lossy(), format!("{}.html", certificate.id));
+    let page = fs::read_to_string(&html).unwrap();
+    assert!(page.contains(&certificate.content_hash));
+    assert!(page.contains("&lt;alice&gt;"));
+    assert!(page.contains("VERIFIED"));
+
+    let loaded = certificate::load(&json).unwrap();
+    assert_eq!(loaded, certificate);
+    assert!(loaded.verify());
+}
@@ -0,0 +1,627 @@
+use chrono::{DateTime, Local};
+use once_cell::sync::Lazy;
+use ratatui::{
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span, Text},
+    widgets::{List, ListItem, ListState, Paragraph, Row, Table, Wrap},
+    Frame,
+};
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::app::{Screen, Transition};
+use crate::audio_test;
+use crate::command::SystemRunner;
+use crate::config;
+use crate::gpu_detect::detect_gpu_type;
+use crate::gpu_test::{self, TestMode};
+use crate::keyboard_test;
+use crate::photo_exporter;
+use crate::preflight::{self, Feature};
+use crate::session::{self, TestResult, TestStatus};
+use crate::smart;
+use crate::theme::{bordered_block, highlight_style, info_box};
+
+/// Profiles shipped with the app; files in the workflow directory can add more
+/// or replace these by using the same name.
+const BUILTIN_PROFILES: &[&str] = &[
+    include_str!("../../assets/workflows/laptop.toml"),
+    include_str!("../../assets/workflows/desktop_tower.toml"),
+    include_str!("../../assets/workflows/graphics_card.toml"),
+    include_str!("../../assets/workflows/loose_drive.toml"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StepKind {
+    Smart,
+    Keyboard,
+    Audio,
+    GpuStress,
+    GpuStability,
+    PhotoExport,
+}
+
+impl StepKind {
+    pub fn label(&self) -> &'static str {
+        match self {
+            StepKind::Smart => "SMART (all drives)",
+            StepKind::Keyboard => "Keyboard",
+            StepKind::Audio => "Audio",
+            StepKind::GpuStress => "GPU stress",
+            StepKind::GpuStability => "GPU stability",
+            StepKind::PhotoExport => "Photo export",
+        }
+    }
+
+    /// Session test name the step's results are recorded under.
+    pub fn test_name(&self) -> &'static str {
+        match self {
+            StepKind::Smart => "SMART",
+            StepKind::Keyboard => "Keyboard",
+            StepKind::Audio => "Audio",
+            StepKind::GpuStress => "GPU stress",
+            StepKind::GpuStability => "GPU stability",
+            StepKind::PhotoExport => "Photo export",
+        }
+    }
+
+    /// Manual tests need the operator at the bench, so the workflow waits for them.
+    pub fn is_manual(&self) -> bool {
+        matches!(self, StepKind::Keyboard | StepKind::Audio)
+    }
+
+    fn feature(&self) -> Option<Feature> {
+        match self {
+            StepKind::Smart => Some(Feature::Smart),
+            StepKind::Keyboard => Some(Feature::KeyboardTest),
+            StepKind::Audio => None,
+            StepKind::GpuStress | StepKind::GpuStability => Some(Feature::GpuTest),
+            StepKind::PhotoExport => Some(Feature::PhotoExport),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Step {
+    pub test: StepKind,
+    /// Shown while the workflow waits for the operator before this step.
+    #[serde(default)]
+    pub prompt: Option<String>,
+    /// Wait for Enter before starting; defaults to true for manual tests and
+    /// for steps with a prompt.
+    #[serde(default)]
+    pub confirm: Option<bool>,
+}
+
+impl Step {
+    pub fn needs_confirmation(&self) -> bool {
+        self.confirm
+            .unwrap_or(self.test.is_manual() || self.prompt.is_some())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub steps: Vec<Step>,
+}
+
+pub fn parse_profile(text: &str) -> Result<Profile, String> {
+    let profile: Profile = toml::from_str(text).map_err(|e| e.to_string())?;
+    if profile.steps.is_empty() {
+        return Err(format!("workflow \"{}\" has no steps", profile.name));
+    }
+    Ok(profile)
+}
+
+/// `$XDG_CONFIG_HOME/electronics_tui_tester/workflows`, unless the config says otherwise.
+pub fn profile_dir() -> Option<PathBuf> {
+    config::get().workflows.dir.clone().or_else(|| {
+        dirs::config_dir().map(|dir| dir.join("electronics_tui_tester").join("workflows"))
+    })
+}
+
+/// Built-in profiles plus every `*.toml` profile in `dir`. Broken files are
+/// logged and skipped so one typo does not hide the other profiles.
+pub fn load_profiles(dir: Option<&Path>) -> Vec<Profile> {
+    let mut profiles: Vec<Profile> = BUILTIN_PROFILES
+        .iter()
+        .map(|text| parse_profile(text).expect("built-in workflow profile is valid"))
+        .collect();
+
+    let Some(dir) = dir else { return profiles };
+    let Ok(entries) = fs::read_dir(dir) else { return profiles };
+
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|ext| ext == "toml"))
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        let profile = fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| parse_profile(&text));
+        match profile {
+            Ok(profile) => match profiles.iter_mut().find(|p| p.name == profile.name) {
+                Some(existing) => *existing = profile,
+                None => profiles.push(profile),
+            },
+            Err(e) => log::warn!("Skipping workflow {}: {}", path.display(), e),
+        }
+    }
+    profiles
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StepState {
+    Pending,
+    /// Waiting for the operator to press Enter.
+    Waiting,
+    Running,
+    Done(TestStatus),
+}
+
+impl StepState {
+    fn label(&self) -> (&'static str, Color) {
+        match self {
+            StepState::Pending => ("pending", Color::Gray),
+            StepState::Waiting => ("waiting", Color::Yellow),
+            StepState::Running => ("running", Color::Cyan),
+            StepState::Done(status) => (status.label(), status.color()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct StepRun {
+    state: StepState,
+    started_at: Option<DateTime<Local>>,
+    /// Results the step must record before it counts as done (one per drive for SMART).
+    expected: usize,
+    note: String,
+}
+
+pub struct WorkflowRun {
+    profile: Profile,
+    steps: Vec<StepRun>,
+    current: usize,
+    started_at: DateTime<Local>,
+    finished: bool,
+}
+
+/// Worst status across the recorded results of one test since `since`, once
+/// at least `expected` of them are in.
+pub fn step_status(results: &[TestResult], test: &str, since: DateTime<Local>, expected: usize) -> Option<TestStatus> {
+    let statuses: Vec<TestStatus> = results
+        .iter()
+        .filter(|r| r.test == test && r.finished_at >= since)
+        .map(|r| r.status)
+        .collect();
+    if statuses.is_empty() || statuses.len() < expected {
+        return None;
+    }
+    Some(worst(&statuses))
+}
+
+fn worst(statuses: &[TestStatus]) -> TestStatus {
+    [TestStatus::Fail, TestStatus::Skipped, TestStatus::Warn]
+        .into_iter()
+        .find(|s| statuses.contains(s))
+        .unwrap_or(TestStatus::Pass)
+}
+
+/// One outcome for the whole unit: any failure fails it, and anything
+/// skipped or not reached leaves it at a warning.
+pub fn combined_status(states: &[StepState]) -> TestStatus {
+    let statuses: Vec<TestStatus> = states
+        .iter()
+        .map(|state| match state {
+            StepState::Done(TestStatus::Skipped) => TestStatus::Warn,
+            StepState::Done(status) => *status,
+            _ => TestStatus::Warn,
+        })
+        .collect();
+    if statuses.is_empty() {
+        return TestStatus::Skipped;
+    }
+    worst(&statuses)
+}
+
+impl WorkflowRun {
+    fn new(profile: Profile) -> Self {
+        let steps = profile
+            .steps
+            .iter()
+            .map(|_| StepRun {
+                state: StepState::Pending,
+                started_at: None,
+                expected: 1,
+                note: String::new(),
+            })
+            .collect();
+        Self {
+            profile,
+            steps,
+            current: 0,
+            started_at: Local::now(),
+            finished: false,
+        }
+    }
+
+    fn states(&self) -> Vec<StepState> {
+        self.steps.iter().map(|s| s.state).collect()
+    }
+
+    /// Moves to the next step that has not run yet, starting it unless it
+    /// needs the operator first.
+    fn advance(&mut self) {
+        if self.current >= self.steps.len() {
+            self.finish(false);
+            return;
+        }
+        if self.steps[self.current].state == StepState::Pending {
+            if self.profile.steps[self.current].needs_confirmation() {
+                self.steps[self.current].state = StepState::Waiting;
+            } else {
+                self.launch();
+            }
+        }
+    }
+
+    fn launch(&mut self) {
+        let kind = self.profile.steps[self.current].test;
+        let step = &mut self.steps[self.current];
+        step.started_at = Some(Local::now());
+        step.note.clear();
+
+        match start_step(kind) {
+            Ok(expected) => {
+                log::info!("Workflow {}: started {}", self.profile.name, kind.label());
+                step.expected = expected;
+                step.state = StepState::Running;
+            }
+            Err(reason) => {
+                log::warn!("Workflow {}: cannot run {}: {}", self.profile.name, kind.label(), reason);
+                step.note = reason;
+                step.state = StepState::Done(TestStatus::Skipped);
+            }
+        }
+    }
+
+    /// Picks up finished steps from the session and moves on after a pass.
+    fn poll(&mut self, results: &[TestResult]) {
+        if self.finished || self.current >= self.steps.len() {
+            return;
+        }
+        let kind = self.profile.steps[self.current].test;
+        let step = &mut self.steps[self.current];
+        if step.state != StepState::Running {
+            return;
+        }
+        let since = step.started_at.unwrap_or(self.started_at);
+        if let Some(status) = step_status(results, kind.test_name(), since, step.expected) {
+            log::info!("Workflow {}: {} {}", self.profile.name, kind.label(), status.label());
+            step.state = StepState::Done(status);
+            if status == TestStatus::Pass {
+                self.current += 1;
+                self.advance();
+            }
+        }
+    }
+
+    fn finish(&mut self, aborted: bool) {
+        if self.finished {
+            return;
+        }
+        self.finished = true;
+        let outcome = combined_status(&self.states());
+        let steps = self
+            .profile
+            .steps
+            .iter()
+            .zip(&self.steps)
+            .map(|(step, run)| format!("{}={}", step.test.label(), run.state.label().0))
+            .collect::<Vec<_>>()
+            .join(", ");
+        log::info!("Workflow {} finished: {}", self.profile.name, outcome.label());
+
+        session::record(
+            TestResult::new("Workflow", &self.profile.name)
+                .started(self.started_at)
+                .status(outcome)
+                .metric("steps", steps)
+                .metric("aborted", if aborted { "yes" } else { "no" })
+                .finish(),
+        );
+    }
+}
+
+/// Starts the test behind a step and returns how many results it will record.
+fn start_step(kind: StepKind) -> Result<usize, String> {
+    if let Some(feature) = kind.feature() {
+        let missing = preflight::missing_tools(feature);
+        if !missing.is_empty() {
+            return Err(format!("{} not installed", missing.join(", ")));
+        }
+    }
+
+    match kind {
+        StepKind::Smart => smart::start_smart_all_drives(),
+        StepKind::Keyboard => {
+            keyboard_test::enter_keyboard_test();
+            Ok(1)
+        }
+        StepKind::Audio => {
+            audio_test::enter_audio_test();
+            Ok(1)
+        }
+        StepKind::GpuStress | StepKind::GpuStability => {
+            gpu_test::enter_gpu_test(&format!("{:?}", detect_gpu_type(&SystemRunner)));
+            gpu_test::run_test(if kind == StepKind::GpuStress {
+                TestMode::Stress
+            } else {
+                TestMode::Stability
+            });
+            Ok(1)
+        }
+        StepKind::PhotoExport => {
+            photo_exporter::run_photo_exporter();
+            Ok(1)
+        }
+    }
+}
+
+/// The screen a manual step runs on.
+fn step_screen(kind: StepKind) -> Option<Screen> {
+    match kind {
+        StepKind::Keyboard => Some(Screen::KeyboardTest),
+        StepKind::Audio => Some(Screen::AudioTest),
+        _ => None,
+    }
+}
+
+static PROFILES: Lazy<Mutex<Vec<Profile>>> = Lazy::new(|| Mutex::new(Vec::new()));
+static PROFILE_INDEX: Lazy<Mutex<usize>> = Lazy::new(|| Mutex::new(0));
+static RUN: Lazy<Mutex<Option<WorkflowRun>>> = Lazy::new(|| Mutex::new(None));
+
+pub fn is_active() -> bool {
+    RUN.lock().unwrap().as_ref().is_some_and(|run| !run.finished)
+}
+
+pub fn enter_workflow_select() {
+    *PROFILES.lock().unwrap() = load_profiles(profile_dir().as_deref());
+    *PROFILE_INDEX.lock().unwrap() = 0;
+}
+
+pub fn previous_profile() {
+    let mut index = PROFILE_INDEX.lock().unwrap();
+    *index = index.saturating_sub(1);
+}
+
+pub fn next_profile() {
+    let count = PROFILES.lock().unwrap().len();
+    let mut index = PROFILE_INDEX.lock().unwrap();
+    if *index < count.saturating_sub(1) {
+        *index += 1;
+    }
+}
+
+pub fn start_selected_profile() -> Transition {
+    let index = *PROFILE_INDEX.lock().unwrap();
+    let Some(profile) = PROFILES.lock().unwrap().get(index).cloned() else {
+        return Transition::Stay;
+    };
+    log::info!("Workflow {} started", profile.name);
+    let mut run = WorkflowRun::new(profile);
+    run.advance();
+    *RUN.lock().unwrap() = Some(run);
+    Transition::Push(Screen::Workflow)
+}
+
+/// Called every UI tick so steps advance even while another screen is shown.
+pub fn tick() {
+    let mut guard = RUN.lock().unwrap();
+    if let Some(run) = guard.as_mut().filter(|run| !run.finished) {
+        run.poll(&session::current().results);
+    }
+}
+
+/// Enter: start a waiting step, or accept a step that did not pass and go on.
+pub fn confirm() -> Transition {
+    let mut guard = RUN.lock().unwrap();
+    let Some(run) = guard.as_mut().filter(|run| !run.finished) else {
+        return Transition::Stay;
+    };
+    let kind = run.profile.steps[run.current].test;
+    match run.steps[run.current].state {
+        StepState::Waiting => {
+            run.launch();
+            match (run.steps[run.current].state, step_screen(kind)) {
+                (StepState::Running, Some(screen)) => Transition::Push(screen),
+                _ => Transition::Stay,
+            }
+        }
+        StepState::Done(_) => {
+            run.current += 1;
+            run.advance();
+            Transition::Stay
+        }
+        StepState::Running => step_screen(kind).map_or(Transition::Stay, Transition::Push),
+        StepState::Pending => Transition::Stay,
+    }
+}
+
+pub fn retry() -> Transition {
+    let mut guard = RUN.lock().unwrap();
+    let Some(run) = guard.as_mut().filter(|run| !run.finished) else {
+        return Transition::Stay;
+    };
+    if !matches!(run.steps[run.current].state, StepState::Done(_)) {
+        return Transition::Stay;
+    }
+    run.launch();
+    let kind = run.profile.steps[run.current].test;
+    match (run.steps[run.current].state, step_screen(kind)) {
+        (StepState::Running, Some(screen)) => Transition::Push(screen),
+        _ => Transition::Stay,
+    }
+}
+
+pub fn skip() {
+    let mut guard = RUN.lock().unwrap();
+    if let Some(run) = guard.as_mut().filter(|run| !run.finished) {
+        let step = &mut run.steps[run.current];
+        if !matches!(step.state, StepState::Done(_)) {
+            step.state = StepState::Done(TestStatus::Skipped);
+            step.note = "Skipped by operator".to_string();
+        }
+        run.current += 1;
+        run.advance();
+    }
+}
+
+pub fn abort() {
+    if let Some(run) = RUN.lock().unwrap().as_mut() {
+        run.finish(true);
+    }
+}
+
+pub fn draw_workflow_select(f: &mut Frame) {
+    let profiles = PROFILES.lock().unwrap();
+    let selected = *PROFILE_INDEX.lock().unwrap();
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([Constraint::Min(3), Constraint::Length(5)])
+        .split(f.area());
+
+    let items: Vec<ListItem> = profiles
+        .iter()
+        .map(|p| {
+            let steps: Vec<&str> = p.steps.iter().map(|s| s.test.label()).collect();
+            ListItem::new(Text::from(vec![
+                Line::from(Span::styled(p.name.clone(), Style::default().add_modifier(Modifier::BOLD))),
+                Line::raw(format!("  {}", steps.join(" → "))),
+            ]))
+        })
+        .collect();
+
+    let mut state = ListState::default();
+    state.select(Some(selected));
+    let list = List::new(items)
+        .block(bordered_block("Select Workflow"))
+        .highlight_style(highlight_style())
+        .highlight_symbol("▶ ");
+
+    let description = profiles
+        .get(selected)
+        .map(|p| p.description.clone())
+        .unwrap_or_default();
+    let dir = profile_dir()
+        .map(|d| d.display().to_string())
+        .unwrap_or_else(|| "none".to_string());
+    let info = Paragraph::new(Text::from(vec![
+        Line::raw(description),
+        info_box("Custom profiles", &dir),
+        Line::raw("Enter to start, Esc to go back."),
+    ]))
+    .block(bordered_block("Details"))
+    .wrap(Wrap { trim: true });
+
+    f.render_stateful_widget(list, chunks[0], &mut state);
+    f.render_widget(info, chunks[1]);
+}
+
+pub fn draw_workflow(f: &mut Frame) {
+    let guard = RUN.lock().unwrap();
+    let Some(run) = guard.as_ref() else {
+        f.render_widget(Paragraph::new("No workflow running.").block(bordered_block("Workflow")), f.area());
+        return;
+    };
+
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .margin(1)
+        .constraints([Constraint::Length(4), Constraint::Min(3), Constraint::Length(4)])
+        .split(f.area());
+
+    let outcome = combined_status(&run.states());
+    let header = Paragraph::new(Text::from(vec![
+        info_box("Profile", &run.profile.name),
+        Line::from(vec![
+            Span::raw(if run.finished { "Outcome: " } else { "Outcome so far: " }),
+            Span::styled(outcome.label(), Style::default().fg(outcome.color()).add_modifier(Modifier::BOLD)),
+        ]),
+    ]))
+    .block(bordered_block("Workflow"));
+
+    let rows: Vec<Row> = run
+        .profile
+        .steps
+        .iter()
+        .zip(&run.steps)
+        .enumerate()
+        .map(|(i, (step, state))| {
+            let (label, color) = state.state.label();
+            let marker = if i == run.current && !run.finished { "▶" } else { " " };
+            Row::new(vec![
+                Span::raw(format!("{} {}", marker, i + 1)),
+                Span::raw(step.test.label()),
+                Span::styled(label, Style::default().fg(color)),
+                Span::raw(state.note.clone()),
+            ])
+        })
+        .collect();
+    let table = Table::new(
+        rows,
+        [Constraint::Length(5), Constraint::Length(20), Constraint::Length(9), Constraint::Min(10)],
+    )
+    .header(
+        Row::new(vec!["#", "Step", "State", "Notes"])
+            .style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD)),
+    )
+    .block(bordered_block("Steps"));
+
+    let instructions = if run.finished {
+        format!("Workflow finished: {}. Esc to go back.", outcome.label())
+    } else {
+        let step = &run.profile.steps[run.current];
+        match run.steps[run.current].state {
+            StepState::Waiting => step
+                .prompt
+                .clone()
+                .unwrap_or_else(|| format!("Press Enter to start the {} test.", step.test.label())),
+            StepState::Running if step_screen(step.test).is_some() => {
+                format!("{} is in progress. Press Enter to return to it.", step.test.label())
+            }
+            StepState::Running => format!("Running {}...", step.test.label()),
+            StepState::Done(status) => format!(
+                "{} finished with {}. Enter: continue anyway, r: retry, s: skip.",
+                step.test.label(),
+                status.label()
+            ),
+            StepState::Pending => String::new(),
+        }
+    };
+    let help = Paragraph::new(Text::from(vec![
+        Line::raw(instructions),
+        Line::raw("a: abort workflow   j: jobs   Esc: leave running in the background"),
+    ]))
+    .block(bordered_block("Next"))
+    .wrap(Wrap { trim: true });
+
+    f.render_widget(header, chunks[0]);
+    f.render_widget(table, chunks[1]);
+    f.render_widget(help, chunks[2]);
+}
+
+#[cfg(test)]
+mod tests;
@@ -0,0 +1,74 @@
+use super::*;
+use chrono::Duration;
+
+fn result(test: &str, device: &str, status: TestStatus) -> TestResult {
+    TestResult::new(test, device).status(status).finish()
+}
+
+#[test]
+fn built_in_profiles_parse() {
+    let profiles = load_profiles(None);
+    let names: Vec<&str> = profiles.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, vec!["Laptop", "Desktop tower", "Graphics card", "Loose drive"]);
+
+    let laptop = &profiles[0];
+    assert_eq!(laptop.steps[0].test, StepKind::Smart);
+    assert!(!laptop.steps[0].needs_confirmation());
+    assert!(laptop.steps[1].needs_confirmation());
+    assert!(laptop.steps[4].prompt.is_some());
+}
+
+#[test]
+fn user_profiles_add_and_replace() {
+    let dir = tempfile::tempdir().unwrap();
+    fs::write(
+        dir.path().join("laptop.toml"),
+        "name = \"Laptop\"\n[[steps]]\ntest = \"keyboard\"\nconfirm = false\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.path().join("tablet.toml"),
+        "name = \"Tablet\"\n[[steps]]\ntest = \"audio\"\n",
+    )
+    .unwrap();
+    fs::write(dir.path().join("broken.toml"), "name = \"Broken\"\n[[steps]]\ntest = \"fax\"\n").unwrap();
+    fs::write(dir.path().join("empty.toml"), "name = \"Empty\"\nsteps = []\n").unwrap();
+
+    let profiles = load_profiles(Some(dir.path()));
+    let names: Vec<&str> = profiles.iter().map(|p| p.name.as_str()).collect();
+    assert_eq!(names, vec!["Laptop", "Desktop tower", "Graphics card", "Loose drive", "Tablet"]);
+
+    let laptop = &profiles[0];
+    assert_eq!(laptop.steps.len(), 1);
+    assert!(!laptop.steps[0].needs_confirmation());
+}
+
+#[test]
+fn step_waits_for_every_expected_result() {
+    let since = Local::now() - Duration::seconds(5);
+    let mut results = vec![
+        result("SMART", "/dev/sda", TestStatus::Pass),
+        result("Keyboard", "kbd", TestStatus::Fail),
+    ];
+    assert_eq!(step_status(&results, "SMART", since, 2), None);
+
+    results.push(result("SMART", "/dev/sdb", TestStatus::Warn));
+    assert_eq!(step_status(&results, "SMART", since, 2), Some(TestStatus::Warn));
+    assert_eq!(step_status(&results, "Audio", since, 1), None);
+}
+
+#[test]
+fn ignores_results_from_before_the_step() {
+    let results = vec![result("Audio", "Speakers", TestStatus::Fail)];
+    let since = Local::now() + Duration::seconds(5);
+    assert_eq!(step_status(&results, "Audio", since, 1), None);
+}
+
+#[test]
+fn combines_step_outcomes() {
+    use StepState::*;
+    assert_eq!(combined_status(&[Done(TestStatus::Pass), Done(TestStatus::Pass)]), TestStatus::Pass);
+    assert_eq!(combined_status(&[Done(TestStatus::Pass), Done(TestStatus::Skipped)]), TestStatus::Warn);
+    assert_eq!(combined_status(&[Done(TestStatus::Pass), Pending]), TestStatus::Warn);
+    assert_eq!(combined_status(&[Done(TestStatus::Fail), Done(TestStatus::Warn)]), TestStatus::Fail);
+}